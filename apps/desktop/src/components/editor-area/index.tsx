@@ -7,11 +7,13 @@ import { useCallback, useEffect, useMemo, useRef, useState } from "react";
 import { z } from "zod";
 
 import { useHypr } from "@/contexts";
+import { extractFollowupsForSession } from "@/utils/followup-extraction";
 import { extractTextFromHtml } from "@/utils/parse";
 import { TemplateService } from "@/utils/template-service";
 import { commands as analyticsCommands } from "@hypr/plugin-analytics";
 import { commands as connectorCommands } from "@hypr/plugin-connector";
 import { commands as dbCommands } from "@hypr/plugin-db";
+import { type ModelTask } from "@hypr/plugin-local-llm";
 import { commands as miscCommands } from "@hypr/plugin-misc";
 import { commands as templateCommands, type Grammar } from "@hypr/plugin-template";
 import Editor, { type TiptapEditor } from "@hypr/tiptap/editor";
@@ -25,6 +27,9 @@ import { enhanceFailedToast } from "../toast/shared";
 import { FloatingButton } from "./floating-button";
 import { NoteHeader } from "./note-header";
 
+// The local model takes up to 16k tokens of prompt. This leaves room for the instructions and the raw note.
+const LOCAL_LLM_TRANSCRIPT_TOKENS = 8 * 1024;
+
 async function generateTitleDirect(enhancedContent: string, targetSessionId: string, sessions: Record<string, any>) {
   const [config, { type }, provider] = await Promise.all([
     dbCommands.getConfig(),
@@ -48,7 +53,7 @@ async function generateTitleDirect(enhancedContent: string, targetSessionId: str
       { role: "user", content: userMessage },
     ],
     providerOptions: {
-      [localProviderName]: { metadata: { grammar: "title" } },
+      [localProviderName]: { metadata: { task: "title" satisfies ModelTask, grammar: "title" } },
     },
   });
 
@@ -115,6 +120,7 @@ export default function EditorArea({
     onSuccess: (content) => {
       if (hasTranscriptWords) {
         generateTitleDirect(content, sessionId, sessionsStore).catch(console.error);
+        extractFollowupsForSession(sessionId);
       }
     },
   });
@@ -382,23 +388,71 @@ export function useEnhanceMutation({
         },
       );
 
-      const userMessage = await templateCommands.render(
-        "enhance.user",
-        {
-          type,
-          editor: finalInput,
-          words: JSON.stringify(words),
-          participants,
-        },
-      );
-
-      const abortSignal = AbortSignal.any([abortController.signal, AbortSignal.timeout(120 * 1000)]);
-
       const provider = await modelProvider();
       const model = sessionId === onboardingSessionId
         ? provider.languageModel("onboardingModel")
         : provider.languageModel("defaultModel");
 
+      // Long meetings don't fit in the local model's context. Write notes for each part, then merge them.
+      const timelineWindows = freshIsLocalLlm
+        ? await templateCommands.splitTimeline(words, LOCAL_LLM_TRANSCRIPT_TOKENS)
+        : [words];
+
+      let userMessage: string;
+
+      if (timelineWindows.length > 1) {
+        const partialNotes: string[] = [];
+
+        for (const [index, windowWords] of timelineWindows.entries()) {
+          const partialMessage = await templateCommands.render(
+            "enhance_partial.user",
+            {
+              type,
+              words: JSON.stringify(windowWords),
+              participants,
+              part: index + 1,
+              total_parts: timelineWindows.length,
+            },
+          );
+
+          const { text } = await generateText({
+            abortSignal: AbortSignal.any([abortController.signal, AbortSignal.timeout(120 * 1000)]),
+            model,
+            messages: [
+              { role: "system", content: systemMessage },
+              { role: "user", content: partialMessage },
+            ],
+            providerOptions: {
+              [localProviderName]: { metadata: { task: "enhance" satisfies ModelTask } },
+            },
+          });
+
+          partialNotes.push(text);
+        }
+
+        userMessage = await templateCommands.render(
+          "enhance_merge.user",
+          {
+            type,
+            editor: finalInput,
+            participants,
+            partial_notes: partialNotes,
+          },
+        );
+      } else {
+        userMessage = await templateCommands.render(
+          "enhance.user",
+          {
+            type,
+            editor: finalInput,
+            words: JSON.stringify(words),
+            participants,
+          },
+        );
+      }
+
+      const abortSignal = AbortSignal.any([abortController.signal, AbortSignal.timeout(120 * 1000)]);
+
       if (sessionId !== onboardingSessionId) {
         analyticsCommands.event({
           event: "normal_enhance_start",
@@ -444,6 +498,7 @@ export function useEnhanceMutation({
           providerOptions: {
             [localProviderName]: {
               metadata: {
+                task: "enhance" satisfies ModelTask,
                 grammar: {
                   task: "enhance",
                   sections: grammarSections,
@@ -5,6 +5,7 @@ import { useLicense } from "@/hooks/use-license";
 import { commands as analyticsCommands } from "@hypr/plugin-analytics";
 import { commands as connectorCommands } from "@hypr/plugin-connector";
 import { commands as dbCommands } from "@hypr/plugin-db";
+import { commands as localLlmCommands } from "@hypr/plugin-local-llm";
 import { commands as miscCommands } from "@hypr/plugin-misc";
 import { commands as templateCommands } from "@hypr/plugin-template";
 import { modelProvider, stepCountIs, streamText, tool } from "@hypr/utils/ai";
@@ -102,6 +103,14 @@ export function useChatLogic({
       }`
       : "";
 
+    // Best-effort: the index may still be building.
+    const retrievalEnabled = await localLlmCommands.isRetrievalEnabled().catch(() => false);
+    const related = currentUserMessage && retrievalEnabled
+      ? await localLlmCommands.retrieveSessionChunks(currentUserMessage, 5)
+        .then((chunks) => chunks.filter((chunk) => chunk.session_id !== sessionId))
+        .catch(() => [])
+      : [];
+
     const systemContent = await templateCommands.render("ai_chat.system", {
       session: freshSessionData,
       words: JSON.stringify(freshSessionData?.words || []),
@@ -113,6 +122,7 @@ export function useChatLogic({
       date: currentDateTime,
       participants: participants,
       event: eventInfo,
+      related: related,
     });
 
     const conversationHistory: Array<{
@@ -306,12 +316,68 @@ export function useChatLogic({
 
       const { type } = await connectorCommands.getLlmConnection();
 
+      const searchSessionsTool = tool({
+        description:
+          "Search for sessions (meeting notes) with multiple keywords. The keywords should be the most important things that the user is talking about. This could be either topics, people, or company names.",
+        inputSchema: z.object({
+          keywords: z.array(z.string()).min(3).max(5).describe(
+            "List of 3-5 keywords to search for, each keyword should be concise",
+          ),
+        }),
+        execute: async ({ keywords }) => {
+          const searchPromises = keywords.map(keyword =>
+            dbCommands.listSessions({
+              type: "search",
+              query: keyword,
+              user_id: userId || "",
+              limit: 3,
+            })
+          );
+
+          const searchResults = await Promise.all(searchPromises);
+
+          const combinedResults = new Map();
+
+          searchResults.forEach((sessions, index) => {
+            const keyword = keywords[index];
+            sessions.forEach(session => {
+              if (combinedResults.has(session.id)) {
+                combinedResults.get(session.id).matchedKeywords.push(keyword);
+              } else {
+                combinedResults.set(session.id, {
+                  ...session,
+                  matchedKeywords: [keyword],
+                });
+              }
+            });
+          });
+
+          const finalResults = Array.from(combinedResults.values())
+            .sort((a, b) => b.matchedKeywords.length - a.matchedKeywords.length);
+
+          return {
+            results: finalResults,
+            summary: {
+              totalSessions: finalResults.length,
+              keywordsSearched: keywords,
+              sessionsByKeywordCount: finalResults.reduce((acc, session) => {
+                const count = session.matchedKeywords.length;
+                acc[count] = (acc[count] || 0) + 1;
+                return acc;
+              }, {} as Record<number, number>),
+            },
+          };
+        },
+      });
+
       const { textStream } = streamText({
         model,
         messages: await prepareMessageHistory(messages, content, mentionedContent),
         ...(type === "HyprLocal" && {
+          stopWhen: stepCountIs(3),
           tools: {
             update_progress: tool({ inputSchema: z.any() }),
+            search_sessions_multi_keywords: searchSessionsTool,
           },
         }),
         ...((type !== "HyprLocal"
@@ -319,62 +385,9 @@ export function useChatLogic({
             || model.modelId === "anthropic/claude-4-sonnet")) && {
           stopWhen: stepCountIs(3),
           tools: {
-            search_sessions_multi_keywords: tool({
-              description:
-                "Search for sessions (meeting notes) with multiple keywords. The keywords should be the most important things that the user is talking about. This could be either topics, people, or company names.",
-              inputSchema: z.object({
-                keywords: z.array(z.string()).min(3).max(5).describe(
-                  "List of 3-5 keywords to search for, each keyword should be concise",
-                ),
-              }),
-              execute: async ({ keywords }) => {
-                const searchPromises = keywords.map(keyword =>
-                  dbCommands.listSessions({
-                    type: "search",
-                    query: keyword,
-                    user_id: userId || "",
-                    limit: 3,
-                  })
-                );
-
-                const searchResults = await Promise.all(searchPromises);
-
-                const combinedResults = new Map();
-
-                searchResults.forEach((sessions, index) => {
-                  const keyword = keywords[index];
-                  sessions.forEach(session => {
-                    if (combinedResults.has(session.id)) {
-                      combinedResults.get(session.id).matchedKeywords.push(keyword);
-                    } else {
-                      combinedResults.set(session.id, {
-                        ...session,
-                        matchedKeywords: [keyword],
-                      });
-                    }
-                  });
-                });
-
-                const finalResults = Array.from(combinedResults.values())
-                  .sort((a, b) => b.matchedKeywords.length - a.matchedKeywords.length);
-
-                return {
-                  results: finalResults,
-                  summary: {
-                    totalSessions: finalResults.length,
-                    keywordsSearched: keywords,
-                    sessionsByKeywordCount: finalResults.reduce((acc, session) => {
-                      const count = session.matchedKeywords.length;
-                      acc[count] = (acc[count] || 0) + 1;
-                      return acc;
-                    }, {} as Record<number, number>),
-                  },
-                };
-              },
-            }),
+            search_sessions_multi_keywords: searchSessionsTool,
           },
         }),
-
         onError: (error) => {
           console.error("On Error Catch:", error);
           setIsGenerating(false);
@@ -0,0 +1,183 @@
+import { Trans } from "@lingui/react/macro";
+import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
+import { message, open } from "@tauri-apps/plugin-dialog";
+import { PlusIcon, TrashIcon } from "lucide-react";
+
+import {
+  commands as localLlmCommands,
+  type CustomModel,
+  type ModelSelection,
+  type ModelTask,
+  type SupportedModel,
+} from "@hypr/plugin-local-llm";
+import { Button } from "@hypr/ui/components/ui/button";
+import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "@hypr/ui/components/ui/select";
+
+const TASKS: { task: ModelTask; label: string }[] = [
+  { task: "enhance", label: "Enhancing notes" },
+  { task: "title", label: "Titles and tags" },
+  { task: "chat", label: "Chat" },
+];
+
+const DEFAULT_VALUE = "default";
+
+function toValue(selection: ModelSelection): string {
+  return `${selection.type}:${selection.value}`;
+}
+
+function fromValue(value: string): ModelSelection | null {
+  if (value === DEFAULT_VALUE) {
+    return null;
+  }
+
+  const [type, ...rest] = value.split(":");
+  const id = rest.join(":");
+  return type === "custom"
+    ? { type: "custom", value: id }
+    : { type: "supported", value: id as SupportedModel };
+}
+
+function formatSize(bytes: number) {
+  return `${(bytes / 1024 / 1024 / 1024).toFixed(1)} GB`;
+}
+
+export function LLMCustomModels() {
+  const queryClient = useQueryClient();
+
+  const downloadedModels = useQuery({
+    queryKey: ["downloaded-local-llm-models"],
+    queryFn: () => localLlmCommands.listDownloadedModel(),
+  });
+
+  const customModels = useQuery({
+    queryKey: ["custom-local-llm-models"],
+    queryFn: () => localLlmCommands.listCustomModels(),
+  });
+
+  const taskModels = useQuery({
+    queryKey: ["task-local-llm-models"],
+    queryFn: async () => {
+      const entries = await Promise.all(
+        TASKS.map(async ({ task }) => [task, await localLlmCommands.getTaskModel(task)] as const),
+      );
+      return Object.fromEntries(entries) as Record<ModelTask, ModelSelection>;
+    },
+  });
+
+  const invalidate = () => {
+    queryClient.invalidateQueries({ queryKey: ["custom-local-llm-models"] });
+    queryClient.invalidateQueries({ queryKey: ["task-local-llm-models"] });
+    localLlmCommands.restartServer();
+  };
+
+  const addModel = useMutation({
+    mutationFn: async () => {
+      const path = await open({ multiple: false, filters: [{ name: "GGUF", extensions: ["gguf"] }] });
+      if (typeof path === "string") {
+        await localLlmCommands.addCustomModel(path);
+      }
+    },
+    onSuccess: invalidate,
+    onError: (error) => {
+      message(String(error), { title: "Could not add model", kind: "error" });
+    },
+  });
+
+  const removeModel = useMutation({
+    mutationFn: (id: string) => localLlmCommands.removeCustomModel(id),
+    onSuccess: invalidate,
+  });
+
+  const setTaskModel = useMutation({
+    mutationFn: ({ task, model }: { task: ModelTask; model: ModelSelection | null }) =>
+      localLlmCommands.setTaskModel(task, model),
+    onSuccess: invalidate,
+  });
+
+  const options: { value: string; label: string }[] = [
+    ...(downloadedModels.data ?? []).map((model) => ({
+      value: toValue({ type: "supported", value: model }),
+      label: model,
+    })),
+    ...(customModels.data ?? []).map((model: CustomModel) => ({
+      value: toValue({ type: "custom", value: model.id }),
+      label: model.name,
+    })),
+  ];
+
+  return (
+    <div className="space-y-6 max-w-2xl">
+      <div className="space-y-2">
+        <div className="flex items-center justify-between">
+          <h3 className="text-sm font-semibold">
+            <Trans>Custom GGUF Models</Trans>
+          </h3>
+          <Button
+            size="sm"
+            variant="outline"
+            onClick={() => addModel.mutate()}
+            disabled={addModel.isPending}
+            className="text-xs h-7 px-2 flex items-center gap-1"
+          >
+            <PlusIcon className="w-3 h-3" />
+            <Trans>Add model</Trans>
+          </Button>
+        </div>
+
+        {(customModels.data ?? []).map((model) => (
+          <div key={model.id} className="p-3 rounded-lg border flex items-center justify-between">
+            <div className="min-w-0">
+              <p className="font-medium text-sm truncate">{model.name}</p>
+              <p className="text-xs text-gray-500 truncate">
+                {[
+                  model.architecture,
+                  model.quantization,
+                  model.context_length && `${model.context_length} ctx`,
+                  formatSize(model.size_bytes),
+                ].filter(Boolean).join(" · ")}
+              </p>
+            </div>
+            <Button
+              size="sm"
+              variant="ghost"
+              onClick={() => removeModel.mutate(model.id)}
+              className="h-7 px-2"
+            >
+              <TrashIcon className="w-3 h-3" />
+            </Button>
+          </div>
+        ))}
+      </div>
+
+      <div className="space-y-2">
+        <h3 className="text-sm font-semibold">
+          <Trans>Model per Task</Trans>
+        </h3>
+
+        {TASKS.map(({ task, label }) => (
+          <div key={task} className="flex items-center justify-between gap-4">
+            <span className="text-sm text-gray-700">{label}</span>
+            <Select
+              value={taskModels.data ? toValue(taskModels.data[task]) : DEFAULT_VALUE}
+              onValueChange={(value) => setTaskModel.mutate({ task, model: fromValue(value) })}
+            >
+              <SelectTrigger className="w-56">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value={DEFAULT_VALUE}>
+                  <Trans>Current model</Trans>
+                </SelectItem>
+                {options.map((option) => (
+                  <SelectItem key={option.value} value={option.value}>
+                    {option.label}
+                  </SelectItem>
+                ))}
+              </SelectContent>
+            </Select>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
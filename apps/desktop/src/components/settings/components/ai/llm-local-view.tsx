@@ -6,6 +6,7 @@ import { useEffect } from "react";
 import { commands as localLlmCommands, SupportedModel } from "@hypr/plugin-local-llm";
 import { Button } from "@hypr/ui/components/ui/button";
 import { cn } from "@hypr/ui/lib/utils";
+import { LLMCustomModels } from "./llm-custom-models";
 import { SharedLLMProps } from "./shared";
 
 export function LLMLocalView({
@@ -145,6 +146,8 @@ export function LLMLocalView({
           ))}
         </div>
       </div>
+
+      <LLMCustomModels />
     </div>
   );
 }
@@ -1,15 +1,18 @@
 import { Trans } from "@lingui/react/macro";
-import { useMutation, useQuery } from "@tanstack/react-query";
-import { CloudLightningIcon } from "lucide-react";
+import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
+import { CloudLightningIcon, LibraryIcon } from "lucide-react";
 
 import { commands as flagsCommands } from "@hypr/plugin-flags";
+import { commands as localLlmCommands } from "@hypr/plugin-local-llm";
 import { Switch } from "@hypr/ui/components/ui/switch";
+import { showEmbeddingModelDownloadToast } from "../../toast/shared";
 
 export default function Lab() {
   return (
     <div>
       <div className="space-y-4">
         <CloudPreview />
+        <RelatedSessions />
       </div>
     </div>
   );
@@ -49,6 +52,38 @@ function CloudPreview() {
   );
 }
 
+function RelatedSessions() {
+  const queryClient = useQueryClient();
+
+  const enabledQuery = useQuery({
+    queryKey: ["retrieval-enabled"],
+    queryFn: () => localLlmCommands.isRetrievalEnabled(),
+  });
+
+  const enabledMutation = useMutation({
+    mutationFn: async (enabled: boolean) => {
+      await localLlmCommands.setRetrievalEnabled(enabled);
+
+      if (enabled && !(await localLlmCommands.isEmbeddingModelDownloaded())) {
+        showEmbeddingModelDownloadToast(undefined, queryClient);
+      }
+    },
+    onSuccess: () => {
+      enabledQuery.refetch();
+    },
+  });
+
+  return (
+    <FeatureFlag
+      title="Related Sessions in Chat"
+      description="Let chat look up your past sessions. Downloads a ~140MB embedding model."
+      icon={<LibraryIcon />}
+      enabled={enabledQuery.data ?? false}
+      onToggle={(enabled) => enabledMutation.mutate(enabled)}
+    />
+  );
+}
+
 function FeatureFlag({
   title,
   description,
@@ -133,6 +133,40 @@ export function showLlmModelDownloadToast(
   );
 }
 
+export function showEmbeddingModelDownloadToast(onComplete?: () => void, queryClient?: QueryClient) {
+  const embeddingChannel = new Channel();
+
+  localLlmCommands.downloadEmbeddingModel(embeddingChannel);
+
+  if (queryClient) {
+    queryClient.invalidateQueries({ queryKey: ["embedding-model-downloaded"] });
+  }
+
+  const id = "embedding-model-download";
+
+  toast(
+    {
+      id,
+      title: "Embedding Model",
+      content: (
+        <div className="space-y-1">
+          <div>Downloading the model used to find related sessions...</div>
+          <DownloadProgress
+            channel={embeddingChannel}
+            onComplete={() => {
+              sonnerToast.dismiss(id);
+              if (onComplete) {
+                onComplete();
+              }
+            }}
+          />
+        </div>
+      ),
+      dismissible: false,
+    },
+  );
+}
+
 export function enhanceFailedToast() {
   const id = "no-llm-connection";
 
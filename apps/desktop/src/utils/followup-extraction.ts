@@ -0,0 +1,158 @@
+import { z } from "zod";
+
+import { commands as connectorCommands } from "@hypr/plugin-connector";
+import { type ActionItem, commands as dbCommands, type Decision, type Human, type Word2 } from "@hypr/plugin-db";
+import { type ModelTask } from "@hypr/plugin-local-llm";
+import { commands as templateCommands } from "@hypr/plugin-template";
+import { generateObject, localProviderName, modelProvider } from "@hypr/utils/ai";
+
+// Same budget as enhancing, so each part fits in the local model's context.
+const LOCAL_LLM_TRANSCRIPT_TOKENS = 8 * 1024;
+
+const schema = z.object({
+  action_items: z.array(z.object({
+    text: z.string().min(1),
+    owner: z.string().nullable(),
+    due_date: z.string().nullable(),
+    quote: z.string(),
+  })),
+  decisions: z.array(z.object({
+    text: z.string().min(1),
+    quote: z.string(),
+  })),
+});
+
+/**
+ * Finds the action items and decisions in the session's transcript and saves them.
+ * Does nothing if the session already has some, so edits made by the user are kept.
+ */
+export async function extractFollowupsForSession(sessionId: string): Promise<void> {
+  try {
+    const [existingActionItems, existingDecisions] = await Promise.all([
+      dbCommands.listSessionActionItems(sessionId),
+      dbCommands.listSessionDecisions(sessionId),
+    ]);
+    if (existingActionItems.length > 0 || existingDecisions.length > 0) {
+      return;
+    }
+
+    const session = await dbCommands.getSession({ id: sessionId });
+    if (!session || session.words.length === 0) {
+      return;
+    }
+
+    const [{ type }, config, participants] = await Promise.all([
+      connectorCommands.getLlmConnection(),
+      dbCommands.getConfig(),
+      dbCommands.sessionListParticipants(sessionId),
+    ]);
+
+    const systemPrompt = await templateCommands.render(
+      "extract_followups.system",
+      { config, type },
+    );
+
+    const provider = await modelProvider();
+    const model = provider.languageModel("defaultModel");
+
+    const windows = type === "HyprLocal"
+      ? await templateCommands.splitTimeline(session.words, LOCAL_LLM_TRANSCRIPT_TOKENS)
+      : [session.words];
+
+    let offset = 0;
+
+    for (const windowWords of windows) {
+      const userPrompt = await templateCommands.render(
+        "extract_followups.user",
+        {
+          type,
+          date: session.record_start ?? session.created_at,
+          participants: participants.map((p) => p.full_name).filter(Boolean),
+          words: JSON.stringify(windowWords),
+        },
+      );
+
+      // A part the model couldn't answer for is skipped, like one without follow-ups.
+      const result = await generateObject({
+        model,
+        schema,
+        schemaName: "followups",
+        messages: [
+          { role: "system", content: systemPrompt },
+          { role: "user", content: userPrompt },
+        ],
+        providerOptions: {
+          [localProviderName]: {
+            metadata: { task: "enhance" satisfies ModelTask },
+          },
+        },
+      }).catch((error) => {
+        console.error("Follow-up extraction failed for a part:", error);
+        return null;
+      });
+
+      if (result) {
+        const createdAt = new Date().toISOString();
+
+        for (const item of result.object.action_items) {
+          await dbCommands.upsertActionItem({
+            id: crypto.randomUUID(),
+            session_id: sessionId,
+            created_at: createdAt,
+            text: item.text,
+            owner_id: item.owner ? findOwner(participants, item.owner)?.id ?? null : null,
+            due_date: parseDueDate(item.due_date),
+            completed_at: null,
+            ...(await locate(session.words, windowWords, offset, item.quote)),
+          } satisfies ActionItem);
+        }
+
+        for (const decision of result.object.decisions) {
+          await dbCommands.upsertDecision({
+            id: crypto.randomUUID(),
+            session_id: sessionId,
+            created_at: createdAt,
+            text: decision.text,
+            ...(await locate(session.words, windowWords, offset, decision.quote)),
+          } satisfies Decision);
+        }
+      }
+
+      offset += windowWords.length;
+    }
+  } catch (error) {
+    console.error("Follow-up extraction failed:", error);
+  }
+}
+
+function findOwner(participants: Human[], name: string): Human | undefined {
+  const target = name.trim().toLowerCase();
+
+  return participants.find((p) => p.full_name?.toLowerCase() === target)
+    ?? participants.find((p) => p.full_name?.split(" ")[0]?.toLowerCase() === target.split(" ")[0]);
+}
+
+function parseDueDate(value: string | null): string | null {
+  if (!value) {
+    return null;
+  }
+
+  const date = new Date(value);
+  return isNaN(date.getTime()) ? null : date.toISOString();
+}
+
+// Word indices are for the whole transcript, while the quote is searched for in the part it came from.
+async function locate(words: Word2[], windowWords: Word2[], offset: number, quote: string) {
+  const range = await templateCommands.locateInTimeline(windowWords, quote);
+  if (!range) {
+    return { start_word: null, end_word: null, start_ms: null, end_ms: null };
+  }
+
+  const [start, end] = [range[0] + offset, range[1] + offset];
+  return {
+    start_word: start,
+    end_word: end,
+    start_ms: words[start]?.start_ms ?? null,
+    end_ms: words[end - 1]?.end_ms ?? null,
+  };
+}
@@ -2,6 +2,7 @@ import { z } from "zod";
 
 import { commands as connectorCommands } from "@hypr/plugin-connector";
 import { commands as dbCommands } from "@hypr/plugin-db";
+import { type ModelTask } from "@hypr/plugin-local-llm";
 import { commands as templateCommands, type Grammar } from "@hypr/plugin-template";
 import { generateText, localProviderName, modelProvider } from "@hypr/utils/ai";
 
@@ -53,6 +54,7 @@ export async function generateTagsForSession(sessionId: string): Promise<string[
       providerOptions: {
         [localProviderName]: {
           metadata: {
+            task: "title" satisfies ModelTask,
             grammar: {
               task: "tags",
             } satisfies Grammar,
@@ -0,0 +1,308 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use realfft::{num_complex::Complex, ComplexToReal, RealFftPlanner, RealToComplex};
+
+// Bluetooth headsets can add a few hundred milliseconds on top of the capture latency.
+const MAX_DELAY_MS: u32 = 500;
+// Long enough to hold the largest delay, with plenty of overlap left to correlate.
+const WINDOW_MS: u32 = 1000;
+const HOP_MS: u32 = 500;
+// Below this, the speaker is silent or the mic doesn't pick it up. Nothing to measure.
+const MIN_REFERENCE_RMS: f32 = 1e-3;
+const MIN_MIC_RMS: f32 = 1e-4;
+// Peak over the mean of the correlation. Uncorrelated signals stay well below.
+const MIN_CONFIDENCE: f32 = 8.0;
+// The reported delay is the median of the last few measurements, so a single bad one is ignored.
+const HISTORY_LEN: usize = 5;
+// Re-aligning drops or inserts samples, so small changes are not worth it.
+const REALIGN_TOLERANCE_MS: u32 = 4;
+
+/// Measures how far the mic lags behind the reference (speaker) signal, with GCC-PHAT.
+pub struct DelayEstimator {
+    sample_rate: u32,
+    window: usize,
+    hop: usize,
+    max_lag: usize,
+    mic: VecDeque<f32>,
+    reference: VecDeque<f32>,
+    since_last: usize,
+    history: VecDeque<i64>,
+    fft: Arc<dyn RealToComplex<f32>>,
+    ifft: Arc<dyn ComplexToReal<f32>>,
+}
+
+impl DelayEstimator {
+    pub fn new(sample_rate: u32) -> Self {
+        let window = (sample_rate * WINDOW_MS / 1000) as usize;
+
+        // Zero-padded to twice the window, so the correlation is linear rather than circular.
+        let mut planner = RealFftPlanner::<f32>::new();
+        let fft = planner.plan_fft_forward(window * 2);
+        let ifft = planner.plan_fft_inverse(window * 2);
+
+        Self {
+            sample_rate,
+            window,
+            hop: (sample_rate * HOP_MS / 1000) as usize,
+            max_lag: (sample_rate * MAX_DELAY_MS / 1000) as usize,
+            mic: VecDeque::with_capacity(window),
+            reference: VecDeque::with_capacity(window),
+            since_last: 0,
+            history: VecDeque::with_capacity(HISTORY_LEN),
+            fft,
+            ifft,
+        }
+    }
+
+    pub fn push(&mut self, mic: &[f32], reference: &[f32]) {
+        for (buffer, samples) in [(&mut self.mic, mic), (&mut self.reference, reference)] {
+            buffer.extend(samples);
+            let excess = buffer.len().saturating_sub(self.window);
+            buffer.drain(..excess);
+        }
+
+        self.since_last += mic.len();
+        if self.since_last < self.hop || self.mic.len() < self.window {
+            return;
+        }
+        self.since_last = 0;
+
+        if let Some(lag) = self.measure() {
+            if self.history.len() == HISTORY_LEN {
+                self.history.pop_front();
+            }
+            self.history.push_back(lag);
+        }
+    }
+
+    /// Positive when the mic lags behind the reference, which is the usual case.
+    pub fn delay_samples(&self) -> Option<i64> {
+        if self.history.is_empty() {
+            return None;
+        }
+
+        let mut sorted = self.history.iter().copied().collect::<Vec<_>>();
+        sorted.sort_unstable();
+        Some(sorted[sorted.len() / 2])
+    }
+
+    pub fn delay_ms(&self) -> Option<f32> {
+        self.delay_samples()
+            .map(|samples| samples as f32 * 1000.0 / self.sample_rate as f32)
+    }
+
+    fn measure(&self) -> Option<i64> {
+        if rms(&self.reference) < MIN_REFERENCE_RMS || rms(&self.mic) < MIN_MIC_RMS {
+            return None;
+        }
+
+        let size = self.window * 2;
+        let spectrum = |samples: &VecDeque<f32>| {
+            let mut input = vec![0.0f32; size];
+            for (dst, src) in input.iter_mut().zip(samples) {
+                *dst = *src;
+            }
+            let mut output = self.fft.make_output_vec();
+            self.fft.process(&mut input, &mut output).ok()?;
+            Some(output)
+        };
+
+        let mic = spectrum(&self.mic)?;
+        let reference = spectrum(&self.reference)?;
+
+        // Phase transform: only the phase of the cross-spectrum is kept, which sharpens the peak.
+        let mut cross = mic
+            .iter()
+            .zip(&reference)
+            .map(|(m, r)| {
+                let c = m * r.conj();
+                let norm = c.norm();
+                if norm > f32::EPSILON {
+                    c / norm
+                } else {
+                    Complex::new(0.0, 0.0)
+                }
+            })
+            .collect::<Vec<_>>();
+        if let Some(first) = cross.first_mut() {
+            first.im = 0.0;
+        }
+        if let Some(last) = cross.last_mut() {
+            last.im = 0.0;
+        }
+
+        let mut correlation = self.ifft.make_output_vec();
+        self.ifft.process(&mut cross, &mut correlation).ok()?;
+
+        let lags = -(self.max_lag as i64)..=self.max_lag as i64;
+        let at = |lag: i64| correlation[lag.rem_euclid(size as i64) as usize];
+
+        let (best_lag, peak) = lags
+            .clone()
+            .map(|lag| (lag, at(lag)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        let mean = lags.clone().map(|lag| at(lag).abs()).sum::<f32>() / lags.count() as f32;
+
+        (mean > 0.0 && peak / mean >= MIN_CONFIDENCE).then_some(best_lag)
+    }
+}
+
+fn rms(samples: &VecDeque<f32>) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// Delays whichever of the mic and reference is ahead, so they line up before `AEC::process_streaming`.
+pub struct DelayCompensator {
+    estimator: DelayEstimator,
+    mic_line: VecDeque<f32>,
+    reference_line: VecDeque<f32>,
+    applied: i64,
+    tolerance: i64,
+}
+
+impl DelayCompensator {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            estimator: DelayEstimator::new(sample_rate),
+            mic_line: VecDeque::new(),
+            reference_line: VecDeque::new(),
+            applied: 0,
+            tolerance: (sample_rate * REALIGN_TOLERANCE_MS / 1000) as i64,
+        }
+    }
+
+    /// Returns the aligned chunks, as long as the ones passed in.
+    pub fn process(&mut self, mic: &[f32], reference: &[f32]) -> (Vec<f32>, Vec<f32>) {
+        self.estimator.push(mic, reference);
+
+        if let Some(delay) = self.estimator.delay_samples() {
+            if (delay - self.applied).abs() > self.tolerance {
+                self.apply(delay);
+            }
+        }
+
+        (
+            delay_line(&mut self.mic_line, mic),
+            delay_line(&mut self.reference_line, reference),
+        )
+    }
+
+    /// The current estimate, `None` until there was enough speaker audio to measure it.
+    pub fn delay_ms(&self) -> Option<f32> {
+        self.estimator.delay_ms()
+    }
+
+    fn apply(&mut self, delay: i64) {
+        let (mic_target, reference_target) = if delay >= 0 {
+            (0, delay as usize)
+        } else {
+            (delay.unsigned_abs() as usize, 0)
+        };
+
+        for (line, target) in [
+            (&mut self.mic_line, mic_target),
+            (&mut self.reference_line, reference_target),
+        ] {
+            if line.len() > target {
+                line.drain(..line.len() - target);
+            } else {
+                for _ in line.len()..target {
+                    line.push_front(0.0);
+                }
+            }
+        }
+
+        self.applied = delay;
+    }
+}
+
+fn delay_line(line: &mut VecDeque<f32>, samples: &[f32]) -> Vec<f32> {
+    line.extend(samples);
+    line.drain(..samples.len()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 16000;
+
+    // Deterministic white noise, so the tests don't need `rand`.
+    fn noise(len: usize, seed: u32) -> Vec<f32> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                (state >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+            })
+            .collect()
+    }
+
+    fn delayed(signal: &[f32], delay: usize, gain: f32) -> Vec<f32> {
+        let noise = noise(signal.len(), 7);
+        (0..signal.len())
+            .map(|i| {
+                let echo = if i >= delay { signal[i - delay] } else { 0.0 };
+                echo * gain + noise[i] * 0.05
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_estimate_delay() {
+        let reference = noise(SAMPLE_RATE as usize * 3, 42);
+        let mic = delayed(&reference, 1600, 0.3);
+
+        let mut estimator = DelayEstimator::new(SAMPLE_RATE);
+        for (m, r) in mic.chunks(512).zip(reference.chunks(512)) {
+            estimator.push(m, r);
+        }
+
+        assert_eq!(estimator.delay_samples(), Some(1600));
+        assert_eq!(estimator.delay_ms(), Some(100.0));
+    }
+
+    #[test]
+    fn test_no_estimate_when_silent() {
+        let reference = vec![0.0; SAMPLE_RATE as usize * 3];
+        let mic = noise(reference.len(), 42);
+
+        let mut estimator = DelayEstimator::new(SAMPLE_RATE);
+        for (m, r) in mic.chunks(512).zip(reference.chunks(512)) {
+            estimator.push(m, r);
+        }
+
+        assert_eq!(estimator.delay_samples(), None);
+    }
+
+    #[test]
+    fn test_compensator_aligns() {
+        let reference = noise(SAMPLE_RATE as usize * 4, 42);
+        let mic = delayed(&reference, 800, 1.0);
+
+        let mut compensator = DelayCompensator::new(SAMPLE_RATE);
+        let mut aligned_mic = vec![];
+        let mut aligned_reference = vec![];
+        for (m, r) in mic.chunks(512).zip(reference.chunks(512)) {
+            let (m, r) = compensator.process(m, r);
+            assert_eq!(m.len(), 512);
+            aligned_mic.extend(m);
+            aligned_reference.extend(r);
+        }
+
+        assert_eq!(compensator.delay_ms(), Some(50.0));
+
+        // Once aligned, the mic is the reference plus the added noise.
+        let tail = aligned_mic.len() - SAMPLE_RATE as usize;
+        let error = aligned_mic[tail..]
+            .iter()
+            .zip(&aligned_reference[tail..])
+            .map(|(m, r)| (m - r).abs())
+            .fold(0.0f32, f32::max);
+        assert!(error < 0.05, "error: {}", error);
+    }
+}
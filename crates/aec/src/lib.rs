@@ -6,7 +6,9 @@ use hypr_onnx::{
     ort::{session::Session, value::TensorRef},
 };
 
+mod delay;
 mod error;
+pub use delay::*;
 pub use error::*;
 
 mod model;
@@ -0,0 +1,217 @@
+use std::collections::VecDeque;
+
+// Consumer devices are usually within a few hundred ppm of each other.
+const MAX_DRIFT: f64 = 0.005;
+// Chunks arrive in bursts, so the backlog is smoothed over a few seconds before it's acted on.
+const BACKLOG_SMOOTHING: f64 = 0.02;
+// Blocks to wait before the current backlog is taken as the one to hold.
+const WARMUP_BLOCKS: u64 = 250;
+// Gains of the PI controller that steers the resampling ratio, per block and per sample of backlog error.
+const PROPORTIONAL_GAIN: f64 = 1e-5;
+const INTEGRAL_GAIN: f64 = 2e-8;
+
+/// Keeps the reference (speaker) stream on the mic's clock.
+///
+/// The two devices run on independent clocks, so over a long session one produces slightly
+/// more samples than the other. The reference is resampled by a ratio that follows how much
+/// of it piles up compared to the mic, so blocks popped together stay sample-aligned.
+pub struct DriftCompensator {
+    mic: VecDeque<f32>,
+    reference_in: VecDeque<f32>,
+    reference: VecDeque<f32>,
+    position: f64,
+    ratio: f64,
+    integral: f64,
+    backlog: Option<f64>,
+    target_backlog: Option<f64>,
+    blocks: u64,
+}
+
+impl Default for DriftCompensator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DriftCompensator {
+    pub fn new() -> Self {
+        Self {
+            mic: VecDeque::new(),
+            reference_in: VecDeque::new(),
+            reference: VecDeque::new(),
+            position: 0.0,
+            ratio: 1.0,
+            integral: 0.0,
+            backlog: None,
+            target_backlog: None,
+            blocks: 0,
+        }
+    }
+
+    pub fn push_mic(&mut self, samples: &[f32]) {
+        self.mic.extend(samples);
+    }
+
+    pub fn push_reference(&mut self, samples: &[f32]) {
+        self.reference_in.extend(samples);
+        self.resample();
+    }
+
+    /// Returns the next mic and reference blocks once both have `size` samples.
+    pub fn pop(&mut self, size: usize) -> Option<(Vec<f32>, Vec<f32>)> {
+        if self.mic.len() < size || self.reference.len() < size {
+            return None;
+        }
+
+        let mic = self.mic.drain(..size).collect();
+        let reference = self.reference.drain(..size).collect();
+
+        self.update_ratio();
+        Some((mic, reference))
+    }
+
+    /// Reference samples consumed per mic sample. Above 1 when the reference clock runs fast.
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    pub fn drift_ppm(&self) -> f64 {
+        (self.ratio - 1.0) * 1e6
+    }
+
+    // Linear interpolation, stepping through the input by `ratio`.
+    fn resample(&mut self) {
+        while self.position + 1.0 < self.reference_in.len() as f64 {
+            let index = self.position as usize;
+            let frac = (self.position - index as f64) as f32;
+            let (a, b) = (self.reference_in[index], self.reference_in[index + 1]);
+            self.reference.push_back(a + (b - a) * frac);
+            self.position += self.ratio;
+        }
+
+        let consumed = (self.position as usize).min(self.reference_in.len());
+        self.reference_in.drain(..consumed);
+        self.position -= consumed as f64;
+    }
+
+    fn update_ratio(&mut self) {
+        // Samples of the reference waiting, in either form, beyond what the mic has waiting.
+        let backlog = (self.reference_in.len() as f64 - self.position) / self.ratio
+            + self.reference.len() as f64
+            - self.mic.len() as f64;
+
+        let smoothed = match self.backlog {
+            None => backlog,
+            Some(previous) => previous + (backlog - previous) * BACKLOG_SMOOTHING,
+        };
+        self.backlog = Some(smoothed);
+
+        self.blocks += 1;
+        if self.blocks < WARMUP_BLOCKS {
+            return;
+        }
+        let target = *self.target_backlog.get_or_insert(smoothed);
+
+        let error = smoothed - target;
+        self.integral = (self.integral + error * INTEGRAL_GAIN).clamp(-MAX_DRIFT, MAX_DRIFT);
+        self.ratio = (1.0 + error * PROPORTIONAL_GAIN + self.integral)
+            .clamp(1.0 - MAX_DRIFT, 1.0 + MAX_DRIFT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: usize = 16000;
+    const BLOCK_SIZE: usize = 512;
+
+    fn fixture(seconds: usize) -> Vec<f32> {
+        hypr_data::english_1::AUDIO
+            .chunks_exact(2)
+            .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / 32768.0)
+            .cycle()
+            .take(SAMPLE_RATE * seconds)
+            .collect()
+    }
+
+    // What a device with a clock `skew` times faster would have captured.
+    fn skewed(samples: &[f32], skew: f64) -> Vec<f32> {
+        let len = ((samples.len() - 1) as f64 * skew) as usize;
+        (0..len)
+            .map(|i| {
+                let position = i as f64 / skew;
+                let index = position as usize;
+                let frac = (position - index as f64) as f32;
+                let next = samples.get(index + 1).copied().unwrap_or_default();
+                samples[index] + (next - samples[index]) * frac
+            })
+            .collect()
+    }
+
+    // Lag of `reference` against `mic` around `at`, by brute-force cross-correlation.
+    fn lag(mic: &[f32], reference: &[f32], at: usize) -> i64 {
+        const MAX_LAG: i64 = 400;
+        let window = at..at + SAMPLE_RATE;
+
+        (-MAX_LAG..=MAX_LAG)
+            .max_by(|a, b| {
+                let score = |lag: i64| {
+                    window
+                        .clone()
+                        .map(|i| mic[i] * reference[(i as i64 + lag) as usize])
+                        .sum::<f32>()
+                };
+                score(*a).total_cmp(&score(*b))
+            })
+            .unwrap()
+    }
+
+    fn run(skew: f64) -> (DriftCompensator, Vec<f32>, Vec<f32>) {
+        let mic = fixture(180);
+        let reference = skewed(&mic, skew);
+
+        let mut compensator = DriftCompensator::new();
+        let (mut mic_out, mut reference_out) = (vec![], vec![]);
+
+        // The reference arrives in uneven chunks, as it would from a real device.
+        let mut sent = 0;
+        let mut state = 7u32;
+        for (i, chunk) in mic.chunks(BLOCK_SIZE).enumerate() {
+            compensator.push_mic(chunk);
+
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            let due = (((i + 1) * BLOCK_SIZE) as f64 * skew) as usize;
+            let until = due
+                .saturating_sub((state >> 24) as usize)
+                .clamp(sent, reference.len());
+            compensator.push_reference(&reference[sent..until]);
+            sent = until;
+
+            while let Some((m, r)) = compensator.pop(BLOCK_SIZE) {
+                mic_out.extend(m);
+                reference_out.extend(r);
+            }
+        }
+
+        (compensator, mic_out, reference_out)
+    }
+
+    #[test]
+    fn test_clock_mismatch() {
+        for skew in [1.001, 0.999] {
+            let (compensator, mic, reference) = run(skew);
+            assert!(
+                (compensator.ratio() - skew).abs() < 2e-4,
+                "{skew}: {}",
+                compensator.ratio()
+            );
+
+            // Without compensation, the lag would grow by 16 samples every second.
+            let lags =
+                [90, 120, 150, 170].map(|seconds| lag(&mic, &reference, seconds * SAMPLE_RATE));
+            let spread = lags.iter().max().unwrap() - lags.iter().min().unwrap();
+            assert!(spread <= 32, "{skew}: {lags:?}");
+        }
+    }
+}
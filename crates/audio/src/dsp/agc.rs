@@ -0,0 +1,51 @@
+use super::{db_to_linear, linear_to_db, rms, Processor};
+
+// Blocks quieter than this are treated as silence, and don't move the gain.
+const SILENCE_DB: f32 = -60.0;
+const ATTACK: f32 = 0.3;
+const RELEASE: f32 = 0.02;
+
+/// Automatic gain control. Slowly moves the level of speech towards `target_db`,
+/// and reacts quickly when the input gets louder.
+pub struct Agc {
+    target_db: f32,
+    max_gain_db: f32,
+    gain_db: f32,
+}
+
+impl Agc {
+    pub fn new(target_db: f32, max_gain_db: f32) -> Self {
+        Self {
+            target_db,
+            max_gain_db,
+            gain_db: 0.0,
+        }
+    }
+
+    pub fn gain_db(&self) -> f32 {
+        self.gain_db
+    }
+}
+
+impl Processor for Agc {
+    fn process(&mut self, block: &mut [f32]) {
+        let level_db = linear_to_db(rms(block));
+        let start = db_to_linear(self.gain_db);
+
+        if level_db > SILENCE_DB {
+            let desired = (self.target_db - level_db).clamp(-self.max_gain_db, self.max_gain_db);
+            let rate = if desired < self.gain_db {
+                ATTACK
+            } else {
+                RELEASE
+            };
+            self.gain_db += (desired - self.gain_db) * rate;
+        }
+
+        let end = db_to_linear(self.gain_db);
+        let len = block.len().max(1) as f32;
+        for (i, sample) in block.iter_mut().enumerate() {
+            *sample *= start + (end - start) * (i as f32 / len);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use super::{linear_to_db, rms, Processor};
+
+// How fast the noise floor estimate follows the signal. It drops quickly and rises slowly,
+// so speech doesn't get mistaken for noise.
+const FLOOR_FALL: f32 = 0.5;
+const FLOOR_RISE: f32 = 0.0005;
+// Anything this far above the noise floor counts as signal.
+const OPEN_ABOVE_FLOOR_DB: f32 = 6.0;
+const GAIN_SMOOTHING: f32 = 0.3;
+
+/// Attenuates blocks that are close to the estimated noise floor, such as fan noise between sentences.
+pub struct NoiseSuppressor {
+    attenuation: f32,
+    noise_floor_db: Option<f32>,
+    gain: f32,
+}
+
+impl NoiseSuppressor {
+    pub fn new(attenuation_db: f32) -> Self {
+        Self {
+            attenuation: super::db_to_linear(-attenuation_db.abs()),
+            noise_floor_db: None,
+            gain: 1.0,
+        }
+    }
+
+    pub fn noise_floor_db(&self) -> Option<f32> {
+        self.noise_floor_db
+    }
+}
+
+impl Processor for NoiseSuppressor {
+    fn process(&mut self, block: &mut [f32]) {
+        let level_db = linear_to_db(rms(block));
+
+        let floor_db = match self.noise_floor_db {
+            None => level_db,
+            Some(floor) if level_db < floor => floor + (level_db - floor) * FLOOR_FALL,
+            Some(floor) => floor + (level_db - floor) * FLOOR_RISE,
+        };
+        self.noise_floor_db = Some(floor_db);
+
+        let target = if level_db > floor_db + OPEN_ABOVE_FLOOR_DB {
+            1.0
+        } else {
+            self.attenuation
+        };
+
+        let start = self.gain;
+        self.gain += (target - self.gain) * GAIN_SMOOTHING;
+
+        // Ramp within the block to avoid clicks.
+        let len = block.len().max(1) as f32;
+        for (i, sample) in block.iter_mut().enumerate() {
+            *sample *= start + (self.gain - start) * (i as f32 / len);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use super::Processor;
+
+const LOOKAHEAD_MS: usize = 10;
+
+/// Lookahead peak limiter. Delays the signal by `LOOKAHEAD_MS`.
+pub struct Limiter {
+    ceiling: f32,
+    lookahead_samples: usize,
+    buffer: Vec<f32>,
+    gain_reduction: Vec<f32>,
+    current_position: usize,
+}
+
+impl Limiter {
+    pub fn new(sample_rate: u32, ceiling_db: f32) -> Self {
+        let lookahead_samples = ((sample_rate as usize * LOOKAHEAD_MS) / 1000).max(1);
+
+        Self {
+            ceiling: super::db_to_linear(ceiling_db),
+            lookahead_samples,
+            buffer: vec![0.0; lookahead_samples],
+            gain_reduction: vec![1.0; lookahead_samples],
+            current_position: 0,
+        }
+    }
+
+    pub fn process_sample(&mut self, sample: f32) -> f32 {
+        self.buffer[self.current_position] = sample;
+
+        let sample_abs = sample.abs();
+        self.gain_reduction[self.current_position] = if sample_abs > self.ceiling {
+            self.ceiling / sample_abs
+        } else {
+            1.0
+        };
+
+        let output_position = (self.current_position + 1) % self.lookahead_samples;
+        let output_sample = self.buffer[output_position] * self.gain_reduction[output_position];
+
+        self.current_position = output_position;
+        output_sample
+    }
+}
+
+impl Processor for Limiter {
+    fn process(&mut self, block: &mut [f32]) {
+        for sample in block.iter_mut() {
+            *sample = self.process_sample(*sample);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use ebur128::{EbuR128, Mode};
+
+use super::Processor;
+
+const ANALYZE_CHUNK_SIZE: usize = 512;
+
+/// Normalizes to `target_lufs`, based on the integrated EBU R128 loudness so far.
+pub struct LoudnessNormalizer {
+    ebur128: EbuR128,
+    target_lufs: f64,
+    gain_linear: f32,
+    pending: Vec<f32>,
+}
+
+impl LoudnessNormalizer {
+    pub fn new(sample_rate: u32, target_lufs: f64) -> Result<Self, crate::Error> {
+        Ok(Self {
+            ebur128: EbuR128::new(1, sample_rate, Mode::I)?,
+            target_lufs,
+            gain_linear: 1.0,
+            pending: Vec::with_capacity(ANALYZE_CHUNK_SIZE),
+        })
+    }
+}
+
+impl Processor for LoudnessNormalizer {
+    fn process(&mut self, block: &mut [f32]) {
+        self.pending.extend_from_slice(block);
+
+        if self.pending.len() >= ANALYZE_CHUNK_SIZE {
+            let _ = self.ebur128.add_frames_f32(&self.pending);
+            self.pending.clear();
+
+            if let Ok(current_lufs) = self.ebur128.loudness_global() {
+                if current_lufs.is_finite() && current_lufs < 0.0 {
+                    let gain_db = self.target_lufs - current_lufs;
+                    self.gain_linear = 10_f32.powf(gain_db as f32 / 20.0);
+                }
+            }
+        }
+
+        for sample in block.iter_mut() {
+            *sample *= self.gain_linear;
+        }
+    }
+}
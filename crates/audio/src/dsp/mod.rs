@@ -0,0 +1,222 @@
+mod agc;
+mod gate;
+mod limiter;
+mod loudness;
+
+pub use agc::*;
+pub use gate::*;
+pub use limiter::*;
+pub use loudness::*;
+
+/// One stage of a [`Chain`]. Works on mono blocks, in place.
+pub trait Processor: Send {
+    fn process(&mut self, block: &mut [f32]);
+}
+
+pub struct Gain(pub f32);
+
+impl Processor for Gain {
+    fn process(&mut self, block: &mut [f32]) {
+        for sample in block.iter_mut() {
+            *sample *= self.0;
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Chain {
+    processors: Vec<Box<dyn Processor>>,
+}
+
+impl Chain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, processor: impl Processor + 'static) -> Self {
+        self.processors.push(Box::new(processor));
+        self
+    }
+
+    pub fn process(&mut self, block: &mut [f32]) {
+        for processor in self.processors.iter_mut() {
+            processor.process(block);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GainControl {
+    Off,
+    /// Follows the level of speech, block by block.
+    Agc {
+        target_db: f32,
+        max_gain_db: f32,
+    },
+    /// Targets the integrated loudness of everything so far. Steadier, but slower to react.
+    Loudness {
+        target_lufs: f64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChainConfig {
+    pub gain: f32,
+    /// Attenuation in dB for blocks close to the noise floor. `None` disables it.
+    pub noise_suppression_db: Option<f32>,
+    pub gain_control: GainControl,
+    /// Peak ceiling in dBFS. `None` disables the limiter.
+    pub limiter_ceiling_db: Option<f32>,
+}
+
+impl Default for ChainConfig {
+    fn default() -> Self {
+        Self {
+            gain: 1.0,
+            noise_suppression_db: None,
+            gain_control: GainControl::Off,
+            limiter_ceiling_db: Some(-1.0),
+        }
+    }
+}
+
+impl ChainConfig {
+    /// Gain, then noise suppression, then gain control, then the limiter.
+    pub fn build(&self, sample_rate: u32) -> Result<Chain, crate::Error> {
+        let mut chain = Chain::new();
+
+        if self.gain != 1.0 {
+            chain = chain.with(Gain(self.gain));
+        }
+
+        if let Some(attenuation_db) = self.noise_suppression_db {
+            chain = chain.with(NoiseSuppressor::new(attenuation_db));
+        }
+
+        chain = match self.gain_control {
+            GainControl::Off => chain,
+            GainControl::Agc {
+                target_db,
+                max_gain_db,
+            } => chain.with(Agc::new(target_db, max_gain_db)),
+            GainControl::Loudness { target_lufs } => {
+                chain.with(LoudnessNormalizer::new(sample_rate, target_lufs)?)
+            }
+        };
+
+        if let Some(ceiling_db) = self.limiter_ceiling_db {
+            chain = chain.with(Limiter::new(sample_rate, ceiling_db));
+        }
+
+        Ok(chain)
+    }
+}
+
+pub(crate) fn rms(block: &[f32]) -> f32 {
+    if block.is_empty() {
+        return 0.0;
+    }
+    (block.iter().map(|s| s * s).sum::<f32>() / block.len() as f32).sqrt()
+}
+
+pub(crate) fn db_to_linear(db: f32) -> f32 {
+    10_f32.powf(db / 20.0)
+}
+
+pub(crate) fn linear_to_db(linear: f32) -> f32 {
+    20.0 * linear.max(1e-10).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 16000;
+    const BLOCK_SIZE: usize = 256;
+
+    fn fixture(scale: f32) -> Vec<f32> {
+        hypr_data::english_1::AUDIO
+            .chunks_exact(2)
+            .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / 32768.0 * scale)
+            .collect()
+    }
+
+    fn run(chain: &mut Chain, mut samples: Vec<f32>) -> Vec<f32> {
+        for block in samples.chunks_mut(BLOCK_SIZE) {
+            chain.process(block);
+        }
+        samples
+    }
+
+    // Level of the blocks that carry speech, skipping the first seconds while the chain adapts.
+    fn speech_level_db(samples: &[f32]) -> f32 {
+        let levels = samples
+            .chunks(BLOCK_SIZE)
+            .skip(SAMPLE_RATE as usize * 5 / BLOCK_SIZE)
+            .map(rms)
+            .filter(|level| linear_to_db(*level) > -50.0)
+            .collect::<Vec<_>>();
+
+        linear_to_db(levels.iter().sum::<f32>() / levels.len() as f32)
+    }
+
+    fn config() -> ChainConfig {
+        ChainConfig {
+            noise_suppression_db: Some(12.0),
+            gain_control: GainControl::Agc {
+                target_db: -20.0,
+                max_gain_db: 30.0,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_quiet_and_loud_inputs_converge() {
+        let quiet = run(&mut config().build(SAMPLE_RATE).unwrap(), fixture(0.05));
+        let loud = run(&mut config().build(SAMPLE_RATE).unwrap(), fixture(4.0));
+
+        let (quiet_db, loud_db) = (speech_level_db(&quiet), speech_level_db(&loud));
+        assert!((quiet_db - loud_db).abs() < 6.0, "{quiet_db} vs {loud_db}");
+        assert!((quiet_db - -20.0).abs() < 8.0, "{quiet_db}");
+
+        let ceiling = db_to_linear(-1.0);
+        assert!(loud.iter().all(|s| s.abs() <= ceiling + 1e-6));
+    }
+
+    // Deterministic white-ish noise.
+    fn noise(len: usize, amplitude: f32) -> Vec<f32> {
+        let mut state = 1u32;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                ((state >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0) * amplitude
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_noise_suppression() {
+        // Speech over constant background noise, then a second of the noise alone.
+        let speech = fixture(1.0);
+        let tail = SAMPLE_RATE as usize;
+        let mut samples = noise(speech.len() + tail, 0.01);
+        for (sample, s) in samples.iter_mut().zip(speech) {
+            *sample += s;
+        }
+
+        let mut chain = ChainConfig {
+            noise_suppression_db: Some(20.0),
+            limiter_ceiling_db: None,
+            ..Default::default()
+        }
+        .build(SAMPLE_RATE)
+        .unwrap();
+        let processed = run(&mut chain, samples.clone());
+
+        let noise_only = samples.len() - tail / 2..;
+        let before = linear_to_db(rms(&samples[noise_only.clone()]));
+        let after = linear_to_db(rms(&processed[noise_only]));
+        assert!(after < before - 10.0, "{before} -> {after}");
+    }
+}
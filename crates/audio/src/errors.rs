@@ -2,4 +2,16 @@
 pub enum Error {
     #[error("no input device found")]
     NoInputDevice,
+    #[error("no output device found")]
+    NoOutputDevice,
+    #[error(transparent)]
+    LoudnessError(#[from] ebur128::Error),
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    DecoderError(#[from] rodio::decoder::DecoderError),
+    #[error(transparent)]
+    OutputStreamError(#[from] rodio::StreamError),
+    #[error(transparent)]
+    PlayError(#[from] rodio::PlayError),
 }
@@ -0,0 +1,220 @@
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::Stream;
+use rodio::Source;
+use tokio::time::{Instant, Sleep};
+
+// In real time, samples are released this far ahead of the clock, so the stream isn't woken for each one.
+const REALTIME_BURST: Duration = Duration::from_millis(10);
+// Unpaced, the stream still yields to the executor now and then, so it can't starve other tasks.
+const UNPACED_YIELD_EVERY: u64 = 16000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pacing {
+    /// Samples come out as fast as a device would produce them.
+    #[default]
+    Realtime,
+    /// Samples come out as fast as they are decoded. For tests and batch processing.
+    Unpaced,
+}
+
+/// Mono samples from a recording, as a stand-in for a device.
+pub struct FileSource {
+    samples: Box<dyn Iterator<Item = f32> + Send>,
+    sample_rate: u32,
+    pacing: Pacing,
+}
+
+impl FileSource {
+    /// Decodes wav, mp3, flac or ogg. Multichannel audio is downmixed.
+    pub fn open(path: impl AsRef<Path>, pacing: Pacing) -> Result<Self, crate::Error> {
+        let file = std::fs::File::open(path)?;
+        let decoder = rodio::Decoder::new(std::io::BufReader::new(file))?;
+
+        let channels = decoder.channels().max(1) as usize;
+        let sample_rate = decoder.sample_rate();
+        let mut samples = decoder.convert_samples::<f32>();
+
+        let downmixed = std::iter::from_fn(move || {
+            let mut sum = samples.next()?;
+            for _ in 1..channels {
+                sum += samples.next().unwrap_or_default();
+            }
+            Some(sum / channels as f32)
+        });
+
+        Ok(Self {
+            samples: Box::new(downmixed),
+            sample_rate,
+            pacing,
+        })
+    }
+
+    /// Headerless pcm_s16le, mono.
+    pub fn from_pcm_s16le(data: Vec<u8>, sample_rate: u32, pacing: Pacing) -> Self {
+        let samples = (0..data.len() / 2)
+            .map(move |i| i16::from_le_bytes([data[i * 2], data[i * 2 + 1]]) as f32 / 32768.0);
+
+        Self {
+            samples: Box::new(samples),
+            sample_rate,
+            pacing,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn stream(self) -> FileStream {
+        FileStream::new(self, Arc::new(OnceLock::new()))
+    }
+}
+
+/// A mic and a speaker recording of the same session, replayed on a shared clock.
+pub struct ReplaySource {
+    mic: FileSource,
+    speaker: FileSource,
+}
+
+impl ReplaySource {
+    pub fn new(mic: FileSource, speaker: FileSource) -> Self {
+        Self { mic, speaker }
+    }
+
+    pub fn open(
+        mic: impl AsRef<Path>,
+        speaker: impl AsRef<Path>,
+        pacing: Pacing,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self::new(
+            FileSource::open(mic, pacing)?,
+            FileSource::open(speaker, pacing)?,
+        ))
+    }
+
+    /// Mic and speaker streams. In real time, both are timed from whichever is polled first.
+    pub fn streams(self) -> (FileStream, FileStream) {
+        let start = Arc::new(OnceLock::new());
+        (
+            FileStream::new(self.mic, start.clone()),
+            FileStream::new(self.speaker, start),
+        )
+    }
+}
+
+/// Needs a Tokio runtime with the timer enabled when paced in real time.
+pub struct FileStream {
+    samples: Box<dyn Iterator<Item = f32> + Send>,
+    sample_rate: u32,
+    pacing: Pacing,
+    start: Arc<OnceLock<Instant>>,
+    emitted: u64,
+    sleep: Option<Pin<Box<Sleep>>>,
+    yielded: bool,
+}
+
+impl FileStream {
+    fn new(source: FileSource, start: Arc<OnceLock<Instant>>) -> Self {
+        Self {
+            samples: source.samples,
+            sample_rate: source.sample_rate,
+            pacing: source.pacing,
+            start,
+            emitted: 0,
+            sleep: None,
+            yielded: false,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+impl Stream for FileStream {
+    type Item = f32;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        match this.pacing {
+            Pacing::Realtime => {
+                let start = *this.start.get_or_init(Instant::now);
+                let due =
+                    start + Duration::from_secs_f64(this.emitted as f64 / this.sample_rate as f64);
+
+                if due > Instant::now() + REALTIME_BURST {
+                    let sleep = this
+                        .sleep
+                        .get_or_insert_with(|| Box::pin(tokio::time::sleep_until(due)));
+                    if sleep.as_mut().poll(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                    this.sleep = None;
+                }
+            }
+            Pacing::Unpaced => {
+                if this.emitted > 0
+                    && this.emitted.is_multiple_of(UNPACED_YIELD_EVERY)
+                    && !this.yielded
+                {
+                    this.yielded = true;
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                this.yielded = false;
+            }
+        }
+
+        this.emitted += 1;
+        Poll::Ready(this.samples.next())
+    }
+}
+
+impl kalosm_sound::AsyncSource for FileStream {
+    fn as_stream(&mut self) -> impl Stream<Item = f32> + '_ {
+        self
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn test_decode_file() {
+        let source = FileSource::open(hypr_data::english_1::AUDIO_PATH, Pacing::Unpaced).unwrap();
+        assert_eq!(source.sample_rate(), 16000);
+
+        let samples = source.stream().collect::<Vec<_>>().await;
+        assert_eq!(samples.len(), hypr_data::english_1::AUDIO.len() / 2);
+        assert!(samples.iter().any(|s| *s != 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_realtime_replay() {
+        let pcm = vec![0u8; 16000];
+        let source = ReplaySource::new(
+            FileSource::from_pcm_s16le(pcm.clone(), 16000, Pacing::Realtime),
+            FileSource::from_pcm_s16le(pcm, 16000, Pacing::Realtime),
+        );
+
+        let started = std::time::Instant::now();
+        let (mic, speaker) = source.streams();
+        let (mic, speaker) = tokio::join!(mic.count(), speaker.count());
+
+        assert_eq!((mic, speaker), (8000, 8000));
+        assert!(started.elapsed() >= Duration::from_millis(450));
+    }
+}
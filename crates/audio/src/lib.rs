@@ -1,11 +1,18 @@
+mod drift;
+pub mod dsp;
 mod errors;
+mod file;
 mod mic;
 mod norm;
+mod player;
 mod speaker;
 
+pub use drift::*;
 pub use errors::*;
+pub use file::*;
 pub use mic::*;
 pub use norm::*;
+pub use player::*;
 pub use speaker::*;
 
 pub use cpal;
@@ -59,13 +66,14 @@ pub enum AudioSource {
     RealtimeMic,
     RealtimeSpeaker,
     Recorded,
+    File,
 }
 
 pub struct AudioInput {
     source: AudioSource,
     mic: Option<MicInput>,
     speaker: Option<SpeakerInput>,
-    data: Option<Vec<u8>>,
+    file: Option<FileSource>,
 }
 
 impl AudioInput {
@@ -97,7 +105,7 @@ impl AudioInput {
             source: AudioSource::RealtimeMic,
             mic: Some(mic),
             speaker: None,
-            data: None,
+            file: None,
         })
     }
 
@@ -106,24 +114,37 @@ impl AudioInput {
             source: AudioSource::RealtimeSpeaker,
             mic: None,
             speaker: Some(SpeakerInput::new().unwrap()),
-            data: None,
+            file: None,
         }
     }
 
+    /// Headerless pcm_s16le at 16kHz, replayed in real time.
     pub fn from_recording(data: Vec<u8>) -> Self {
         Self {
             source: AudioSource::Recorded,
             mic: None,
             speaker: None,
-            data: Some(data),
+            file: Some(FileSource::from_pcm_s16le(data, 16000, Pacing::Realtime)),
         }
     }
 
+    pub fn from_file(
+        path: impl AsRef<std::path::Path>,
+        pacing: Pacing,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            source: AudioSource::File,
+            mic: None,
+            speaker: None,
+            file: Some(FileSource::open(path, pacing)?),
+        })
+    }
+
     pub fn device_name(&self) -> String {
         match &self.source {
             AudioSource::RealtimeMic => self.mic.as_ref().unwrap().device_name(),
             AudioSource::RealtimeSpeaker => "TODO".to_string(),
-            AudioSource::Recorded => "TODO".to_string(),
+            AudioSource::Recorded | AudioSource::File => "TODO".to_string(),
         }
     }
 
@@ -135,9 +156,8 @@ impl AudioInput {
             AudioSource::RealtimeSpeaker => AudioStream::RealtimeSpeaker {
                 speaker: self.speaker.take().unwrap().stream().unwrap(),
             },
-            AudioSource::Recorded => AudioStream::Recorded {
-                data: self.data.as_ref().unwrap().clone(),
-                position: 0,
+            AudioSource::Recorded | AudioSource::File => AudioStream::File {
+                file: self.file.take().unwrap().stream(),
             },
         }
     }
@@ -146,7 +166,7 @@ impl AudioInput {
 pub enum AudioStream {
     RealtimeMic { mic: MicStream },
     RealtimeSpeaker { speaker: SpeakerStream },
-    Recorded { data: Vec<u8>, position: usize },
+    File { file: FileStream },
 }
 
 impl Stream for AudioStream {
@@ -162,19 +182,7 @@ impl Stream for AudioStream {
         match &mut *self {
             AudioStream::RealtimeMic { mic } => mic.poll_next_unpin(cx),
             AudioStream::RealtimeSpeaker { speaker } => speaker.poll_next_unpin(cx),
-            // assume pcm_s16le, without WAV header
-            AudioStream::Recorded { data, position } => {
-                if *position + 2 <= data.len() {
-                    let bytes = [data[*position], data[*position + 1]];
-                    let sample = i16::from_le_bytes(bytes) as f32 / 32768.0;
-                    *position += 2;
-
-                    std::thread::sleep(std::time::Duration::from_secs_f64(1.0 / 16000.0));
-                    Poll::Ready(Some(sample))
-                } else {
-                    Poll::Ready(None)
-                }
-            }
+            AudioStream::File { file } => file.poll_next_unpin(cx),
         }
     }
 }
@@ -188,7 +196,7 @@ impl kalosm_sound::AsyncSource for AudioStream {
         match self {
             AudioStream::RealtimeMic { mic } => mic.sample_rate(),
             AudioStream::RealtimeSpeaker { speaker } => speaker.sample_rate(),
-            AudioStream::Recorded { .. } => 16000,
+            AudioStream::File { file } => file.sample_rate(),
         }
     }
 }
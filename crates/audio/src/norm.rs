@@ -4,10 +4,11 @@ use std::task::{Context, Poll};
 use ebur128::{EbuR128, Mode};
 use futures_util::Stream;
 
+use crate::dsp::Limiter;
+
 const CHANNELS: u32 = 1;
 const TARGET_LUFS: f64 = -23.0;
-const TRUE_PEAK_LIMIT: f64 = -1.0;
-const LIMITER_LOOKAHEAD_MS: usize = 10;
+const TRUE_PEAK_LIMIT: f32 = -1.0;
 const ANALYZE_CHUNK_SIZE: usize = 512;
 
 pub struct NormalizedSource<S: kalosm_sound::AsyncSource> {
@@ -15,46 +16,7 @@ pub struct NormalizedSource<S: kalosm_sound::AsyncSource> {
     gain_linear: f32,
     ebur128: EbuR128,
     loudness_buffer: Vec<f32>,
-    limiter: TruePeakLimiter,
-    true_peak_limit: f32,
-}
-
-struct TruePeakLimiter {
-    lookahead_samples: usize,
-    buffer: Vec<f32>,
-    gain_reduction: Vec<f32>,
-    current_position: usize,
-}
-
-impl TruePeakLimiter {
-    fn new(sample_rate: u32) -> Self {
-        let lookahead_samples = ((sample_rate as usize * LIMITER_LOOKAHEAD_MS) / 1000).max(1);
-
-        Self {
-            lookahead_samples,
-            buffer: vec![0.0; lookahead_samples],
-            gain_reduction: vec![1.0; lookahead_samples],
-            current_position: 0,
-        }
-    }
-
-    fn process(&mut self, sample: f32, true_peak_limit: f32) -> f32 {
-        self.buffer[self.current_position] = sample;
-
-        let sample_abs = sample.abs();
-        if sample_abs > true_peak_limit {
-            let reduction = true_peak_limit / sample_abs;
-            self.gain_reduction[self.current_position] = reduction;
-        } else {
-            self.gain_reduction[self.current_position] = 1.0;
-        }
-
-        let output_position = (self.current_position + 1) % self.lookahead_samples;
-        let output_sample = self.buffer[output_position] * self.gain_reduction[output_position];
-
-        self.current_position = output_position;
-        output_sample
-    }
+    limiter: Limiter,
 }
 
 pub trait NormalizeExt<S: kalosm_sound::AsyncSource> {
@@ -67,15 +29,12 @@ impl<S: kalosm_sound::AsyncSource> NormalizeExt<S> for S {
         let ebur128 = EbuR128::new(CHANNELS, sample_rate, Mode::I | Mode::TRUE_PEAK)
             .expect("Failed to create EBU R128 analyzer");
 
-        let true_peak_limit = 10_f32.powf(TRUE_PEAK_LIMIT as f32 / 20.0);
-
         NormalizedSource {
             source: self,
             gain_linear: 1.0,
             ebur128,
             loudness_buffer: Vec::with_capacity(ANALYZE_CHUNK_SIZE),
-            limiter: TruePeakLimiter::new(sample_rate),
-            true_peak_limit,
+            limiter: Limiter::new(sample_rate, TRUE_PEAK_LIMIT),
         }
     }
 }
@@ -104,7 +63,7 @@ impl<S: kalosm_sound::AsyncSource + Unpin> Stream for NormalizedSource<S> {
                 }
 
                 let amplified = sample * this.gain_linear;
-                let limited = this.limiter.process(amplified, this.true_peak_limit);
+                let limited = this.limiter.process_sample(amplified);
 
                 Poll::Ready(Some(limited))
             }
@@ -0,0 +1,232 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use rodio::{OutputStream, Sink, Source};
+
+// How often the position is published while playing.
+const POSITION_INTERVAL: Duration = Duration::from_millis(50);
+
+pub type PlayerSource = Box<dyn Source<Item = f32> + Send>;
+
+/// Where playback is. Published on `AudioPlayer::subscribe` whenever it changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Playback {
+    pub position: Duration,
+    pub duration: Duration,
+    pub playing: bool,
+    pub rate: f32,
+}
+
+enum Command {
+    Play,
+    Pause,
+    Seek(Duration),
+    SetRate(f32),
+}
+
+/// Plays something seekable on the default output device.
+///
+/// Seeking reopens the source at the new position, so it works for any source that can be opened mid-way.
+pub struct AudioPlayer {
+    commands: mpsc::Sender<Command>,
+    playback: tokio::sync::watch::Receiver<Playback>,
+}
+
+impl AudioPlayer {
+    /// Starts paused at the beginning. `open` is called with the position to play from.
+    pub fn new<F>(duration: Duration, open: F) -> Result<Self, crate::Error>
+    where
+        F: FnMut(Duration) -> Option<PlayerSource> + Send + 'static,
+    {
+        let (commands, commands_rx) = mpsc::channel();
+        let (playback_tx, playback) = tokio::sync::watch::channel(Playback {
+            position: Duration::ZERO,
+            duration,
+            playing: false,
+            rate: 1.0,
+        });
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        // The output stream has to stay on the thread that created it.
+        std::thread::spawn(move || {
+            let (_stream, sink) = match OutputStream::try_default()
+                .map_err(crate::Error::from)
+                .and_then(|(stream, handle)| Ok((stream, Sink::try_new(&handle)?)))
+            {
+                Ok(output) => {
+                    let _ = ready_tx.send(Ok(()));
+                    output
+                }
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+
+            PlayerThread {
+                sink,
+                open: Box::new(open),
+                playback_tx,
+                loaded: None,
+            }
+            .run(commands_rx);
+        });
+
+        ready_rx
+            .recv()
+            .unwrap_or(Err(crate::Error::NoOutputDevice))?;
+
+        Ok(Self { commands, playback })
+    }
+
+    pub fn play(&self) {
+        let _ = self.commands.send(Command::Play);
+    }
+
+    pub fn pause(&self) {
+        let _ = self.commands.send(Command::Pause);
+    }
+
+    pub fn seek(&self, position: Duration) {
+        let _ = self.commands.send(Command::Seek(position));
+    }
+
+    /// Like a tape, the pitch follows the rate.
+    pub fn set_rate(&self, rate: f32) {
+        let _ = self.commands.send(Command::SetRate(rate));
+    }
+
+    pub fn playback(&self) -> Playback {
+        *self.playback.borrow()
+    }
+
+    /// Ends once the player is dropped.
+    pub fn subscribe(&self) -> tokio::sync::watch::Receiver<Playback> {
+        self.playback.clone()
+    }
+}
+
+struct Loaded {
+    from: Duration,
+    frames_per_second: f64,
+    samples: Arc<AtomicU64>,
+}
+
+impl Loaded {
+    fn position(&self) -> Duration {
+        let played = self.samples.load(Ordering::Relaxed) as f64 / self.frames_per_second;
+        self.from + Duration::from_secs_f64(played)
+    }
+}
+
+struct PlayerThread {
+    sink: Sink,
+    open: Box<dyn FnMut(Duration) -> Option<PlayerSource> + Send>,
+    playback_tx: tokio::sync::watch::Sender<Playback>,
+    loaded: Option<Loaded>,
+}
+
+impl PlayerThread {
+    fn run(mut self, commands: mpsc::Receiver<Command>) {
+        loop {
+            match commands.recv_timeout(POSITION_INTERVAL) {
+                Ok(Command::Play) => {
+                    let playback = *self.playback_tx.borrow();
+                    if self.loaded.is_none() || playback.position >= playback.duration {
+                        self.load(Duration::ZERO);
+                    }
+                    self.sink.play();
+                }
+                Ok(Command::Pause) => self.sink.pause(),
+                Ok(Command::Seek(position)) => {
+                    let playback = *self.playback_tx.borrow();
+                    self.load(position.min(playback.duration));
+                    if playback.playing {
+                        self.sink.play();
+                    }
+                }
+                Ok(Command::SetRate(rate)) => self.sink.set_speed(rate.clamp(0.25, 4.0)),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+
+            self.publish();
+        }
+
+        self.sink.stop();
+    }
+
+    // Leaves the sink paused.
+    fn load(&mut self, from: Duration) {
+        self.sink.clear();
+        self.loaded = None;
+
+        let Some(source) = (self.open)(from) else {
+            return;
+        };
+
+        let samples = Arc::new(AtomicU64::new(0));
+        self.loaded = Some(Loaded {
+            from,
+            frames_per_second: source.sample_rate() as f64 * source.channels() as f64,
+            samples: samples.clone(),
+        });
+        self.sink.append(Counted { source, samples });
+    }
+
+    fn publish(&self) {
+        self.playback_tx.send_if_modified(|playback| {
+            let ended = self.loaded.is_some() && self.sink.empty();
+
+            let next = Playback {
+                position: match &self.loaded {
+                    _ if ended => playback.duration,
+                    Some(loaded) => loaded.position().min(playback.duration),
+                    None => playback.position,
+                },
+                playing: !self.sink.is_paused() && !ended && self.loaded.is_some(),
+                rate: self.sink.speed(),
+                ..*playback
+            };
+
+            let modified = next != *playback;
+            *playback = next;
+            modified
+        });
+    }
+}
+
+// Counts samples as the output pulls them, which is close enough to what is heard.
+struct Counted {
+    source: PlayerSource,
+    samples: Arc<AtomicU64>,
+}
+
+impl Iterator for Counted {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.source.next()?;
+        self.samples.fetch_add(1, Ordering::Relaxed);
+        Some(sample)
+    }
+}
+
+impl Source for Counted {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
@@ -1,26 +1,208 @@
+use anyhow::Result;
 use futures_util::Stream;
+use std::collections::VecDeque;
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Poll, Waker};
+use std::thread;
+use std::time::Duration;
+use tracing::error;
 
-pub struct SpeakerInput {}
+use libpulse_binding::{
+    def::BufferAttr,
+    sample::{Format, Spec},
+    stream::Direction,
+};
+use libpulse_simple_binding::Simple;
+
+// Resolved by the server (PulseAudio, or PipeWire through pipewire-pulse) to the monitor of the default sink.
+const DEFAULT_MONITOR: &str = "@DEFAULT_MONITOR@";
+// The server converts from whatever the sink runs at, so this is what the stream delivers.
+const SAMPLE_RATE: u32 = 48000;
+// 10ms per read. Without a fragment size, the server may deliver seconds at a time.
+const READ_FRAMES: usize = 480;
+const MAX_QUEUED_SAMPLES: usize = 16384;
+
+pub struct SpeakerInput {
+    source: String,
+}
 
 impl SpeakerInput {
-    pub fn new(_sample_rate_override: Option<u32>) -> Self {
-        Self {}
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            source: DEFAULT_MONITOR.to_string(),
+        })
+    }
+
+    /// Records the monitor of a specific sink instead of the default one.
+    pub fn from_sink(sink: impl AsRef<str>) -> Self {
+        Self {
+            source: format!("{}.monitor", sink.as_ref()),
+        }
     }
 
-    pub fn stream(self) -> SpeakerStream {
-        SpeakerStream::new()
+    /// Fails if the server can't be reached, or has no such source.
+    pub fn stream(self) -> Result<SpeakerStream> {
+        let sample_queue = Arc::new(Mutex::new(VecDeque::new()));
+        let waker_state = Arc::new(Mutex::new(WakerState {
+            waker: None,
+            has_data: false,
+            shutdown: false,
+        }));
+
+        let queue_clone = sample_queue.clone();
+        let waker_clone = waker_state.clone();
+        let (init_tx, init_rx) = mpsc::channel();
+
+        let capture_thread = thread::spawn(move || {
+            if let Err(e) =
+                SpeakerStream::capture_audio_loop(self.source, queue_clone, waker_clone, init_tx)
+            {
+                error!("Audio capture loop failed: {}", e);
+            }
+        });
+
+        // Dropping it stops the capture thread, if it is still connecting.
+        let stream = SpeakerStream {
+            sample_queue,
+            waker_state,
+            capture_thread: Some(capture_thread),
+        };
+
+        match init_rx.recv_timeout(Duration::from_secs(5)) {
+            Ok(Ok(())) => Ok(stream),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(anyhow::anyhow!("timed out connecting to the audio server")),
+        }
     }
 }
 
-pub struct SpeakerStream {}
+struct WakerState {
+    waker: Option<Waker>,
+    has_data: bool,
+    shutdown: bool,
+}
+
+pub struct SpeakerStream {
+    sample_queue: Arc<Mutex<VecDeque<f32>>>,
+    waker_state: Arc<Mutex<WakerState>>,
+    capture_thread: Option<thread::JoinHandle<()>>,
+}
 
 impl SpeakerStream {
-    pub fn new() -> Self {
-        Self {}
+    pub fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
     }
 
-    pub fn sample_rate(&self) -> u32 {
-        16000
+    fn capture_audio_loop(
+        source: String,
+        sample_queue: Arc<Mutex<VecDeque<f32>>>,
+        waker_state: Arc<Mutex<WakerState>>,
+        init_tx: mpsc::Sender<Result<()>>,
+    ) -> Result<()> {
+        let spec = Spec {
+            format: Format::F32le,
+            channels: 1,
+            rate: SAMPLE_RATE,
+        };
+
+        let bytes_per_read = READ_FRAMES * std::mem::size_of::<f32>();
+        let attr = BufferAttr {
+            maxlength: u32::MAX,
+            tlength: u32::MAX,
+            prebuf: u32::MAX,
+            minreq: u32::MAX,
+            fragsize: bytes_per_read as u32,
+        };
+
+        let simple = match Simple::new(
+            None,
+            "Hyprnote",
+            Direction::Record,
+            Some(&source),
+            "System audio",
+            &spec,
+            None,
+            Some(&attr),
+        ) {
+            Ok(simple) => {
+                let _ = init_tx.send(Ok(()));
+                simple
+            }
+            Err(e) => {
+                let _ = init_tx.send(Err(anyhow::anyhow!("{}: {}", source, e)));
+                Self::shutdown(&waker_state);
+                return Ok(());
+            }
+        };
+
+        let mut buffer = vec![0u8; bytes_per_read];
+
+        loop {
+            {
+                let state = waker_state.lock().unwrap();
+                if state.shutdown {
+                    break;
+                }
+            }
+
+            if let Err(e) = simple.read(&mut buffer) {
+                error!("Failed to read audio data: {}", e);
+                Self::shutdown(&waker_state);
+                break;
+            }
+
+            {
+                let mut queue = sample_queue.lock().unwrap();
+                queue.extend(
+                    buffer
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+                );
+
+                let len = queue.len();
+                if len > MAX_QUEUED_SAMPLES {
+                    queue.drain(0..(len - MAX_QUEUED_SAMPLES));
+                }
+            }
+
+            {
+                let mut state = waker_state.lock().unwrap();
+                if !state.has_data {
+                    state.has_data = true;
+                    if let Some(waker) = state.waker.take() {
+                        drop(state);
+                        waker.wake();
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Ends the stream, so the consumer doesn't wait forever on a server that went away.
+    fn shutdown(waker_state: &Mutex<WakerState>) {
+        let mut state = waker_state.lock().unwrap();
+        state.shutdown = true;
+        if let Some(waker) = state.waker.take() {
+            drop(state);
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for SpeakerStream {
+    fn drop(&mut self) {
+        {
+            let mut state = self.waker_state.lock().unwrap();
+            state.shutdown = true;
+        }
+
+        if let Some(thread) = self.capture_thread.take() {
+            if let Err(e) = thread.join() {
+                error!("Failed to join capture thread: {:?}", e);
+            }
+        }
     }
 }
 
@@ -29,8 +211,31 @@ impl Stream for SpeakerStream {
 
     fn poll_next(
         self: std::pin::Pin<&mut Self>,
-        _cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Option<Self::Item>> {
-        std::task::Poll::Ready(Some(0.0))
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        {
+            let mut queue = self.sample_queue.lock().unwrap();
+            if let Some(sample) = queue.pop_front() {
+                return Poll::Ready(Some(sample));
+            }
+        }
+
+        {
+            let mut state = self.waker_state.lock().unwrap();
+            if state.shutdown {
+                return Poll::Ready(None);
+            }
+            state.has_data = false;
+            state.waker = Some(cx.waker().clone());
+            drop(state);
+        }
+
+        {
+            let mut queue = self.sample_queue.lock().unwrap();
+            match queue.pop_front() {
+                Some(sample) => Poll::Ready(Some(sample)),
+                None => Poll::Pending,
+            }
+        }
     }
 }
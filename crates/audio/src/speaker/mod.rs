@@ -29,13 +29,20 @@ pub struct SpeakerInput {
 }
 
 impl SpeakerInput {
-    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
     pub fn new() -> Result<Self> {
         let inner = PlatformSpeakerInput::new()?;
         Ok(Self { inner })
     }
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    /// Records what is played to `sink`, rather than to the default output.
+    #[cfg(target_os = "linux")]
+    pub fn from_sink(sink: impl AsRef<str>) -> Self {
+        let inner = PlatformSpeakerInput::from_sink(sink);
+        Self { inner }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
     pub fn new() -> Result<Self> {
         Err(anyhow::anyhow!(
             "'SpeakerInput::new' is not supported on this platform"
@@ -48,7 +55,13 @@ impl SpeakerInput {
         Ok(SpeakerStream { inner })
     }
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[cfg(target_os = "linux")]
+    pub fn stream(self) -> Result<SpeakerStream> {
+        let inner = self.inner.stream()?;
+        Ok(SpeakerStream { inner })
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
     pub fn stream(self) -> Result<SpeakerStream> {
         Err(anyhow::anyhow!(
             "'SpeakerInput::stream' is not supported on this platform"
@@ -68,12 +81,12 @@ impl Stream for SpeakerStream {
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
-        #[cfg(any(target_os = "macos", target_os = "windows"))]
+        #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
         {
             self.inner.poll_next_unpin(cx)
         }
 
-        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
         {
             std::task::Poll::Pending
         }
@@ -85,12 +98,12 @@ impl kalosm_sound::AsyncSource for SpeakerStream {
         self
     }
 
-    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
     fn sample_rate(&self) -> u32 {
         self.inner.sample_rate()
     }
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
     fn sample_rate(&self) -> u32 {
         0
     }
@@ -150,6 +163,76 @@ mod tests {
         assert!(buffer.iter().any(|x| *x != 0.0));
     }
 
+    // Plays into a null sink, so it runs without sound hardware and doesn't reach real speakers.
+    // Needs a PulseAudio or PipeWire server.
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_linux() {
+        use kalosm_sound::AsyncSource;
+        use libpulse_binding::{
+            sample::{Format, Spec},
+            stream::Direction,
+        };
+        use libpulse_simple_binding::Simple;
+        use std::process::Command;
+
+        const SINK: &str = "hypr_test_sink";
+
+        let output = Command::new("pactl")
+            .args(["load-module", "module-null-sink"])
+            .arg(format!("sink_name={}", SINK))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let module = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        let mut stream = SpeakerInput::from_sink(SINK).stream().unwrap();
+        assert_eq!(stream.sample_rate(), 48000);
+
+        let handle = std::thread::spawn(|| {
+            let spec = Spec {
+                format: Format::F32le,
+                channels: 1,
+                rate: 48000,
+            };
+            let playback = Simple::new(
+                None,
+                "hypr-test",
+                Direction::Playback,
+                Some(SINK),
+                "sine",
+                &spec,
+                None,
+                None,
+            )
+            .unwrap();
+
+            let bytes = (0..48000 * 2)
+                .map(|i| (i as f32 * 440.0 * 2.0 * std::f32::consts::PI / 48000.0).sin() * 0.1)
+                .flat_map(f32::to_le_bytes)
+                .collect::<Vec<_>>();
+            playback.write(&bytes).unwrap();
+            playback.drain().unwrap();
+        });
+
+        let mut buffer = Vec::new();
+        while let Some(sample) = stream.next().await {
+            buffer.push(sample);
+            if buffer.len() > 48000 {
+                break;
+            }
+        }
+
+        handle.join().unwrap();
+        let _ = Command::new("pactl")
+            .args(["unload-module", &module])
+            .status();
+
+        assert!(buffer.iter().any(|x| *x != 0.0));
+    }
+
     #[cfg(target_os = "windows")]
     #[tokio::test]
     #[serial]
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+// Whisper sees 30 seconds at a time, and padding comes on top of the speech.
+const DEFAULT_MAX_SPEECH_TIME: Duration = Duration::from_secs(25);
+// Silero's own gap between the two thresholds, so speech doesn't flicker on and off around one value.
+const THRESHOLD_HYSTERESIS: f32 = 0.15;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VadChunkConfig {
+    /// Speech probability above which a frame counts as speech.
+    pub positive_speech_threshold: f32,
+    /// How long speech has to stay away before a segment ends.
+    pub redemption_time: Duration,
+    /// Audio kept from before the speech started.
+    pub pre_speech_pad: Duration,
+    /// Audio kept from after the speech ended.
+    pub post_speech_pad: Duration,
+    /// Shorter segments are dropped.
+    pub min_speech_time: Duration,
+    /// Longer segments are split at their quietest point. `None` never splits.
+    pub max_speech_time: Option<Duration>,
+}
+
+impl Default for VadChunkConfig {
+    fn default() -> Self {
+        Self::from_redemption_time(Duration::from_millis(500))
+    }
+}
+
+impl VadChunkConfig {
+    pub fn from_redemption_time(redemption_time: Duration) -> Self {
+        Self {
+            positive_speech_threshold: 0.5,
+            redemption_time,
+            pre_speech_pad: redemption_time,
+            post_speech_pad: Duration::ZERO,
+            min_speech_time: Duration::from_millis(50),
+            max_speech_time: Some(DEFAULT_MAX_SPEECH_TIME),
+        }
+    }
+
+    pub(crate) fn to_silero(self, sample_rate: usize) -> silero_rs::VadConfig {
+        silero_rs::VadConfig {
+            positive_speech_threshold: self.positive_speech_threshold,
+            negative_speech_threshold: (self.positive_speech_threshold - THRESHOLD_HYSTERESIS)
+                .max(0.01),
+            pre_speech_pad: self.pre_speech_pad,
+            post_speech_pad: self.post_speech_pad,
+            redemption_time: self.redemption_time,
+            sample_rate,
+            min_speech_time: self.min_speech_time,
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::VecDeque,
     pin::Pin,
     task::{Context, Poll},
     time::Duration,
@@ -7,10 +8,15 @@ use std::{
 use futures_util::Stream;
 use kalosm_sound::AsyncSource;
 
-use silero_rs::{VadConfig, VadSession, VadTransition};
+use silero_rs::{VadSession, VadTransition};
 
+mod config;
 mod error;
+mod tracker;
+
+pub use config::*;
 use error::*;
+pub use tracker::*;
 
 pub struct ChunkStream<S: AsyncSource> {
     source: S,
@@ -69,14 +75,13 @@ pub trait VadExt: AsyncSource + Sized {
     where
         Self: Unpin,
     {
-        let config = VadConfig {
-            redemption_time,
-            pre_speech_pad: redemption_time,
-            post_speech_pad: Duration::from_millis(0),
-            min_speech_time: Duration::from_millis(50),
-            ..Default::default()
-        };
+        self.vad_chunks_with(VadChunkConfig::from_redemption_time(redemption_time))
+    }
 
+    fn vad_chunks_with(self, config: VadChunkConfig) -> VadChunkStream<Self>
+    where
+        Self: Unpin,
+    {
         VadChunkStream::new(self, config).unwrap()
     }
 }
@@ -86,24 +91,88 @@ impl<T: AsyncSource> VadExt for T {}
 pub struct VadChunkStream<S: AsyncSource> {
     chunk_stream: ChunkStream<S>,
     vad_session: VadSession,
-    pending_chunks: Vec<AudioChunk>,
+    max_speech_samples: Option<usize>,
+    // Samples of the ongoing speech that already went out in a forced split.
+    emitted_samples: usize,
+    pending_chunks: VecDeque<AudioChunk>,
+    finished: bool,
 }
 
 impl<S: AsyncSource> VadChunkStream<S> {
-    fn new(source: S, mut config: VadConfig) -> Result<Self, Error> {
-        config.sample_rate = source.sample_rate() as usize;
+    fn new(source: S, config: VadChunkConfig) -> Result<Self, Error> {
+        let sample_rate = source.sample_rate() as usize;
 
         // https://github.com/emotechlab/silero-rs/blob/26a6460/src/lib.rs#L775
         let chunk_duration = Duration::from_millis(30);
 
+        let max_speech_samples = config
+            .max_speech_time
+            .map(|max| (max.as_secs_f64() * sample_rate as f64) as usize);
+
         Ok(Self {
             chunk_stream: ChunkStream::new(source, chunk_duration),
-            vad_session: VadSession::new(config).map_err(|_| Error::VadSessionCreationFailed)?,
-            pending_chunks: Vec::new(),
+            vad_session: VadSession::new(config.to_silero(sample_rate))
+                .map_err(|_| Error::VadSessionCreationFailed)?,
+            max_speech_samples,
+            emitted_samples: 0,
+            pending_chunks: VecDeque::new(),
+            finished: false,
+        })
+    }
+
+    // Cuts the ongoing speech once it reaches the maximum length, at the quietest point of its second half.
+    fn split_long_speech(&mut self) -> Option<AudioChunk> {
+        let max = self.max_speech_samples?;
+        if !self.vad_session.is_speaking() {
+            return None;
+        }
+
+        let speech = self.vad_session.get_current_speech();
+        let pending = speech.get(self.emitted_samples..)?;
+        if pending.len() < max {
+            return None;
+        }
+
+        let at = quietest_point(&pending[..max], max / 2, self.chunk_stream.chunk_samples);
+        let samples = pending[..at].to_vec();
+        self.emitted_samples += at;
+
+        Some(AudioChunk { samples })
+    }
+
+    // The part of a finished speech segment that was not split off already.
+    fn remainder(&mut self, samples: &[f32]) -> Option<AudioChunk> {
+        let rest = samples.get(self.emitted_samples..).unwrap_or_default();
+        self.emitted_samples = 0;
+
+        (!rest.is_empty()).then(|| AudioChunk {
+            samples: rest.to_vec(),
         })
     }
 }
 
+// Middle of the quietest frame that starts at or after `from`.
+fn quietest_point(samples: &[f32], from: usize, frame: usize) -> usize {
+    let frame = frame.max(1);
+    if samples.len() < from + frame {
+        return samples.len();
+    }
+
+    let energy = |start: usize| {
+        samples[start..start + frame]
+            .iter()
+            .map(|s| s * s)
+            .sum::<f32>()
+    };
+
+    let start = (from..=samples.len() - frame)
+        .step_by((frame / 2).max(1))
+        .min_by(|a, b| energy(*a).total_cmp(&energy(*b)))
+        .unwrap_or(from);
+
+    start + frame / 2
+}
+
 #[derive(Debug, Clone)]
 pub struct AudioChunk {
     pub samples: Vec<f32>,
@@ -115,21 +184,31 @@ impl<S: AsyncSource + Unpin> Stream for VadChunkStream<S> {
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.get_mut();
 
-        if let Some(chunk) = this.pending_chunks.pop() {
+        if let Some(chunk) = this.pending_chunks.pop_front() {
             return Poll::Ready(Some(Ok(chunk)));
         }
 
+        if this.finished {
+            return Poll::Ready(None);
+        }
+
         loop {
             match Pin::new(&mut this.chunk_stream).poll_next(cx) {
                 Poll::Ready(Some(samples)) => match this.vad_session.process(&samples) {
                     Ok(transitions) => {
                         for transition in transitions {
                             if let VadTransition::SpeechEnd { samples, .. } = transition {
-                                this.pending_chunks.push(AudioChunk { samples });
+                                if let Some(chunk) = this.remainder(&samples) {
+                                    this.pending_chunks.push_back(chunk);
+                                }
                             }
                         }
 
-                        if let Some(chunk) = this.pending_chunks.pop() {
+                        if let Some(chunk) = this.split_long_speech() {
+                            this.pending_chunks.push_back(chunk);
+                        }
+
+                        if let Some(chunk) = this.pending_chunks.pop_front() {
                             return Poll::Ready(Some(Ok(chunk)));
                         }
                     }
@@ -138,9 +217,143 @@ impl<S: AsyncSource + Unpin> Stream for VadChunkStream<S> {
                         return Poll::Ready(Some(Err(error)));
                     }
                 },
-                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(None) => {
+                    this.finished = true;
+
+                    // The source ended mid-utterance, so no `SpeechEnd` will ever come for it.
+                    if this.vad_session.is_speaking() {
+                        let samples = this.vad_session.get_current_speech().to_vec();
+                        if let Some(chunk) = this.remainder(&samples) {
+                            return Poll::Ready(Some(Ok(chunk)));
+                        }
+                    }
+
+                    return Poll::Ready(None);
+                }
                 Poll::Pending => return Poll::Pending,
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    const SAMPLE_RATE: u32 = 16000;
+
+    struct Samples(Vec<f32>);
+
+    impl AsyncSource for Samples {
+        fn as_stream(&mut self) -> impl Stream<Item = f32> + '_ {
+            futures_util::stream::iter(self.0.drain(..))
+        }
+
+        fn sample_rate(&self) -> u32 {
+            SAMPLE_RATE
+        }
+    }
+
+    fn fixture() -> Samples {
+        Samples(
+            hypr_data::english_1::AUDIO
+                .chunks_exact(2)
+                .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / 32768.0)
+                .collect(),
+        )
+    }
+
+    async fn chunk_lengths(config: VadChunkConfig) -> Vec<usize> {
+        fixture()
+            .vad_chunks_with(config)
+            .map(|chunk| chunk.unwrap().samples.len())
+            .collect()
+            .await
+    }
+
+    #[test]
+    fn test_quietest_point() {
+        let mut samples = vec![0.5; 1000];
+        samples[700..720].fill(0.0);
+
+        assert_eq!(quietest_point(&samples, 500, 20), 710);
+        assert_eq!(quietest_point(&samples, 990, 20), 1000);
+    }
+
+    #[tokio::test]
+    async fn test_max_speech_time() {
+        let unsplit = chunk_lengths(VadChunkConfig {
+            max_speech_time: None,
+            ..Default::default()
+        })
+        .await;
+        assert!(!unsplit.is_empty());
+
+        let max_speech_time = Duration::from_secs(3);
+        let split = chunk_lengths(VadChunkConfig {
+            max_speech_time: Some(max_speech_time),
+            ..Default::default()
+        })
+        .await;
+
+        assert!(split.len() > unsplit.len());
+
+        // The same speech, give or take what a segment end trims off after a split.
+        let (split_total, unsplit_total) = (
+            split.iter().sum::<usize>() as f64,
+            unsplit.iter().sum::<usize>() as f64,
+        );
+        assert!((split_total / unsplit_total - 1.0).abs() < 0.1);
+
+        // A segment can run one frame past the limit before it is split.
+        let max_samples = (max_speech_time + Duration::from_millis(30)).as_millis() as usize
+            * SAMPLE_RATE as usize
+            / 1000;
+        assert!(split.iter().all(|len| *len <= max_samples), "{split:?}");
+    }
+
+    #[tokio::test]
+    async fn test_speech_threshold() {
+        let lenient = chunk_lengths(VadChunkConfig {
+            positive_speech_threshold: 0.2,
+            ..Default::default()
+        })
+        .await;
+        let strict = chunk_lengths(VadChunkConfig {
+            positive_speech_threshold: 0.9,
+            ..Default::default()
+        })
+        .await;
+
+        assert!(strict.iter().sum::<usize>() < lenient.iter().sum::<usize>());
+    }
+
+    #[test]
+    fn test_speech_tracker() {
+        let samples = fixture().0;
+        let duration = Duration::from_secs_f64(samples.len() as f64 / SAMPLE_RATE as f64);
+
+        let mut tracker = SpeechTracker::new(
+            VadChunkConfig {
+                pre_speech_pad: Duration::ZERO,
+                ..Default::default()
+            },
+            SAMPLE_RATE,
+        )
+        .unwrap();
+
+        let mut spans = samples
+            .chunks(512)
+            .flat_map(|block| tracker.process(block).unwrap())
+            .collect::<Vec<_>>();
+        spans.extend(tracker.finish());
+
+        assert!(!spans.is_empty());
+        assert!(spans.iter().all(|s| s.start < s.end && s.end <= duration));
+        assert!(
+            spans.windows(2).all(|w| w[0].end <= w[1].start),
+            "{spans:?}"
+        );
+    }
+}
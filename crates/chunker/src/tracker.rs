@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use silero_rs::{VadSession, VadTransition};
+
+use crate::{Error, VadChunkConfig};
+
+/// Where speech was, in time since the first sample.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeechSpan {
+    pub start: Duration,
+    pub end: Duration,
+}
+
+/// Follows VAD transitions on a stream of samples, without keeping any audio for the caller.
+pub struct SpeechTracker {
+    vad_session: VadSession,
+    sample_rate: usize,
+    processed_samples: usize,
+    speech_start: Option<Duration>,
+}
+
+impl SpeechTracker {
+    pub fn new(config: VadChunkConfig, sample_rate: u32) -> Result<Self, Error> {
+        let sample_rate = sample_rate as usize;
+
+        Ok(Self {
+            vad_session: VadSession::new(config.to_silero(sample_rate))
+                .map_err(|_| Error::VadSessionCreationFailed)?,
+            sample_rate,
+            processed_samples: 0,
+            speech_start: None,
+        })
+    }
+
+    /// Speech that ended within `samples`.
+    pub fn process(&mut self, samples: &[f32]) -> Result<Vec<SpeechSpan>, Error> {
+        let transitions = self
+            .vad_session
+            .process(samples)
+            .map_err(|e| Error::VadProcessingFailed(e.to_string()))?;
+        self.processed_samples += samples.len();
+
+        let mut spans = Vec::new();
+        for transition in transitions {
+            match transition {
+                VadTransition::SpeechStart { timestamp_ms } => {
+                    self.speech_start = Some(Duration::from_millis(timestamp_ms as u64));
+                }
+                VadTransition::SpeechEnd {
+                    start_timestamp_ms,
+                    end_timestamp_ms,
+                    ..
+                } => {
+                    self.speech_start = None;
+                    spans.push(SpeechSpan {
+                        start: Duration::from_millis(start_timestamp_ms as u64),
+                        end: Duration::from_millis(end_timestamp_ms as u64),
+                    });
+                }
+            }
+        }
+
+        Ok(spans)
+    }
+
+    /// Closes the speech that is still going on, if any.
+    pub fn finish(&mut self) -> Option<SpeechSpan> {
+        let start = self.speech_start.take()?;
+        let end = Duration::from_secs_f64(self.processed_samples as f64 / self.sample_rate as f64);
+
+        Some(SpeechSpan { start, end })
+    }
+}
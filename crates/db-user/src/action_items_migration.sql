@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS action_items (
+  id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL,
+  created_at TEXT NOT NULL,
+  text TEXT NOT NULL,
+  owner_id TEXT,
+  due_date TEXT,
+  completed_at TEXT,
+  start_word INTEGER,
+  end_word INTEGER,
+  start_ms INTEGER,
+  end_ms INTEGER,
+  FOREIGN KEY (session_id) REFERENCES sessions(id),
+  FOREIGN KEY (owner_id) REFERENCES humans(id)
+);
@@ -0,0 +1,209 @@
+use super::{ActionItem, UserDatabase};
+
+impl UserDatabase {
+    pub async fn upsert_action_item(&self, item: ActionItem) -> Result<ActionItem, crate::Error> {
+        let conn = self.conn()?;
+
+        let mut rows = conn
+            .query(
+                "INSERT OR REPLACE INTO action_items (
+                    id,
+                    session_id,
+                    created_at,
+                    text,
+                    owner_id,
+                    due_date,
+                    completed_at,
+                    start_word,
+                    end_word,
+                    start_ms,
+                    end_ms
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                RETURNING *",
+                libsql::params![
+                    item.id,
+                    item.session_id,
+                    item.created_at.to_rfc3339(),
+                    item.text,
+                    item.owner_id,
+                    item.due_date.map(|dt| dt.to_rfc3339()),
+                    item.completed_at.map(|dt| dt.to_rfc3339()),
+                    item.start_word,
+                    item.end_word,
+                    item.start_ms.map(|v| v as i64),
+                    item.end_ms.map(|v| v as i64),
+                ],
+            )
+            .await?;
+
+        let row = rows.next().await?.unwrap();
+        let item: ActionItem = libsql::de::from_row(&row)?;
+        Ok(item)
+    }
+
+    pub async fn set_action_item_completed(
+        &self,
+        id: impl Into<String>,
+        completed: bool,
+    ) -> Result<(), crate::Error> {
+        let conn = self.conn()?;
+
+        conn.execute(
+            "UPDATE action_items SET completed_at = ? WHERE id = ?",
+            (
+                completed.then(|| chrono::Utc::now().to_rfc3339()),
+                id.into(),
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_action_item(&self, id: impl Into<String>) -> Result<(), crate::Error> {
+        let conn = self.conn()?;
+
+        conn.execute("DELETE FROM action_items WHERE id = ?", vec![id.into()])
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_session_action_items(
+        &self,
+        session_id: impl Into<String>,
+    ) -> Result<Vec<ActionItem>, crate::Error> {
+        let conn = self.conn()?;
+
+        let mut rows = conn
+            .query(
+                "SELECT * FROM action_items
+                WHERE session_id = ?
+                ORDER BY start_ms IS NULL, start_ms ASC, created_at ASC",
+                vec![session_id.into()],
+            )
+            .await?;
+
+        let mut items = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let item: ActionItem = libsql::de::from_row(&row)?;
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    /// Open action items across all sessions, soonest due first. Optionally only those of one owner.
+    pub async fn list_open_action_items(
+        &self,
+        owner_id: Option<String>,
+    ) -> Result<Vec<ActionItem>, crate::Error> {
+        let conn = self.conn()?;
+
+        let mut rows = conn
+            .query(
+                "SELECT * FROM action_items
+                WHERE completed_at IS NULL
+                    AND (?1 IS NULL OR owner_id = ?1)
+                ORDER BY due_date IS NULL, due_date ASC, created_at DESC",
+                vec![owner_id],
+            )
+            .await?;
+
+        let mut items = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let item: ActionItem = libsql::de::from_row(&row)?;
+            items.push(item);
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::setup_db, ActionItem, Human, Session};
+
+    #[tokio::test]
+    async fn test_action_items() {
+        let db = setup_db().await;
+
+        let user = db
+            .upsert_human(Human {
+                full_name: Some("John Doe".to_string()),
+                ..Human::default()
+            })
+            .await
+            .unwrap();
+
+        let session = db
+            .upsert_session(Session {
+                id: uuid::Uuid::new_v4().to_string(),
+                user_id: user.id.clone(),
+                created_at: chrono::Utc::now(),
+                visited_at: chrono::Utc::now(),
+                calendar_event_id: None,
+                title: "Test Session".to_string(),
+                raw_memo_html: "".to_string(),
+                enhanced_memo_html: None,
+                conversations: vec![],
+                words: vec![],
+                record_start: None,
+                record_end: None,
+                pre_meeting_memo_html: None,
+            })
+            .await
+            .unwrap();
+
+        let item = db
+            .upsert_action_item(ActionItem {
+                id: uuid::Uuid::new_v4().to_string(),
+                session_id: session.id.clone(),
+                created_at: chrono::Utc::now(),
+                text: "Send the budget".to_string(),
+                owner_id: Some(user.id.clone()),
+                due_date: None,
+                completed_at: None,
+                start_word: Some(3),
+                end_word: Some(8),
+                start_ms: Some(1200),
+                end_ms: Some(3400),
+            })
+            .await
+            .unwrap();
+        assert_eq!(item.start_ms, Some(1200));
+
+        assert_eq!(
+            db.list_session_action_items(&session.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            db.list_open_action_items(Some(user.id.clone()))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            db.list_open_action_items(Some("someone-else".to_string()))
+                .await
+                .unwrap()
+                .len(),
+            0
+        );
+
+        db.set_action_item_completed(&item.id, true).await.unwrap();
+        assert_eq!(db.list_open_action_items(None).await.unwrap().len(), 0);
+
+        let items = db.list_session_action_items(&session.id).await.unwrap();
+        assert!(items[0].completed_at.is_some());
+
+        db.delete_session(&session.id).await.unwrap();
+        assert_eq!(
+            db.list_session_action_items(&session.id)
+                .await
+                .unwrap()
+                .len(),
+            0
+        );
+    }
+}
@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+
+use crate::user_common_derives;
+
+user_common_derives! {
+    pub struct ActionItem {
+        pub id: String,
+        pub session_id: String,
+        pub created_at: DateTime<Utc>,
+        pub text: String,
+        /// The `Human` responsible for it, if known.
+        pub owner_id: Option<String>,
+        pub due_date: Option<DateTime<Utc>>,
+        /// Open while `None`.
+        pub completed_at: Option<DateTime<Utc>>,
+        /// Where it came from in `Session::words`. `end_word` is exclusive.
+        pub start_word: Option<u32>,
+        pub end_word: Option<u32>,
+        pub start_ms: Option<u64>,
+        pub end_ms: Option<u64>,
+    }
+}
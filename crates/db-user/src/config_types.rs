@@ -45,6 +45,44 @@ user_common_derives! {
         pub telemetry_consent: bool,
         pub save_recordings: Option<bool>,
         pub selected_template_id: Option<String>,
+        #[serde(default)]
+        pub recording_format: Option<RecordingFormat>,
+        #[serde(default)]
+        pub save_multitrack: Option<bool>,
+        #[serde(default)]
+        pub audio_processing: Option<ConfigAudioProcessing>,
+    }
+}
+
+user_common_derives! {
+    #[derive(Default)]
+    pub struct ConfigAudioProcessing {
+        pub mic_gain: Option<f32>,
+        pub speaker_gain: Option<f32>,
+        pub noise_suppression: Option<bool>,
+        pub gain_control: Option<GainControlMode>,
+    }
+}
+
+user_common_derives! {
+    pub enum GainControlMode {
+        #[serde(rename = "off")]
+        Off,
+        #[serde(rename = "agc")]
+        Agc,
+        #[serde(rename = "loudness")]
+        Loudness,
+    }
+}
+
+user_common_derives! {
+    pub enum RecordingFormat {
+        #[serde(rename = "wav")]
+        Wav,
+        #[serde(rename = "flac")]
+        Flac,
+        #[serde(rename = "opus")]
+        Opus,
     }
 }
 
@@ -58,6 +96,9 @@ impl Default for ConfigGeneral {
             telemetry_consent: true,
             save_recordings: Some(false),
             selected_template_id: None,
+            recording_format: None,
+            save_multitrack: None,
+            audio_processing: None,
         }
     }
 }
@@ -87,6 +128,20 @@ user_common_derives! {
         pub api_key: Option<String>,
         pub ai_specificity: Option<u8>,
         pub redemption_time_ms: Option<u32>,
+        #[serde(default)]
+        pub vad: Option<ConfigVad>,
+    }
+}
+
+user_common_derives! {
+    #[derive(Default)]
+    pub struct ConfigVad {
+        pub threshold: Option<f32>,
+        pub pre_speech_pad_ms: Option<u32>,
+        pub post_speech_pad_ms: Option<u32>,
+        pub min_speech_ms: Option<u32>,
+        /// `0` never splits long speech.
+        pub max_speech_ms: Option<u32>,
     }
 }
 
@@ -97,6 +152,7 @@ impl Default for ConfigAI {
             api_key: None,
             ai_specificity: Some(3),
             redemption_time_ms: Some(500),
+            vad: None,
         }
     }
 }
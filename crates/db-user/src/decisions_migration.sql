@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS decisions (
+  id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL,
+  created_at TEXT NOT NULL,
+  text TEXT NOT NULL,
+  start_word INTEGER,
+  end_word INTEGER,
+  start_ms INTEGER,
+  end_ms INTEGER,
+  FOREIGN KEY (session_id) REFERENCES sessions(id)
+);
@@ -0,0 +1,68 @@
+use super::{Decision, UserDatabase};
+
+impl UserDatabase {
+    pub async fn upsert_decision(&self, decision: Decision) -> Result<Decision, crate::Error> {
+        let conn = self.conn()?;
+
+        let mut rows = conn
+            .query(
+                "INSERT OR REPLACE INTO decisions (
+                    id,
+                    session_id,
+                    created_at,
+                    text,
+                    start_word,
+                    end_word,
+                    start_ms,
+                    end_ms
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                RETURNING *",
+                libsql::params![
+                    decision.id,
+                    decision.session_id,
+                    decision.created_at.to_rfc3339(),
+                    decision.text,
+                    decision.start_word,
+                    decision.end_word,
+                    decision.start_ms.map(|v| v as i64),
+                    decision.end_ms.map(|v| v as i64),
+                ],
+            )
+            .await?;
+
+        let row = rows.next().await?.unwrap();
+        let decision: Decision = libsql::de::from_row(&row)?;
+        Ok(decision)
+    }
+
+    pub async fn delete_decision(&self, id: impl Into<String>) -> Result<(), crate::Error> {
+        let conn = self.conn()?;
+
+        conn.execute("DELETE FROM decisions WHERE id = ?", vec![id.into()])
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_session_decisions(
+        &self,
+        session_id: impl Into<String>,
+    ) -> Result<Vec<Decision>, crate::Error> {
+        let conn = self.conn()?;
+
+        let mut rows = conn
+            .query(
+                "SELECT * FROM decisions
+                WHERE session_id = ?
+                ORDER BY start_ms IS NULL, start_ms ASC, created_at ASC",
+                vec![session_id.into()],
+            )
+            .await?;
+
+        let mut items = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let item: Decision = libsql::de::from_row(&row)?;
+            items.push(item);
+        }
+        Ok(items)
+    }
+}
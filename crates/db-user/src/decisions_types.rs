@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+
+use crate::user_common_derives;
+
+user_common_derives! {
+    pub struct Decision {
+        pub id: String,
+        pub session_id: String,
+        pub created_at: DateTime<Utc>,
+        pub text: String,
+        /// Where it came from in `Session::words`. `end_word` is exclusive.
+        pub start_word: Option<u32>,
+        pub end_word: Option<u32>,
+        pub start_ms: Option<u64>,
+        pub end_ms: Option<u64>,
+    }
+}
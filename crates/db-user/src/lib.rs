@@ -1,3 +1,5 @@
+mod action_items_ops;
+mod action_items_types;
 mod calendars_ops;
 mod calendars_types;
 mod chat_groups_ops;
@@ -6,6 +8,8 @@ mod chat_messages_ops;
 mod chat_messages_types;
 mod config_ops;
 mod config_types;
+mod decisions_ops;
+mod decisions_types;
 mod events_ops;
 mod events_types;
 mod extensions_ops;
@@ -14,13 +18,21 @@ mod humans_ops;
 mod humans_types;
 mod organizations_ops;
 mod organizations_types;
+mod session_chunks_ops;
+mod session_chunks_types;
 mod sessions_ops;
 mod sessions_types;
+mod speech_segments_ops;
+mod speech_segments_types;
 mod tags_ops;
 mod tags_types;
 mod templates_ops;
 mod templates_types;
 
+#[allow(unused)]
+pub use action_items_ops::*;
+#[allow(unused)]
+pub use action_items_types::*;
 #[allow(unused)]
 pub use calendars_ops::*;
 #[allow(unused)]
@@ -38,6 +50,10 @@ pub use config_ops::*;
 #[allow(unused)]
 pub use config_types::*;
 #[allow(unused)]
+pub use decisions_ops::*;
+#[allow(unused)]
+pub use decisions_types::*;
+#[allow(unused)]
 pub use events_ops::*;
 #[allow(unused)]
 pub use events_types::*;
@@ -54,10 +70,18 @@ pub use organizations_ops::*;
 #[allow(unused)]
 pub use organizations_types::*;
 #[allow(unused)]
+pub use session_chunks_ops::*;
+#[allow(unused)]
+pub use session_chunks_types::*;
+#[allow(unused)]
 pub use sessions_ops::*;
 #[allow(unused)]
 pub use sessions_types::*;
 #[allow(unused)]
+pub use speech_segments_ops::*;
+#[allow(unused)]
+pub use speech_segments_types::*;
+#[allow(unused)]
 pub use tags_ops::*;
 #[allow(unused)]
 pub use tags_types::*;
@@ -129,7 +153,7 @@ impl std::ops::Deref for UserDatabase {
 }
 
 // Append only. Do not reorder.
-const MIGRATIONS: [&str; 21] = [
+const MIGRATIONS: [&str; 28] = [
     include_str!("./calendars_migration.sql"),
     include_str!("./configs_migration.sql"),
     include_str!("./events_migration.sql"),
@@ -151,6 +175,13 @@ const MIGRATIONS: [&str; 21] = [
     include_str!("./chat_groups_migration_1.sql"),
     include_str!("./events_migration_1.sql"),
     include_str!("./session_participants_migration_1.sql"),
+    include_str!("./session_chunks_migration.sql"),
+    include_str!("./session_chunks_migration_1.sql"),
+    include_str!("./action_items_migration.sql"),
+    include_str!("./decisions_migration.sql"),
+    include_str!("./speech_segments_migration.sql"),
+    include_str!("./sessions_migration_5.sql"),
+    include_str!("./sessions_migration_6.sql"),
 ];
 
 pub async fn migrate(db: &UserDatabase) -> Result<(), crate::Error> {
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS session_chunks (
+  id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL,
+  position INTEGER NOT NULL,
+  kind TEXT NOT NULL,
+  content TEXT NOT NULL,
+  start_ms INTEGER,
+  end_ms INTEGER,
+  embedding_model TEXT,
+  embedding F32_BLOB,
+  FOREIGN KEY (session_id) REFERENCES sessions(id)
+);
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS idx_session_chunks_session_id ON session_chunks(session_id);
@@ -0,0 +1,294 @@
+use super::{GetSessionFilter, Session, SessionChunk, SessionChunkMatch, UserDatabase};
+
+impl UserDatabase {
+    /// Chunks the sessions edited since they were last chunked, at most `limit` of them. Returns how many were.
+    pub async fn sync_stale_session_chunks(&self, limit: u32) -> Result<usize, crate::Error> {
+        let conn = self.conn()?;
+
+        let mut rows = conn
+            .query(
+                "SELECT id, revision FROM sessions WHERE chunks_revision < revision LIMIT ?",
+                libsql::params![limit],
+            )
+            .await?;
+
+        let mut stale = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let id: String = row.get(0)?;
+            let revision: i64 = row.get(1)?;
+            stale.push((id, revision));
+        }
+
+        for (id, revision) in &stale {
+            if let Some(session) = self.get_session(GetSessionFilter::Id(id.clone())).await? {
+                self.sync_session_chunks(&session).await?;
+            }
+
+            // Edits made in the meantime bump the revision again, and are picked up next time.
+            conn.execute(
+                "UPDATE sessions SET chunks_revision = ? WHERE id = ?",
+                libsql::params![*revision, id.clone()],
+            )
+            .await?;
+        }
+
+        Ok(stale.len())
+    }
+
+    // Chunks that didn't change keep their embedding, even if they moved.
+    async fn sync_session_chunks(&self, session: &Session) -> Result<(), crate::Error> {
+        let conn = self.conn()?;
+        let chunks = session.chunks();
+
+        let mut rows = conn
+            .query(
+                "SELECT id FROM session_chunks WHERE session_id = ?",
+                vec![session.id.clone()],
+            )
+            .await?;
+
+        let mut removed = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let id: String = row.get(0)?;
+            if !chunks.iter().any(|chunk| chunk.id == id) {
+                removed.push(id);
+            }
+        }
+
+        for id in removed {
+            conn.execute("DELETE FROM session_chunks WHERE id = ?", vec![id])
+                .await?;
+        }
+
+        for chunk in chunks {
+            conn.execute(
+                "INSERT INTO session_chunks (
+                    id,
+                    session_id,
+                    position,
+                    kind,
+                    content,
+                    start_ms,
+                    end_ms
+                ) VALUES (?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(id) DO UPDATE SET
+                    position = excluded.position",
+                libsql::params![
+                    chunk.id,
+                    chunk.session_id,
+                    chunk.position,
+                    chunk.kind.to_string(),
+                    chunk.content,
+                    chunk.start_ms.map(|v| v as i64),
+                    chunk.end_ms.map(|v| v as i64),
+                ],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Chunks not yet embedded with `model`, including ones embedded with a different model.
+    pub async fn list_unembedded_session_chunks(
+        &self,
+        model: impl Into<String>,
+        limit: u32,
+    ) -> Result<Vec<SessionChunk>, crate::Error> {
+        let conn = self.conn()?;
+
+        let mut rows = conn
+            .query(
+                "SELECT id, session_id, position, kind, content, start_ms, end_ms
+                FROM session_chunks
+                WHERE embedding IS NULL OR embedding_model IS NOT ?
+                LIMIT ?",
+                (model.into(), limit),
+            )
+            .await?;
+
+        let mut items = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let item: SessionChunk = libsql::de::from_row(&row)?;
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    pub async fn set_session_chunk_embedding(
+        &self,
+        id: impl Into<String>,
+        model: impl Into<String>,
+        embedding: &[f32],
+    ) -> Result<(), crate::Error> {
+        let conn = self.conn()?;
+
+        conn.execute(
+            "UPDATE session_chunks
+            SET embedding_model = ?, embedding = vector32(?)
+            WHERE id = ?",
+            (
+                model.into(),
+                serde_json::to_string(embedding).unwrap(),
+                id.into(),
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Chunks of the user's sessions closest to `embedding`, which must come from `model`.
+    pub async fn search_session_chunks(
+        &self,
+        user_id: impl Into<String>,
+        model: impl Into<String>,
+        embedding: &[f32],
+        limit: u8,
+    ) -> Result<Vec<SessionChunkMatch>, crate::Error> {
+        let conn = self.conn()?;
+
+        let mut rows = conn
+            .query(
+                "SELECT
+                    c.session_id,
+                    s.title AS session_title,
+                    s.created_at AS session_created_at,
+                    c.kind,
+                    c.content,
+                    c.start_ms,
+                    c.end_ms,
+                    vector_distance_cos(c.embedding, vector32(:embedding)) AS distance
+                FROM session_chunks c
+                JOIN sessions s ON s.id = c.session_id
+                WHERE s.user_id = :user_id
+                    AND c.embedding IS NOT NULL
+                    AND c.embedding_model = :model
+                ORDER BY distance ASC
+                LIMIT :limit",
+                libsql::named_params! {
+                    ":embedding": serde_json::to_string(embedding).unwrap(),
+                    ":user_id": user_id.into(),
+                    ":model": model.into(),
+                    ":limit": limit,
+                },
+            )
+            .await?;
+
+        let mut items = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let item: SessionChunkMatch = libsql::de::from_row(&row)?;
+            items.push(item);
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::setup_db, Human, Session, SessionChunkKind};
+
+    fn word(text: &str, start_ms: u64) -> owhisper_interface::Word2 {
+        owhisper_interface::Word2 {
+            text: text.to_string(),
+            start_ms: Some(start_ms),
+            end_ms: Some(start_ms + 400),
+            speaker: None,
+            confidence: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_chunks() {
+        let db = setup_db().await;
+
+        let user = db
+            .upsert_human(Human {
+                full_name: Some("John Doe".to_string()),
+                ..Human::default()
+            })
+            .await
+            .unwrap();
+
+        let mut session = db
+            .upsert_session(Session {
+                id: uuid::Uuid::new_v4().to_string(),
+                user_id: user.id.clone(),
+                created_at: chrono::Utc::now(),
+                visited_at: chrono::Utc::now(),
+                calendar_event_id: None,
+                title: "Budget review".to_string(),
+                raw_memo_html: "<p>Alice will send the &amp; numbers</p><p>Ship on Friday</p>"
+                    .to_string(),
+                enhanced_memo_html: None,
+                conversations: vec![],
+                words: vec![word("hello", 0), word("there", 500)],
+                record_start: None,
+                record_end: None,
+                pre_meeting_memo_html: None,
+            })
+            .await
+            .unwrap();
+
+        // Chunked when indexing, not on every edit.
+        assert!(db
+            .list_unembedded_session_chunks("test", 10)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(db.sync_stale_session_chunks(10).await.unwrap(), 1);
+        assert_eq!(db.sync_stale_session_chunks(10).await.unwrap(), 0);
+
+        let chunks = db.list_unembedded_session_chunks("test", 10).await.unwrap();
+        let kinds = chunks.iter().map(|c| c.kind.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                SessionChunkKind::Title,
+                SessionChunkKind::Memo,
+                SessionChunkKind::Transcript
+            ]
+        );
+        assert_eq!(
+            chunks[1].content,
+            "Alice will send the & numbers\nShip on Friday"
+        );
+        assert_eq!(chunks[2].content, "Unknown: hello there");
+        assert_eq!((chunks[2].start_ms, chunks[2].end_ms), (Some(0), Some(900)));
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            let mut embedding = vec![0.0; 3];
+            embedding[i] = 1.0;
+            db.set_session_chunk_embedding(&chunk.id, "test", &embedding)
+                .await
+                .unwrap();
+        }
+        assert!(db
+            .list_unembedded_session_chunks("test", 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let matches = db
+            .search_session_chunks(&user.id, "test", &[0.1, 0.9, 0.0], 2)
+            .await
+            .unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].kind, SessionChunkKind::Memo);
+        assert_eq!(matches[0].session_title, "Budget review");
+
+        // Only the changed chunk needs a new embedding.
+        session.title = "Q3 budget review".to_string();
+        db.upsert_session(session.clone()).await.unwrap();
+        assert_eq!(db.sync_stale_session_chunks(10).await.unwrap(), 1);
+        let chunks = db.list_unembedded_session_chunks("test", 10).await.unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].content, "Q3 budget review");
+
+        db.delete_session(&session.id).await.unwrap();
+        assert!(db
+            .list_unembedded_session_chunks("other", 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+use chrono::{DateTime, Utc};
+use owhisper_interface::{SpeakerIdentity, Word2};
+
+use crate::{user_common_derives, Session};
+
+// Estimated, like `hypr_template::timeline::split`. Small enough to keep retrieval precise.
+const TRANSCRIPT_CHUNK_TOKENS: usize = 384;
+const MEMO_CHUNK_CHARS: usize = 1500;
+
+user_common_derives! {
+    #[derive(strum::EnumString, strum::Display)]
+    pub enum SessionChunkKind {
+        Title,
+        Memo,
+        Transcript,
+    }
+}
+
+user_common_derives! {
+    /// Piece of a session that is embedded for retrieval.
+    pub struct SessionChunk {
+        pub id: String,
+        pub session_id: String,
+        pub position: u32,
+        pub kind: SessionChunkKind,
+        pub content: String,
+        /// Position in the recording, for transcript chunks.
+        pub start_ms: Option<u64>,
+        pub end_ms: Option<u64>,
+    }
+}
+
+user_common_derives! {
+    /// Retrieved chunk, with what is needed to cite it.
+    pub struct SessionChunkMatch {
+        pub session_id: String,
+        pub session_title: String,
+        pub session_created_at: DateTime<Utc>,
+        pub kind: SessionChunkKind,
+        pub content: String,
+        pub start_ms: Option<u64>,
+        pub end_ms: Option<u64>,
+        /// Cosine distance to the query. Lower is closer.
+        pub distance: f32,
+    }
+}
+
+impl Session {
+    /// Title, memo and transcript windows, in that order. Identical chunks are only kept once.
+    pub fn chunks(&self) -> Vec<SessionChunk> {
+        let mut parts = Vec::new();
+
+        if !self.title.trim().is_empty() {
+            parts.push((SessionChunkKind::Title, self.title.trim().to_string(), None));
+        }
+
+        let memo = match &self.enhanced_memo_html {
+            Some(enhanced) if !enhanced.trim().is_empty() => enhanced,
+            _ => &self.raw_memo_html,
+        };
+        for text in split_text(&html_to_text(memo), MEMO_CHUNK_CHARS) {
+            parts.push((SessionChunkKind::Memo, text, None));
+        }
+
+        for window in hypr_template::timeline::split(&self.words, TRANSCRIPT_CHUNK_TOKENS) {
+            let range = window
+                .first()
+                .and_then(|w| w.start_ms)
+                .zip(window.last().and_then(|w| w.end_ms));
+            parts.push((SessionChunkKind::Transcript, render_words(&window), range));
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(position, (kind, content, range))| SessionChunk {
+                id: chunk_id(&self.id, &kind, &content, range),
+                session_id: self.id.clone(),
+                position: position as u32,
+                kind,
+                content,
+                start_ms: range.map(|(start, _)| start),
+                end_ms: range.map(|(_, end)| end),
+            })
+            .filter(|chunk| seen.insert(chunk.id.clone()))
+            .collect()
+    }
+}
+
+// Keyed by what gets embedded, so a chunk that only moved keeps its embedding.
+fn chunk_id(
+    session_id: &str,
+    kind: &SessionChunkKind,
+    content: &str,
+    range: Option<(u64, u64)>,
+) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(kind.to_string());
+    hasher.update([0]);
+    hasher.update(content);
+    if let Some((start, end)) = range {
+        hasher.update(start.to_le_bytes());
+        hasher.update(end.to_le_bytes());
+    }
+
+    let hash = hasher.finalize()[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!("{}-{}", session_id, hash)
+}
+
+fn render_words(words: &[Word2]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current_speaker = None;
+
+    for word in words {
+        if lines.is_empty() || word.speaker != current_speaker {
+            current_speaker = word.speaker.clone();
+            let label = match &word.speaker {
+                Some(SpeakerIdentity::Assigned { label, .. }) => label.clone(),
+                Some(SpeakerIdentity::Unassigned { index }) => format!("Speaker {}", index),
+                None => "Unknown".to_string(),
+            };
+            lines.push(format!("{}:", label));
+        }
+
+        let line = lines.last_mut().unwrap();
+        line.push(' ');
+        line.push_str(word.text.trim());
+    }
+
+    lines.join("\n")
+}
+
+// Good enough for the memos the editor produces. Block elements become line breaks.
+fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+
+        let Some(len) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let tag = rest[start + 1..start + len]
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        if matches!(
+            tag.as_str(),
+            "p" | "br" | "li" | "div" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote"
+        ) {
+            text.push('\n');
+        }
+
+        rest = &rest[start + len + 1..];
+    }
+    text.push_str(rest);
+
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Splits between lines, unless a single line is longer than `max_chars`.
+fn split_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for line in text.lines() {
+        if !current.is_empty() && current.len() + line.len() + 1 > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+
+        if line.len() > max_chars {
+            let mut piece = String::new();
+            for word in line.split_whitespace() {
+                if !piece.is_empty() && piece.len() + word.len() + 1 > max_chars {
+                    chunks.push(std::mem::take(&mut piece));
+                }
+                if !piece.is_empty() {
+                    piece.push(' ');
+                }
+                piece.push_str(word);
+            }
+            current = piece;
+            continue;
+        }
+
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
@@ -0,0 +1,4 @@
+ALTER TABLE
+  sessions
+ADD
+  COLUMN revision INTEGER NOT NULL DEFAULT 1;
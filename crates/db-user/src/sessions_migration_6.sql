@@ -0,0 +1,4 @@
+ALTER TABLE
+  sessions
+ADD
+  COLUMN chunks_revision INTEGER NOT NULL DEFAULT 0;
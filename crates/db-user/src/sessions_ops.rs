@@ -27,6 +27,22 @@ impl UserDatabase {
         )
         .await?;
 
+        for table in [
+            "session_chunks",
+            "action_items",
+            "decisions",
+            "speech_segments",
+        ] {
+            conn.execute(
+                &format!(
+                    "DELETE FROM {} WHERE session_id NOT IN (SELECT id FROM sessions)",
+                    table
+                ),
+                (),
+            )
+            .await?;
+        }
+
         Ok(())
     }
 
@@ -132,6 +148,19 @@ impl UserDatabase {
         )
         .await?;
 
+        for table in [
+            "session_chunks",
+            "action_items",
+            "decisions",
+            "speech_segments",
+        ] {
+            conn.execute(
+                &format!("DELETE FROM {} WHERE session_id = ?", table),
+                vec![session_id.clone()],
+            )
+            .await?;
+        }
+
         conn.execute("DELETE FROM sessions WHERE id = ?", vec![session_id])
             .await?;
 
@@ -331,7 +360,8 @@ impl UserDatabase {
                     words = :words,
                     record_start = :record_start,
                     record_end = :record_end,
-                    pre_meeting_memo_html = :pre_meeting_memo_html
+                    pre_meeting_memo_html = :pre_meeting_memo_html,
+                    revision = revision + 1
                 RETURNING *",
                 libsql::named_params! {
                     ":id": session.id.clone(),
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS speech_segments (
+  id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL,
+  channel TEXT NOT NULL,
+  start_ms INTEGER NOT NULL,
+  end_ms INTEGER NOT NULL,
+  FOREIGN KEY (session_id) REFERENCES sessions(id)
+);
+CREATE INDEX IF NOT EXISTS idx_speech_segments_session_id ON speech_segments(session_id);
@@ -0,0 +1,208 @@
+use super::{SpeakerSegment, SpeechSegment, TalkTimeStats, UserDatabase};
+
+impl UserDatabase {
+    pub async fn insert_speech_segments(
+        &self,
+        segments: Vec<SpeechSegment>,
+    ) -> Result<(), crate::Error> {
+        let conn = self.conn()?;
+
+        for segment in segments {
+            conn.execute(
+                "INSERT OR REPLACE INTO speech_segments (
+                    id,
+                    session_id,
+                    channel,
+                    start_ms,
+                    end_ms
+                ) VALUES (?, ?, ?, ?, ?)",
+                libsql::params![
+                    segment.id,
+                    segment.session_id,
+                    segment.channel.to_string(),
+                    segment.start_ms as i64,
+                    segment.end_ms as i64,
+                ],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn list_session_speech_segments(
+        &self,
+        session_id: impl Into<String>,
+    ) -> Result<Vec<SpeechSegment>, crate::Error> {
+        let conn = self.conn()?;
+
+        let mut rows = conn
+            .query(
+                "SELECT * FROM speech_segments
+                WHERE session_id = ?
+                ORDER BY start_ms ASC",
+                vec![session_id.into()],
+            )
+            .await?;
+
+        let mut items = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let item: SpeechSegment = libsql::de::from_row(&row)?;
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    /// Speakers come from the transcript as it is now, so renaming or reassigning them is reflected.
+    pub async fn get_session_talk_time(
+        &self,
+        session_id: impl Into<String>,
+    ) -> Result<TalkTimeStats, crate::Error> {
+        let session_id = session_id.into();
+
+        let segments = self.list_session_speech_segments(&session_id).await?;
+        let words = self.get_words(&session_id).await?;
+
+        Ok(TalkTimeStats::compute(&SpeakerSegment::attribute(
+            &segments, &words,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use owhisper_interface::{SpeakerIdentity, Word2};
+
+    use crate::{tests::setup_db, Human, Session, SpeechChannel, SpeechSegment};
+
+    fn segment(
+        session_id: &str,
+        channel: SpeechChannel,
+        start_ms: u64,
+        end_ms: u64,
+    ) -> SpeechSegment {
+        SpeechSegment {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: session_id.to_string(),
+            channel,
+            start_ms,
+            end_ms,
+        }
+    }
+
+    fn word(index: u8, start_ms: u64) -> Word2 {
+        Word2 {
+            text: "word".to_string(),
+            speaker: Some(SpeakerIdentity::Unassigned { index }),
+            confidence: None,
+            start_ms: Some(start_ms),
+            end_ms: Some(start_ms + 400),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_talk_time() {
+        let db = setup_db().await;
+
+        let user = db
+            .upsert_human(Human {
+                full_name: Some("John Doe".to_string()),
+                ..Human::default()
+            })
+            .await
+            .unwrap();
+
+        // The remote side has two people, told apart by diarization.
+        let session = db
+            .upsert_session(Session {
+                id: uuid::Uuid::new_v4().to_string(),
+                user_id: user.id.clone(),
+                created_at: chrono::Utc::now(),
+                visited_at: chrono::Utc::now(),
+                calendar_event_id: None,
+                title: "Weekly sync".to_string(),
+                raw_memo_html: "".to_string(),
+                enhanced_memo_html: None,
+                conversations: vec![],
+                words: vec![
+                    word(0, 500),
+                    word(0, 2000),
+                    word(1, 4200),
+                    word(2, 6500),
+                    word(1, 8600),
+                    word(0, 9200),
+                ],
+                record_start: None,
+                record_end: None,
+                pre_meeting_memo_html: None,
+            })
+            .await
+            .unwrap();
+
+        db.insert_speech_segments(vec![
+            segment(&session.id, SpeechChannel::Mic, 0, 4000),
+            segment(&session.id, SpeechChannel::Speaker, 3500, 8000),
+            segment(&session.id, SpeechChannel::Speaker, 8500, 9500),
+            // Crosses the previous one too briefly to count as an overlap.
+            segment(&session.id, SpeechChannel::Mic, 9400, 10000),
+        ])
+        .await
+        .unwrap();
+
+        let stats = db.get_session_talk_time(&session.id).await.unwrap();
+        assert_eq!(stats.duration_ms, 10000);
+        assert_eq!(stats.speech_ms, 9500);
+        assert_eq!(stats.silence_ratio, 0.05);
+        assert_eq!((stats.overlap_ms, stats.overlap_count), (500, 1));
+        assert_eq!(stats.interruption_count, 1);
+
+        let speakers = stats
+            .speakers
+            .iter()
+            .map(|s| {
+                (
+                    s.channel.clone(),
+                    s.speaker.clone(),
+                    s.talk_ms,
+                    s.longest_monologue_ms,
+                    s.interruptions,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            speakers,
+            vec![
+                (
+                    SpeechChannel::Mic,
+                    Some(SpeakerIdentity::Unassigned { index: 0 }),
+                    4600,
+                    4000,
+                    0
+                ),
+                (
+                    SpeechChannel::Speaker,
+                    Some(SpeakerIdentity::Unassigned { index: 1 }),
+                    3050,
+                    2050,
+                    1
+                ),
+                (
+                    SpeechChannel::Speaker,
+                    Some(SpeakerIdentity::Unassigned { index: 2 }),
+                    2450,
+                    2450,
+                    0
+                ),
+            ]
+        );
+        let shares = stats.speakers.iter().map(|s| s.share).sum::<f32>();
+        assert!((shares - 1.0).abs() < 1e-6);
+
+        db.delete_session(&session.id).await.unwrap();
+        assert!(db
+            .list_session_speech_segments(&session.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
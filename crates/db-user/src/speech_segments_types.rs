@@ -0,0 +1,304 @@
+use owhisper_interface::{SpeakerIdentity, Word2};
+
+use crate::user_common_derives;
+
+// Shorter crossings are turn-taking, or VAD edges.
+const MIN_OVERLAP_MS: u64 = 250;
+// A monologue carries on through pauses up to this long, as long as nobody else speaks.
+const MAX_MONOLOGUE_PAUSE_MS: u64 = 2000;
+
+user_common_derives! {
+    #[derive(strum::EnumString, strum::Display)]
+    pub enum SpeechChannel {
+        Mic,
+        Speaker,
+    }
+}
+
+user_common_derives! {
+    /// Speech detected on one channel of a session, in recording time.
+    pub struct SpeechSegment {
+        pub id: String,
+        pub session_id: String,
+        pub channel: SpeechChannel,
+        pub start_ms: u64,
+        pub end_ms: u64,
+    }
+}
+
+user_common_derives! {
+    /// Part of a `SpeechSegment` attributed to a speaker of the transcript.
+    pub struct SpeakerSegment {
+        pub channel: SpeechChannel,
+        /// `None` when no word of the transcript falls in it.
+        pub speaker: Option<SpeakerIdentity>,
+        pub start_ms: u64,
+        pub end_ms: u64,
+    }
+}
+
+user_common_derives! {
+    pub struct SpeakerTalkTime {
+        pub channel: SpeechChannel,
+        pub speaker: Option<SpeakerIdentity>,
+        pub talk_ms: u64,
+        /// Of all talk time in the session, between 0 and 1.
+        pub share: f32,
+        pub longest_monologue_ms: u64,
+        /// Times they started speaking while someone else kept going.
+        pub interruptions: u32,
+    }
+}
+
+user_common_derives! {
+    pub struct TalkTimeStats {
+        /// Up to the end of the last speech.
+        pub duration_ms: u64,
+        pub speech_ms: u64,
+        pub silence_ratio: f32,
+        pub overlap_ms: u64,
+        pub overlap_count: u32,
+        pub interruption_count: u32,
+        /// In order of first appearance.
+        pub speakers: Vec<SpeakerTalkTime>,
+    }
+}
+
+impl SpeakerSegment {
+    /// Splits segments between the speakers of `words`.
+    ///
+    /// Words carry no channel, so each speaker is first matched to the channel that alone covers most of their words.
+    pub fn attribute(segments: &[SpeechSegment], words: &[Word2]) -> Vec<SpeakerSegment> {
+        let timed = words
+            .iter()
+            .filter_map(|w| Some((w.speaker.as_ref()?, w.start_ms?, w.end_ms?)))
+            .collect::<Vec<_>>();
+
+        let mut votes: Vec<(&SpeakerIdentity, [usize; 2])> = Vec::new();
+        for (speaker, start, end) in &timed {
+            let mid = (start + end) / 2;
+            let covering = segments
+                .iter()
+                .filter(|s| s.start_ms <= mid && mid < s.end_ms)
+                .map(|s| channel_index(&s.channel))
+                .fold([false; 2], |mut acc, i| {
+                    acc[i] = true;
+                    acc
+                });
+
+            let channel = match covering {
+                [true, false] => 0,
+                [false, true] => 1,
+                _ => continue,
+            };
+
+            match votes.iter_mut().find(|(s, _)| **s == **speaker) {
+                Some((_, counts)) => counts[channel] += 1,
+                None => {
+                    let mut counts = [0; 2];
+                    counts[channel] += 1;
+                    votes.push((*speaker, counts));
+                }
+            }
+        }
+
+        let belongs_to = |speaker: &SpeakerIdentity, channel: &SpeechChannel| {
+            let i = channel_index(channel);
+            votes
+                .iter()
+                .find(|(s, _)| *s == speaker)
+                .is_none_or(|(_, counts)| counts[i] >= counts[1 - i])
+        };
+
+        let mut attributed = Vec::new();
+        for segment in segments {
+            let mut inside = timed
+                .iter()
+                .filter(|(speaker, start, end)| {
+                    *start < segment.end_ms
+                        && *end > segment.start_ms
+                        && belongs_to(speaker, &segment.channel)
+                })
+                .collect::<Vec<_>>();
+            inside.sort_by_key(|(_, start, _)| *start);
+
+            let mut push = |speaker: Option<&SpeakerIdentity>, start_ms: u64, end_ms: u64| {
+                if start_ms < end_ms {
+                    attributed.push(SpeakerSegment {
+                        channel: segment.channel.clone(),
+                        speaker: speaker.cloned(),
+                        start_ms,
+                        end_ms,
+                    });
+                }
+            };
+
+            let mut cursor = segment.start_ms;
+            let mut current = inside.first().map(|(speaker, _, _)| *speaker);
+            for pair in inside.windows(2) {
+                let ((prev, _, prev_end), (next, next_start, _)) = (pair[0], pair[1]);
+                if prev == next {
+                    continue;
+                }
+
+                let boundary = ((prev_end + next_start) / 2).clamp(cursor, segment.end_ms);
+                push(Some(*prev), cursor, boundary);
+                cursor = boundary;
+                current = Some(*next);
+            }
+            push(current, cursor, segment.end_ms);
+        }
+
+        attributed.sort_by_key(|s| s.start_ms);
+        attributed
+    }
+}
+
+impl TalkTimeStats {
+    pub fn compute(segments: &[SpeakerSegment]) -> Self {
+        let mut segments = segments.to_vec();
+        segments.sort_by_key(|s| s.start_ms);
+
+        let same_speaker = |a: &SpeakerSegment, b: &SpeakerSegment| {
+            a.channel == b.channel && a.speaker == b.speaker
+        };
+
+        let mut speakers: Vec<SpeakerTalkTime> = Vec::new();
+        let mut spans: Vec<Vec<(u64, u64)>> = Vec::new();
+        for segment in &segments {
+            let i = match speakers
+                .iter()
+                .position(|s| s.channel == segment.channel && s.speaker == segment.speaker)
+            {
+                Some(i) => i,
+                None => {
+                    speakers.push(SpeakerTalkTime {
+                        channel: segment.channel.clone(),
+                        speaker: segment.speaker.clone(),
+                        talk_ms: 0,
+                        share: 0.0,
+                        longest_monologue_ms: 0,
+                        interruptions: 0,
+                    });
+                    spans.push(Vec::new());
+                    speakers.len() - 1
+                }
+            };
+            spans[i].push((segment.start_ms, segment.end_ms));
+
+            let interrupted = segments.iter().any(|other| {
+                !same_speaker(other, segment)
+                    && other.start_ms < segment.start_ms
+                    && other.end_ms >= segment.start_ms + MIN_OVERLAP_MS
+            });
+            if interrupted {
+                speakers[i].interruptions += 1;
+            }
+        }
+
+        for (speaker, spans) in speakers.iter_mut().zip(&spans) {
+            speaker.talk_ms = union(spans).iter().map(|(start, end)| end - start).sum();
+        }
+
+        // Consecutive segments of one speaker, with nobody else starting in between.
+        let mut turns: Vec<(&SpeakerSegment, u64, u64)> = Vec::new();
+        for segment in &segments {
+            match turns.last_mut() {
+                Some((owner, _, end))
+                    if same_speaker(owner, segment)
+                        && segment.start_ms <= *end + MAX_MONOLOGUE_PAUSE_MS =>
+                {
+                    *end = (*end).max(segment.end_ms);
+                }
+                _ => turns.push((segment, segment.start_ms, segment.end_ms)),
+            }
+        }
+        for (owner, start, end) in turns {
+            if let Some(speaker) = speakers
+                .iter_mut()
+                .find(|s| s.channel == owner.channel && s.speaker == owner.speaker)
+            {
+                speaker.longest_monologue_ms = speaker.longest_monologue_ms.max(end - start);
+            }
+        }
+
+        let total_talk_ms = speakers.iter().map(|s| s.talk_ms).sum::<u64>();
+        for speaker in &mut speakers {
+            speaker.share = ratio(speaker.talk_ms, total_talk_ms);
+        }
+
+        let duration_ms = segments.iter().map(|s| s.end_ms).max().unwrap_or(0);
+        let speech_ms = union(&spans.concat())
+            .iter()
+            .map(|(start, end)| end - start)
+            .sum::<u64>();
+
+        let overlaps = overlaps(&segments)
+            .into_iter()
+            .filter(|(start, end)| end - start >= MIN_OVERLAP_MS)
+            .collect::<Vec<_>>();
+
+        Self {
+            duration_ms,
+            speech_ms,
+            silence_ratio: ratio(duration_ms - speech_ms, duration_ms),
+            overlap_ms: overlaps.iter().map(|(start, end)| end - start).sum(),
+            overlap_count: overlaps.len() as u32,
+            interruption_count: speakers.iter().map(|s| s.interruptions).sum(),
+            speakers,
+        }
+    }
+}
+
+fn channel_index(channel: &SpeechChannel) -> usize {
+    match channel {
+        SpeechChannel::Mic => 0,
+        SpeechChannel::Speaker => 1,
+    }
+}
+
+fn ratio(part: u64, whole: u64) -> f32 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f32 / whole as f32
+    }
+}
+
+fn union(spans: &[(u64, u64)]) -> Vec<(u64, u64)> {
+    let mut spans = spans.to_vec();
+    spans.sort();
+
+    let mut merged: Vec<(u64, u64)> = Vec::new();
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+// Stretches where two or more segments are going at once.
+fn overlaps(segments: &[SpeakerSegment]) -> Vec<(u64, u64)> {
+    let mut edges = segments
+        .iter()
+        .flat_map(|s| [(s.start_ms, 1i32), (s.end_ms, -1)])
+        .collect::<Vec<_>>();
+    // Ends before starts at the same time, so touching segments do not overlap.
+    edges.sort();
+
+    let mut overlaps = Vec::new();
+    let mut active = 0;
+    let mut since = 0;
+    for (at, delta) in edges {
+        if active >= 2 && active + delta < 2 && at > since {
+            overlaps.push((since, at));
+        }
+        if active < 2 && active + delta >= 2 {
+            since = at;
+        }
+        active += delta;
+    }
+    overlaps
+}
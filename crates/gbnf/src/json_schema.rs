@@ -0,0 +1,462 @@
+// Converts a JSON schema into a GBNF grammar, similar to llama.cpp's `json-schema-to-grammar`.
+// Only the parts of the spec that shape the output are supported. Constraints like `pattern`,
+// `minLength` or `maximum` are ignored, since the grammar can't express them cheaply.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use crate::Error;
+
+const PRIMITIVES: &[(&str, &str)] = &[
+    ("ws", r#"ws ::= (" " | "\n" [ \t]*)?"#),
+    (
+        "string",
+        r#"string ::= "\"" ([^"\\\x7F\x00-\x1F] | "\\" (["\\/bfnrt] | "u" [0-9a-fA-F] [0-9a-fA-F] [0-9a-fA-F] [0-9a-fA-F]))* "\"""#,
+    ),
+    (
+        "number",
+        r#"number ::= "-"? ("0" | [1-9] [0-9]*) ("." [0-9]+)? ([eE] [-+]? [0-9]+)?"#,
+    ),
+    ("integer", r#"integer ::= "-"? ("0" | [1-9] [0-9]*)"#),
+    ("boolean", r#"boolean ::= "true" | "false""#),
+    ("null", r#"null ::= "null""#),
+    (
+        "value",
+        r#"value ::= object | array | string | number | boolean | null"#,
+    ),
+    (
+        "object",
+        r#"object ::= "{" ws (string ws ":" ws value ws ("," ws string ws ":" ws value ws)*)? "}""#,
+    ),
+    (
+        "array",
+        r#"array ::= "[" ws (value ws ("," ws value ws)*)? "]""#,
+    ),
+];
+
+/// Grammar for any JSON object, as in OpenAI's `response_format: { type: "json_object" }`.
+pub fn json_object() -> String {
+    let mut converter = Converter::new(&Value::Null);
+    converter.primitive("object");
+    converter.rules.insert(0, "root ::= object".to_string());
+    converter.rules.join("\n")
+}
+
+/// Grammar for JSON values matching `schema`.
+/// Properties that are not `required` may be left out, but always come after the required ones.
+/// An object without any required property gets all of them.
+pub fn from_json_schema(schema: &Value) -> Result<String, Error> {
+    let mut converter = Converter::new(schema);
+    let root = converter.visit(schema, "root")?;
+    if root != "root" {
+        converter.rules.insert(0, format!("root ::= {}", root));
+    }
+    Ok(converter.rules.join("\n"))
+}
+
+/// Grammar for one or more calls to `tools`, given as names and JSON schemas of their arguments.
+/// Calls use the `<tool_call>` format of Hermes and Qwen models, and may come after a `<think>` block.
+/// Unless `required`, the model can answer in plain text instead.
+pub fn tool_calls(tools: &[(String, Value)], required: bool) -> Result<String, Error> {
+    let mut converter = Converter::new(&Value::Null);
+    converter.primitive("ws");
+
+    let mut calls = Vec::new();
+    for (i, (name, parameters)) in tools.iter().enumerate() {
+        converter.root = parameters;
+        converter.refs.clear();
+
+        let arguments = converter.visit(parameters, &format!("tool-{}-arguments", i))?;
+        let prefix = format!(
+            r#"{{"name": {}, "arguments": "#,
+            Value::String(name.clone())
+        );
+        calls.push(format!(
+            "{} {} {}",
+            text_literal(&prefix),
+            arguments,
+            text_literal("}")
+        ));
+    }
+
+    let call = converter.add_rule(
+        "tool-call",
+        format!(
+            r#""<tool_call>\n" ({}) "\n</tool_call>""#,
+            calls.join(" | ")
+        ),
+    );
+    let think = converter.add_rule(
+        "think",
+        r#""<think>" ([^<] | "<" [^/])* "</think>" [ \t\n]*"#.to_string(),
+    );
+
+    let root = if required {
+        format!("root ::= {think}? {call} (ws {call})*")
+    } else {
+        let content = converter.add_rule("content", not_prefixed("<tool_call>"));
+        format!("root ::= {think}? ({call} (ws {call})* | {content})")
+    };
+    converter.rules.insert(0, root);
+
+    Ok(converter.rules.join("\n"))
+}
+
+struct Converter<'a> {
+    root: &'a Value,
+    rules: Vec<String>,
+    names: HashSet<String>,
+    refs: HashMap<String, String>,
+}
+
+impl<'a> Converter<'a> {
+    fn new(root: &'a Value) -> Self {
+        Self {
+            root,
+            rules: Vec::new(),
+            names: HashSet::new(),
+            refs: HashMap::new(),
+        }
+    }
+
+    fn primitive(&mut self, name: &str) -> String {
+        if self.names.insert(name.to_string()) {
+            let dependencies: &[&str] = match name {
+                "value" => &["object", "array", "string", "number", "boolean", "null"],
+                "object" => &["ws", "string", "value"],
+                "array" => &["ws", "value"],
+                _ => &[],
+            };
+            for dependency in dependencies {
+                self.primitive(dependency);
+            }
+
+            let (_, rule) = PRIMITIVES.iter().find(|(n, _)| *n == name).unwrap();
+            self.rules.push(rule.to_string());
+        }
+        name.to_string()
+    }
+
+    fn reserve(&mut self, name: &str) -> String {
+        let base = sanitize(name);
+        let mut name = base.clone();
+        let mut i = 1;
+        while self.names.contains(&name) || PRIMITIVES.iter().any(|(n, _)| *n == name) {
+            name = format!("{}{}", base, i);
+            i += 1;
+        }
+        self.names.insert(name.clone());
+        name
+    }
+
+    fn add_rule(&mut self, name: &str, body: String) -> String {
+        let name = self.reserve(name);
+        self.rules.push(format!("{} ::= {}", name, body));
+        name
+    }
+
+    // Returns the name of the rule that matches `schema`.
+    fn visit(&mut self, schema: &Value, name: &str) -> Result<String, Error> {
+        let schema = match schema {
+            Value::Bool(true) => return Ok(self.primitive("value")),
+            Value::Object(schema) => schema,
+            _ => return Err(Error::UnsupportedSchema(schema.to_string())),
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            return self.visit_ref(reference);
+        }
+
+        if let Some(value) = schema.get("const") {
+            return Ok(self.add_rule(name, literal(value)));
+        }
+
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            let body = values.iter().map(literal).collect::<Vec<_>>().join(" | ");
+            return Ok(self.add_rule(name, body));
+        }
+
+        if let Some(variants) = schema
+            .get("anyOf")
+            .or_else(|| schema.get("oneOf"))
+            .and_then(Value::as_array)
+        {
+            let alternatives = variants
+                .iter()
+                .enumerate()
+                .map(|(i, variant)| self.visit(variant, &format!("{}-{}", name, i)))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(self.add_rule(name, alternatives.join(" | ")));
+        }
+
+        match schema.get("type") {
+            Some(Value::Array(types)) => {
+                let alternatives = types
+                    .iter()
+                    .map(|t| {
+                        let mut variant = schema.clone();
+                        variant.insert("type".to_string(), t.clone());
+                        let t = t.as_str().unwrap_or_default();
+                        self.visit(&Value::Object(variant), &format!("{}-{}", name, t))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(self.add_rule(name, alternatives.join(" | ")))
+            }
+            Some(Value::String(t)) => match t.as_str() {
+                "object" => self.visit_object(schema, name),
+                "array" => self.visit_array(schema, name),
+                "string" | "number" | "integer" | "boolean" | "null" => Ok(self.primitive(t)),
+                _ => Err(Error::UnsupportedSchema(t.clone())),
+            },
+            None if schema.contains_key("properties") => self.visit_object(schema, name),
+            None if schema.contains_key("items") => self.visit_array(schema, name),
+            None => Ok(self.primitive("value")),
+            Some(other) => Err(Error::UnsupportedSchema(other.to_string())),
+        }
+    }
+
+    fn visit_ref(&mut self, reference: &str) -> Result<String, Error> {
+        if let Some(name) = self.refs.get(reference) {
+            return Ok(name.clone());
+        }
+
+        let target = reference
+            .strip_prefix('#')
+            .and_then(|pointer| self.root.pointer(pointer))
+            .ok_or_else(|| Error::UnresolvedRef(reference.to_string()))?;
+
+        // Registered before visiting, so recursive schemas refer back to the same rule.
+        let name = self.reserve(reference.rsplit('/').next().unwrap_or("ref"));
+        self.refs.insert(reference.to_string(), name.clone());
+
+        let body = self.visit(target, &format!("{}-inner", name))?;
+        self.rules.push(format!("{} ::= {}", name, body));
+        Ok(name)
+    }
+
+    fn visit_object(
+        &mut self,
+        schema: &serde_json::Map<String, Value>,
+        name: &str,
+    ) -> Result<String, Error> {
+        let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+            return Ok(self.primitive("object"));
+        };
+        if properties.is_empty() {
+            return Ok(self.primitive("object"));
+        }
+
+        self.primitive("ws");
+
+        let required = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect::<HashSet<_>>())
+            .unwrap_or_default();
+        let all_required = !properties.keys().any(|k| required.contains(k.as_str()));
+
+        let mut required_parts = Vec::new();
+        let mut optional_parts = Vec::new();
+
+        for (key, property) in properties {
+            let value = self.visit(property, &format!("{}-{}", name, key))?;
+            let part = format!(
+                r#"{} ws ":" ws {} ws"#,
+                literal(&Value::String(key.clone())),
+                value
+            );
+
+            if all_required || required.contains(key.as_str()) {
+                required_parts.push(part);
+            } else {
+                optional_parts.push(part);
+            }
+        }
+
+        let mut body = format!(r#""{{" ws {}"#, required_parts.join(r#" "," ws "#));
+        for part in optional_parts {
+            body.push_str(&format!(r#" ("," ws {})?"#, part));
+        }
+        body.push_str(r#" "}""#);
+
+        Ok(self.add_rule(name, body))
+    }
+
+    fn visit_array(
+        &mut self,
+        schema: &serde_json::Map<String, Value>,
+        name: &str,
+    ) -> Result<String, Error> {
+        let item = match schema.get("items") {
+            Some(items) => self.visit(items, &format!("{}-item", name))?,
+            None => self.primitive("value"),
+        };
+        self.primitive("ws");
+
+        let min_items = schema.get("minItems").and_then(Value::as_u64).unwrap_or(0);
+        let rest = format!(r#"("," ws {} ws)*"#, item);
+
+        let body = if min_items > 0 {
+            format!(r#""[" ws {} ws {} "]""#, item, rest)
+        } else {
+            format!(r#""[" ws ({} ws {})? "]""#, item, rest)
+        };
+
+        Ok(self.add_rule(name, body))
+    }
+}
+
+// The JSON encoding of `value`, as a GBNF string literal.
+fn literal(value: &Value) -> String {
+    text_literal(&value.to_string())
+}
+
+fn text_literal(text: &str) -> String {
+    let escaped = text
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n");
+    format!(r#""{}""#, escaped)
+}
+
+// Any non-empty text, except text starting with `prefix`.
+fn not_prefixed(prefix: &str) -> String {
+    let chars = prefix.chars().collect::<Vec<_>>();
+
+    let mut alternatives = Vec::new();
+    for i in 0..chars.len() {
+        let head = chars[..i].iter().collect::<String>();
+        let next = format!(r"[^\x{:02X}] .*", chars[i] as u32);
+
+        if head.is_empty() {
+            alternatives.push(next);
+        } else {
+            alternatives.push(format!("{} ({})?", text_literal(&head), next));
+        }
+    }
+
+    alternatives.join(" | ")
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_object_schema() {
+        let grammar = from_json_schema(&json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string" },
+                "priority": { "enum": ["low", "high"] },
+                "done": { "type": "boolean" },
+                "assignee": { "type": ["string", "null"] }
+            },
+            "required": ["title", "priority", "done"]
+        }))
+        .unwrap();
+
+        let gbnf = gbnf_validator::Validator::new().unwrap();
+
+        for (input, expected) in [
+            (
+                r#"{"title": "Ship it", "priority": "high", "done": false}"#,
+                true,
+            ),
+            (
+                r#"{"title": "Ship it", "priority": "low", "done": true, "assignee": null}"#,
+                true,
+            ),
+            (
+                r#"{"title": "Ship it", "priority": "urgent", "done": true}"#,
+                false,
+            ),
+            (
+                r#"{"priority": "low", "title": "Ship it", "done": true}"#,
+                false,
+            ),
+            (r#"{"title": "Ship it"}"#, false),
+        ] {
+            let result = gbnf.validate(&grammar, input).unwrap();
+            assert_eq!(result, expected, "failed: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_array_and_refs() {
+        let grammar = from_json_schema(&json!({
+            "type": "array",
+            "minItems": 1,
+            "items": { "$ref": "#/$defs/item" },
+            "$defs": {
+                "item": {
+                    "type": "object",
+                    "properties": { "text": { "type": "string" }, "count": { "type": "integer" } },
+                    "required": ["text"]
+                }
+            }
+        }))
+        .unwrap();
+
+        let gbnf = gbnf_validator::Validator::new().unwrap();
+
+        for (input, expected) in [
+            (r#"[{"text": "a"}, {"text": "b", "count": 2}]"#, true),
+            (r#"[]"#, false),
+            (r#"[{"text": "a", "count": 1.5}]"#, false),
+        ] {
+            let result = gbnf.validate(&grammar, input).unwrap();
+            assert_eq!(result, expected, "failed: {}", input);
+        }
+
+        assert!(gbnf
+            .validate(&json_object(), r#"{"anything": [1, "two", null]}"#)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_tool_calls() {
+        let tools = vec![(
+            "search_sessions".to_string(),
+            json!({
+                "type": "object",
+                "properties": { "query": { "type": "string" } },
+                "required": ["query"]
+            }),
+        )];
+
+        let gbnf = gbnf_validator::Validator::new().unwrap();
+        let call = "<tool_call>\n{\"name\": \"search_sessions\", \"arguments\": {\"query\": \"roadmap\"}}\n</tool_call>";
+
+        for (input, required, expected) in [
+            (call.to_string(), true, true),
+            (
+                format!("<think>\nLet me look.\n</think>\n\n{call}"),
+                true,
+                true,
+            ),
+            ("No need to search.".to_string(), true, false),
+            ("No need to search.".to_string(), false, true),
+            ("<b>No need</b> to search.".to_string(), false, true),
+            ("<".to_string(), false, true),
+            ("<tool_call> is how I call tools.".to_string(), false, false),
+            (
+                call.replace("search_sessions", "delete_sessions"),
+                false,
+                false,
+            ),
+        ] {
+            let grammar = tool_calls(&tools, required).unwrap();
+            let result = gbnf.validate(&grammar, &input).unwrap();
+            assert_eq!(result, expected, "failed: {}", input);
+        }
+    }
+}
@@ -1,3 +1,9 @@
+mod error;
+mod json_schema;
+
+pub use error::*;
+pub use json_schema::*;
+
 #[derive(specta::Type, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "task")]
 pub enum Grammar {
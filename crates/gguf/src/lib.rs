@@ -2,7 +2,6 @@ use std::fs::File;
 use std::io::{Cursor, Seek, SeekFrom};
 use std::path::Path;
 
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
 use memmap2::Mmap;
 
 mod error;
@@ -17,8 +16,12 @@ pub use value::*;
 mod utils;
 pub use utils::*;
 
+mod metadata;
+pub use metadata::*;
+
 pub trait GgufExt {
     fn gguf_chat_format(&self) -> Result<Option<ChatTemplate>>;
+    fn gguf_metadata(&self) -> Result<GgufMetadata>;
 }
 
 impl<T: AsRef<Path>> GgufExt for T {
@@ -27,43 +30,11 @@ impl<T: AsRef<Path>> GgufExt for T {
         let map = unsafe { Mmap::map(&file)? };
         let mut reader = Cursor::new(&map[..]);
 
-        let magic = reader.read_u32::<LittleEndian>()?;
-        if magic != GGUF_MAGIC {
-            return Err(Error::InvalidMagic);
-        }
-
-        let (version, is_little_endian) = {
-            reader.seek(SeekFrom::Start(4))?;
-            let version_le = reader.read_u32::<LittleEndian>()?;
-
-            if version_le & 65535 != 0 {
-                (version_le, true)
-            } else {
-                reader.seek(SeekFrom::Start(4))?;
-                let version_be = reader.read_u32::<BigEndian>()?;
-                (version_be, false)
-            }
-        };
-
-        if version > 3 {
-            return Err(Error::UnsupportedVersion(version));
-        }
-
-        // Reset position to after version
-        reader.seek(SeekFrom::Start(8))?;
-
-        let _tensor_count = read_versioned_size(&mut reader, version, is_little_endian)?;
-        let metadata_kv_count = read_versioned_size(&mut reader, version, is_little_endian)?;
+        let (version, is_little_endian, metadata_kv_count) = read_header(&mut reader)?;
 
         for _ in 0..metadata_kv_count {
             let key = read_string(&mut reader, version, is_little_endian)?;
-
-            let value_type_raw = if is_little_endian {
-                reader.read_u32::<LittleEndian>()?
-            } else {
-                reader.read_u32::<BigEndian>()?
-            };
-            let value_type = GGUFMetadataValueType::try_from(value_type_raw)?;
+            let value_type = read_value_type(&mut reader, is_little_endian)?;
 
             if key == "tokenizer.chat_template" {
                 if let GGUFMetadataValueType::String = value_type {
@@ -78,25 +49,14 @@ impl<T: AsRef<Path>> GgufExt for T {
         }
 
         // If we didn't find a chat template, try to infer from architecture
-        reader.seek(SeekFrom::Start(8))?;
-
-        // Skip tensor count
-        read_versioned_size(&mut reader, version, is_little_endian)?;
-
-        // Read metadata count again
-        let metadata_kv_count = read_versioned_size(&mut reader, version, is_little_endian)?;
+        reader.seek(SeekFrom::Start(0))?;
+        let (_, _, metadata_kv_count) = read_header(&mut reader)?;
 
         let mut architecture = None;
 
         for _ in 0..metadata_kv_count {
             let key = read_string(&mut reader, version, is_little_endian)?;
-
-            let value_type_raw = if is_little_endian {
-                reader.read_u32::<LittleEndian>()?
-            } else {
-                reader.read_u32::<BigEndian>()?
-            };
-            let value_type = GGUFMetadataValueType::try_from(value_type_raw)?;
+            let value_type = read_value_type(&mut reader, is_little_endian)?;
 
             if key == "general.architecture" {
                 if let GGUFMetadataValueType::String = value_type {
@@ -130,6 +90,19 @@ impl<T: AsRef<Path>> GgufExt for T {
             Ok(None)
         }
     }
+
+    fn gguf_metadata(&self) -> Result<GgufMetadata> {
+        let file = File::open(self.as_ref())?;
+        let map = unsafe { Mmap::map(&file)? };
+        let mut metadata = read_metadata(&mut Cursor::new(&map[..]))?;
+
+        if metadata.chat_template.is_none() {
+            metadata.chat_template = self.gguf_chat_format()?.map(|t| t.as_ref().to_string());
+        }
+        metadata.size_bytes = map.len() as u64;
+
+        Ok(metadata)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+
+use crate::{
+    read_header, read_integer, read_string, read_value_type, skip_value, Error,
+    GGUFMetadataValueType,
+};
+
+/// What we need to know about a model before loading it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GgufMetadata {
+    pub name: Option<String>,
+    pub architecture: Option<String>,
+    pub context_length: Option<u64>,
+    /// The Jinja template in the file, or the name of a llama.cpp built-in one inferred from the architecture.
+    pub chat_template: Option<String>,
+    /// Like `Q4_K_M`, from `general.file_type`.
+    pub quantization: Option<String>,
+    pub size_bytes: u64,
+}
+
+pub(crate) fn read_metadata<R: Read + Seek>(reader: &mut R) -> Result<GgufMetadata, Error> {
+    let (version, is_little_endian, metadata_kv_count) = read_header(reader)?;
+
+    let mut metadata = GgufMetadata::default();
+    // Keyed by architecture, which may come after them.
+    let mut context_lengths = HashMap::new();
+
+    for _ in 0..metadata_kv_count {
+        let key = read_string(reader, version, is_little_endian)?;
+        let value_type = read_value_type(reader, is_little_endian)?;
+
+        match (key.as_str(), value_type) {
+            ("general.name", GGUFMetadataValueType::String) => {
+                metadata.name = Some(read_string(reader, version, is_little_endian)?);
+            }
+            ("general.architecture", GGUFMetadataValueType::String) => {
+                metadata.architecture = Some(read_string(reader, version, is_little_endian)?);
+            }
+            ("tokenizer.chat_template", GGUFMetadataValueType::String) => {
+                metadata.chat_template = Some(read_string(reader, version, is_little_endian)?);
+            }
+            ("general.file_type", _) => {
+                metadata.quantization =
+                    read_integer(reader, value_type, version, is_little_endian)?
+                        .and_then(file_type_name)
+                        .map(str::to_string);
+            }
+            (key, _) if key.ends_with(".context_length") => {
+                if let Some(length) = read_integer(reader, value_type, version, is_little_endian)? {
+                    context_lengths
+                        .insert(key.trim_end_matches(".context_length").to_string(), length);
+                }
+            }
+            _ => skip_value(reader, value_type, version, is_little_endian)?,
+        }
+    }
+
+    metadata.context_length = metadata
+        .architecture
+        .as_ref()
+        .and_then(|arch| context_lengths.get(arch).copied());
+
+    Ok(metadata)
+}
+
+// https://github.com/ggml-org/llama.cpp/blob/master/include/llama.h (`llama_ftype`)
+fn file_type_name(file_type: u64) -> Option<&'static str> {
+    let name = match file_type {
+        0 => "F32",
+        1 => "F16",
+        2 => "Q4_0",
+        3 => "Q4_1",
+        7 => "Q8_0",
+        8 => "Q5_0",
+        9 => "Q5_1",
+        10 => "Q2_K",
+        11 => "Q3_K_S",
+        12 => "Q3_K_M",
+        13 => "Q3_K_L",
+        14 => "Q4_K_S",
+        15 => "Q4_K_M",
+        16 => "Q5_K_S",
+        17 => "Q5_K_M",
+        18 => "Q6_K",
+        19 => "IQ2_XXS",
+        20 => "IQ2_XS",
+        21 => "Q2_K_S",
+        22 => "IQ3_XS",
+        23 => "IQ3_XXS",
+        24 => "IQ1_S",
+        25 => "IQ4_NL",
+        26 => "IQ3_S",
+        27 => "IQ3_M",
+        28 => "IQ2_S",
+        29 => "IQ2_M",
+        30 => "IQ4_XS",
+        31 => "IQ1_M",
+        32 => "BF16",
+        36 => "TQ1_0",
+        37 => "TQ2_0",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn string(buf: &mut Vec<u8>, s: &str) {
+        buf.extend((s.len() as u64).to_le_bytes());
+        buf.extend(s.as_bytes());
+    }
+
+    fn entry(buf: &mut Vec<u8>, key: &str, value_type: u32, value: &[u8]) {
+        string(buf, key);
+        buf.extend(value_type.to_le_bytes());
+        buf.extend(value);
+    }
+
+    #[test]
+    fn test_read_metadata() {
+        let mut buf = Vec::new();
+        buf.extend(crate::GGUF_MAGIC.to_le_bytes());
+        buf.extend(3u32.to_le_bytes());
+        buf.extend(0u64.to_le_bytes());
+        buf.extend(6u64.to_le_bytes());
+
+        entry(&mut buf, "qwen3.context_length", 4, &40960u32.to_le_bytes());
+        entry(&mut buf, "general.file_type", 4, &15u32.to_le_bytes());
+        entry(&mut buf, "general.alignment", 4, &32u32.to_le_bytes());
+
+        let mut name = Vec::new();
+        string(&mut name, "Qwen3 4B");
+        entry(&mut buf, "general.name", 8, &name);
+
+        let mut arch = Vec::new();
+        string(&mut arch, "qwen3");
+        entry(&mut buf, "general.architecture", 8, &arch);
+
+        let mut template = Vec::new();
+        string(&mut template, "{{ messages }}");
+        entry(&mut buf, "tokenizer.chat_template", 8, &template);
+
+        assert_eq!(
+            read_metadata(&mut Cursor::new(buf)).unwrap(),
+            GgufMetadata {
+                name: Some("Qwen3 4B".to_string()),
+                architecture: Some("qwen3".to_string()),
+                context_length: Some(40960),
+                chat_template: Some("{{ messages }}".to_string()),
+                quantization: Some("Q4_K_M".to_string()),
+                size_bytes: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_read_metadata_invalid_magic() {
+        assert!(matches!(
+            read_metadata(&mut Cursor::new(vec![0u8; 24])),
+            Err(Error::InvalidMagic)
+        ));
+    }
+}
@@ -1,7 +1,78 @@
 use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
 use std::io::{Read, Seek, SeekFrom};
 
-use crate::{value::GGUFMetadataValueType, Error};
+use crate::{value::GGUFMetadataValueType, Error, GGUF_MAGIC};
+
+/// Reads the header up to the metadata, returning the version, endianness and number of metadata entries.
+pub fn read_header<R: Read + Seek>(reader: &mut R) -> Result<(u32, bool, u64), Error> {
+    let magic = reader.read_u32::<LittleEndian>()?;
+    if magic != GGUF_MAGIC {
+        return Err(Error::InvalidMagic);
+    }
+
+    let (version, is_little_endian) = {
+        reader.seek(SeekFrom::Start(4))?;
+        let version_le = reader.read_u32::<LittleEndian>()?;
+
+        if version_le & 65535 != 0 {
+            (version_le, true)
+        } else {
+            reader.seek(SeekFrom::Start(4))?;
+            let version_be = reader.read_u32::<BigEndian>()?;
+            (version_be, false)
+        }
+    };
+
+    if version > 3 {
+        return Err(Error::UnsupportedVersion(version));
+    }
+
+    // Reset position to after version
+    reader.seek(SeekFrom::Start(8))?;
+
+    let _tensor_count = read_versioned_size(reader, version, is_little_endian)?;
+    let metadata_kv_count = read_versioned_size(reader, version, is_little_endian)?;
+
+    Ok((version, is_little_endian, metadata_kv_count))
+}
+
+pub fn read_value_type<R: Read + Seek>(
+    reader: &mut R,
+    is_little_endian: bool,
+) -> Result<GGUFMetadataValueType, Error> {
+    let value_type_raw = if is_little_endian {
+        reader.read_u32::<LittleEndian>()?
+    } else {
+        reader.read_u32::<BigEndian>()?
+    };
+    GGUFMetadataValueType::try_from(value_type_raw)
+}
+
+/// Reads an unsigned integer value. Other values are skipped, and give `None`.
+pub fn read_integer<R: Read + Seek>(
+    reader: &mut R,
+    value_type: GGUFMetadataValueType,
+    version: u32,
+    is_little_endian: bool,
+) -> Result<Option<u64>, Error> {
+    let value = match (value_type, is_little_endian) {
+        (GGUFMetadataValueType::Uint8, _) => reader.read_u8()? as u64,
+        (GGUFMetadataValueType::Uint16, true) => reader.read_u16::<LittleEndian>()? as u64,
+        (GGUFMetadataValueType::Uint16, false) => reader.read_u16::<BigEndian>()? as u64,
+        (GGUFMetadataValueType::Uint32, true) => reader.read_u32::<LittleEndian>()? as u64,
+        (GGUFMetadataValueType::Uint32, false) => reader.read_u32::<BigEndian>()? as u64,
+        (GGUFMetadataValueType::Uint64, true) => reader.read_u64::<LittleEndian>()?,
+        (GGUFMetadataValueType::Uint64, false) => reader.read_u64::<BigEndian>()?,
+        (GGUFMetadataValueType::Int32, true) => reader.read_i32::<LittleEndian>()?.max(0) as u64,
+        (GGUFMetadataValueType::Int32, false) => reader.read_i32::<BigEndian>()?.max(0) as u64,
+        _ => {
+            skip_value(reader, value_type, version, is_little_endian)?;
+            return Ok(None);
+        }
+    };
+
+    Ok(Some(value))
+}
 
 pub fn read_versioned_size<R: Read + Seek>(
     reader: &mut R,
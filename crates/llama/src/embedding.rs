@@ -0,0 +1,68 @@
+use llama_cpp_2::{
+    context::params::LlamaContextParams,
+    llama_batch::LlamaBatch,
+    model::{AddBos, LlamaModel},
+};
+
+// Longer inputs are truncated. Chunks we embed are well under this.
+const MAX_TOKENS: usize = 2048;
+
+/// Embeds text with an embedding model, like nomic-embed-text.
+pub struct Embedder {
+    model: LlamaModel,
+}
+
+impl Embedder {
+    pub fn new(model_path: impl AsRef<std::path::Path>) -> Result<Self, crate::Error> {
+        crate::Llama::setup_log();
+
+        let model = crate::Llama::load_model(model_path)?;
+        Ok(Self { model })
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.model.n_embd() as usize
+    }
+
+    /// One L2-normalized vector per text. This blocks, so keep it off the async runtime.
+    pub fn embed(&self, texts: &[impl AsRef<str>]) -> Result<Vec<Vec<f32>>, crate::Error> {
+        let backend = crate::Llama::get_backend();
+
+        let mut ctx = self.model.new_context(
+            &backend,
+            LlamaContextParams::default()
+                .with_n_ctx(std::num::NonZeroU32::new(MAX_TOKENS as u32))
+                .with_n_batch(MAX_TOKENS as u32)
+                // Encoder models need the whole input in one micro-batch.
+                .with_n_ubatch(MAX_TOKENS as u32)
+                .with_embeddings(true),
+        )?;
+
+        let mut batch = LlamaBatch::new(MAX_TOKENS, 1);
+        let mut embeddings = Vec::with_capacity(texts.len());
+
+        for text in texts {
+            let mut tokens = self.model.str_to_token(text.as_ref(), AddBos::Always)?;
+            tokens.truncate(MAX_TOKENS);
+
+            batch.clear();
+            batch.add_sequence(&tokens, 0, false)?;
+
+            ctx.clear_kv_cache();
+            ctx.decode(&mut batch)?;
+
+            embeddings.push(normalize(ctx.embeddings_seq_ith(0)?));
+        }
+
+        Ok(embeddings)
+    }
+}
+
+fn normalize(embedding: &[f32]) -> Vec<f32> {
+    let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return embedding.to_vec();
+    }
+
+    embedding.iter().map(|v| v / norm).collect()
+}
@@ -5,12 +5,20 @@ pub enum Error {
     #[error(transparent)]
     GgufError(#[from] hypr_gguf::Error),
     #[error(transparent)]
+    GbnfError(#[from] hypr_gbnf::Error),
+    #[error(transparent)]
+    TemplateError(#[from] minijinja::Error),
+    #[error(transparent)]
     LlamaCppError(#[from] llama_cpp_2::LLamaCppError),
     #[error(transparent)]
     LlamaModelLoadError(#[from] llama_cpp_2::LlamaModelLoadError),
     #[error(transparent)]
     LlamaContextLoadError(#[from] llama_cpp_2::LlamaContextLoadError),
     #[error(transparent)]
+    ApplyChatTemplateError(#[from] llama_cpp_2::ApplyChatTemplateError),
+    #[error(transparent)]
+    NewLlamaChatMessageError(#[from] llama_cpp_2::NewLlamaChatMessageError),
+    #[error(transparent)]
     StringToTokenError(#[from] llama_cpp_2::StringToTokenError),
     #[error(transparent)]
     TokenToStringError(#[from] llama_cpp_2::TokenToStringError),
@@ -19,7 +27,19 @@ pub enum Error {
     #[error(transparent)]
     DecodeError(#[from] llama_cpp_2::DecodeError),
     #[error(transparent)]
+    EmbeddingsError(#[from] llama_cpp_2::EmbeddingsError),
+    #[error(transparent)]
     TaskSendError(#[from] tokio::sync::mpsc::error::SendError<crate::Task>),
+    #[error("prompt is {input_tokens} tokens, but at most {max_input_tokens} fit in the context")]
+    ContextLengthExceeded {
+        input_tokens: usize,
+        max_input_tokens: usize,
+    },
+    #[error("max_tokens is {max_output_tokens}, but the context only has {context_length} tokens")]
+    MaxTokensExceeded {
+        max_output_tokens: u32,
+        context_length: u32,
+    },
 }
 
 impl Serialize for Error {
@@ -7,21 +7,32 @@ use llama_cpp_2::{
     llama_batch::LlamaBatch,
     model::{params::LlamaModelParams, AddBos, LlamaChatTemplate, LlamaModel, Special},
     sampling::LlamaSampler,
-    send_logs_to_tracing, LogOptions,
+    send_logs_to_tracing,
+    token::LlamaToken,
+    LogOptions,
 };
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tokio_util::sync::CancellationToken;
 
-use hypr_gguf::GgufExt;
+use hypr_gguf::{ChatTemplate, GgufExt};
 
+mod embedding;
 mod error;
+mod stop;
+mod tools;
 mod types;
 
+pub use embedding::*;
 pub use error::*;
+pub use tools::{parse_tool_calls, starts_tool_call};
 pub use types::*;
 
-const DEFAULT_MAX_INPUT_TOKENS: u32 = 1024 * 16;
-const DEFAULT_MAX_OUTPUT_TOKENS: u32 = 1024 * 2;
+pub const DEFAULT_MAX_INPUT_TOKENS: u32 = 1024 * 16;
+pub const DEFAULT_MAX_OUTPUT_TOKENS: u32 = 1024 * 2;
+const DEFAULT_TEMPERATURE: f32 = 0.6;
+const DEFAULT_SEED: u32 = 1234;
+// Penalties look at this many of the latest tokens.
+const PENALTY_LAST_N: i32 = 64;
 
 static LLAMA_BACKEND: OnceLock<Arc<LlamaBackend>> = OnceLock::new();
 
@@ -31,14 +42,39 @@ pub enum ModelName {
     Other(Option<String>),
 }
 
+impl ModelName {
+    /// Used for whatever the request leaves unset.
+    pub fn default_sampling(&self) -> SamplingParams {
+        match self {
+            ModelName::HyprLLM => SamplingParams {
+                temperature: Some(DEFAULT_TEMPERATURE),
+                seed: Some(DEFAULT_SEED),
+                ..Default::default()
+            },
+            // https://huggingface.co/Qwen/Qwen3-1.7B-GGUF
+            ModelName::Other(_) => SamplingParams {
+                temperature: Some(DEFAULT_TEMPERATURE),
+                top_p: Some(0.95),
+                seed: Some(DEFAULT_SEED),
+                ..Default::default()
+            },
+        }
+    }
+}
+
 pub struct Llama {
     pub name: ModelName,
+    model: Arc<LlamaModel>,
+    tpl: LlamaChatTemplate,
+    // The template as written in the GGUF, if it has one. llama.cpp can't render tools with it.
+    jinja_template: Option<String>,
     task_sender: tokio::sync::mpsc::UnboundedSender<Task>,
 }
 
 pub enum Task {
     Generate {
         request: LlamaRequest,
+        tokens: Vec<LlamaToken>,
         response_sender: tokio::sync::mpsc::UnboundedSender<String>,
         callback: Box<dyn FnMut(f64) + Send + 'static>,
         cancellation_token: CancellationToken,
@@ -81,7 +117,11 @@ impl Llama {
         }
     }
 
-    fn get_sampler(model: &LlamaModel, grammar: Option<&str>) -> LlamaSampler {
+    fn get_sampler(
+        model: &LlamaModel,
+        grammar: Option<&str>,
+        params: &SamplingParams,
+    ) -> LlamaSampler {
         let mut samplers = Vec::new();
 
         if let Some(grammar) = grammar {
@@ -94,11 +134,29 @@ impl Llama {
             }
         }
 
-        {
-            // https://huggingface.co/Qwen/Qwen3-1.7B-GGUF
-            samplers.push(LlamaSampler::temp(0.6));
-            samplers.push(LlamaSampler::penalties(0, 1.5, 0.2, 1.4));
-            samplers.push(LlamaSampler::mirostat_v2(1234, 3.0, 0.2));
+        let frequency_penalty = params.frequency_penalty.unwrap_or(0.0);
+        let presence_penalty = params.presence_penalty.unwrap_or(0.0);
+        if frequency_penalty != 0.0 || presence_penalty != 0.0 {
+            samplers.push(LlamaSampler::penalties(
+                PENALTY_LAST_N,
+                1.0,
+                frequency_penalty,
+                presence_penalty,
+            ));
+        }
+
+        let temperature = params.temperature.unwrap_or(DEFAULT_TEMPERATURE);
+        let seed = params.seed.unwrap_or(DEFAULT_SEED);
+
+        if temperature <= 0.0 {
+            samplers.push(LlamaSampler::greedy());
+        } else if let Some(top_p) = params.top_p {
+            samplers.push(LlamaSampler::top_p(top_p, 1));
+            samplers.push(LlamaSampler::temp(temperature));
+            samplers.push(LlamaSampler::dist(seed));
+        } else {
+            samplers.push(LlamaSampler::temp(temperature));
+            samplers.push(LlamaSampler::mirostat_v2(seed, 3.0, 0.2));
         }
 
         LlamaSampler::chain_simple(samplers)
@@ -107,8 +165,8 @@ impl Llama {
     fn process_prefill<'a>(
         model: &'a LlamaModel,
         backend: &LlamaBackend,
-        tpl: &LlamaChatTemplate,
-        request: &LlamaRequest,
+        tokens_list: Vec<LlamaToken>,
+        max_output_tokens: u32,
         callback: Box<dyn FnMut(f64) + Send + 'static>,
         cancellation_token: CancellationToken,
     ) -> Result<
@@ -120,12 +178,6 @@ impl Llama {
         ),
         crate::Error,
     > {
-        let prompt = model
-            .apply_chat_template(tpl, &request.messages, true)
-            .unwrap();
-
-        let mut tokens_list = model.str_to_token(&prompt, AddBos::Always).unwrap();
-        tokens_list.truncate(DEFAULT_MAX_INPUT_TOKENS as usize);
         let input_tokens_len = tokens_list.len() as u32;
 
         let progress_data = Box::new(ProgressData {
@@ -187,7 +239,7 @@ impl Llama {
                 backend,
                 LlamaContextParams::default()
                     .with_n_ctx(std::num::NonZeroU32::new(
-                        input_tokens_len + DEFAULT_MAX_OUTPUT_TOKENS,
+                        input_tokens_len + max_output_tokens,
                     ))
                     .with_n_batch(input_tokens_len)
                     .with_embeddings(false)
@@ -235,9 +287,10 @@ impl Llama {
     ) {
         let mut n_cur = batch.n_tokens();
         let mut decoder = encoding_rs::UTF_8.new_decoder();
-        let mut sampler = Self::get_sampler(model, request.grammar.as_deref());
+        let mut sampler = Self::get_sampler(model, request.grammar.as_deref(), &request.sampling);
+        let mut stop = stop::StopSequences::new(request.stop.clone());
 
-        while n_cur <= last_index + DEFAULT_MAX_OUTPUT_TOKENS as i32 {
+        while n_cur <= last_index + request.max_output_tokens() as i32 {
             if cancellation_token.is_cancelled() {
                 break;
             }
@@ -258,7 +311,16 @@ impl Llama {
                 io::stdout().flush().unwrap();
             }
 
-            if response_sender.send(output_string).is_err() {
+            let (output_string, stopped) = match stop.push(&output_string) {
+                stop::StopResult::Continue(text) => (text, false),
+                stop::StopResult::Stop(text) => (text, true),
+            };
+
+            if !output_string.is_empty() && response_sender.send(output_string).is_err() {
+                break;
+            }
+
+            if stopped {
                 break;
             }
 
@@ -269,6 +331,11 @@ impl Llama {
             ctx.decode(&mut batch).unwrap();
         }
 
+        let rest = stop.finish();
+        if !rest.is_empty() {
+            let _ = response_sender.send(rest);
+        }
+
         drop(response_sender);
 
         unsafe {
@@ -285,9 +352,13 @@ impl Llama {
 
         let fmt = model_path.gguf_chat_format()?.unwrap();
         let tpl = LlamaChatTemplate::new(fmt.as_ref()).unwrap();
+        let jinja_template = match &fmt {
+            ChatTemplate::TemplateValue(template) => Some(template.clone()),
+            ChatTemplate::TemplateKey(_) => None,
+        };
 
         let backend = Self::get_backend();
-        let model = Self::load_model(model_path)?;
+        let model = Arc::new(Self::load_model(model_path)?);
         let name = match model.meta_val_str("general.name") {
             Ok(name) if name == "hypr-llm" => ModelName::HyprLLM,
             Ok(name) => ModelName::Other(Some(name.to_string())),
@@ -297,11 +368,14 @@ impl Llama {
         let (task_sender, mut task_receiver) = tokio::sync::mpsc::unbounded_channel::<Task>();
 
         std::thread::spawn({
+            let model = model.clone();
+
             move || {
                 while let Some(task) = task_receiver.blocking_recv() {
                     match task {
                         Task::Generate {
                             request,
+                            tokens,
                             response_sender,
                             callback,
                             cancellation_token,
@@ -309,8 +383,8 @@ impl Llama {
                             match Self::process_prefill(
                                 &model,
                                 &backend,
-                                &tpl,
-                                &request,
+                                tokens,
+                                request.max_output_tokens(),
                                 callback,
                                 cancellation_token.clone(),
                             ) {
@@ -337,7 +411,87 @@ impl Llama {
             }
         });
 
-        Ok(Self { name, task_sender })
+        Ok(Self {
+            name,
+            model,
+            tpl,
+            jinja_template,
+            task_sender,
+        })
+    }
+
+    fn apply_chat_template(&self, messages: &[LlamaChatMessage]) -> Result<String, crate::Error> {
+        let messages = messages
+            .iter()
+            .map(LlamaChatMessage::to_llama)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.model
+            .apply_chat_template(&self.tpl, &messages, true)
+            .map_err(Into::into)
+    }
+
+    fn apply_chat_template_with_tools(
+        &self,
+        request: &LlamaRequest,
+    ) -> Result<String, crate::Error> {
+        // Templates that never mention tools would silently drop them.
+        let template = self.jinja_template.as_ref().filter(|t| t.contains("tools"));
+
+        if let Some(template) = template {
+            match tools::render_template(template, &request.messages, &request.tools) {
+                Ok(prompt) => return Ok(prompt),
+                Err(e) => tracing::warn!("chat_template_render_failed: {:?}", e),
+            }
+        }
+
+        self.apply_chat_template(&tools::inline_tools(&request.messages, &request.tools))
+    }
+
+    fn tokenize(&self, request: &LlamaRequest) -> Result<Vec<LlamaToken>, crate::Error> {
+        // Earlier tool calls and results need the tools path too, even if no tools are offered anymore.
+        let uses_tools = !request.tools.is_empty()
+            || request
+                .messages
+                .iter()
+                .any(|m| !m.tool_calls.is_empty() || m.tool_call_id.is_some());
+
+        let prompt = if uses_tools {
+            self.apply_chat_template_with_tools(request)?
+        } else {
+            self.apply_chat_template(&request.messages)?
+        };
+
+        self.model
+            .str_to_token(&prompt, AddBos::Always)
+            .map_err(Into::into)
+    }
+
+    /// Number of tokens the prompt of `request` takes, after applying the model's chat template.
+    pub fn count_tokens(&self, request: &LlamaRequest) -> Result<usize, crate::Error> {
+        self.tokenize(request).map(|tokens| tokens.len())
+    }
+
+    /// Context length the model was trained with. Prompt and output together must fit in it.
+    pub fn context_length(&self) -> u32 {
+        self.model.n_ctx_train()
+    }
+
+    /// Largest prompt `request` may have, given its own limits and the room it asks for the output.
+    ///
+    /// Fails if the output alone would take up the whole context.
+    pub fn max_input_tokens(&self, request: &LlamaRequest) -> Result<usize, crate::Error> {
+        let context_length = self.context_length();
+        let max_output_tokens = request.max_output_tokens();
+        if max_output_tokens >= context_length {
+            return Err(crate::Error::MaxTokensExceeded {
+                max_output_tokens,
+                context_length,
+            });
+        }
+
+        let room = context_length - max_output_tokens;
+        Ok(request.max_input_tokens().min(room) as usize)
     }
 
     pub fn generate_stream(
@@ -352,14 +506,30 @@ impl Llama {
 
     pub fn generate_stream_with_callback(
         &self,
-        request: LlamaRequest,
+        mut request: LlamaRequest,
         callback: Box<dyn FnMut(f64) + Send + 'static>,
     ) -> Result<(impl futures_util::Stream<Item = String>, CancellationToken), crate::Error> {
+        // Checked before queueing, so an oversized prompt fails the request instead of being cut short.
+        let tokens = self.tokenize(&request)?;
+        let max_input_tokens = self.max_input_tokens(&request)?;
+        if tokens.len() > max_input_tokens {
+            return Err(crate::Error::ContextLengthExceeded {
+                input_tokens: tokens.len(),
+                max_input_tokens,
+            });
+        }
+
+        request.sampling = request.sampling.or(self.name.default_sampling());
+        if !request.tools.is_empty() {
+            request.grammar = Some(tools::grammar(&request.tools, request.tool_choice)?);
+        }
+
         let (response_sender, response_receiver) = tokio::sync::mpsc::unbounded_channel::<String>();
         let cancellation_token = CancellationToken::new();
 
         let task = Task::Generate {
             request,
+            tokens,
             response_sender,
             callback,
             cancellation_token: cancellation_token.clone(),
@@ -421,6 +591,7 @@ mod tests {
                 LlamaChatMessage::new("user".into(), hypr_data::english_3::WORDS_JSON.repeat(1))
                     .unwrap(),
             ],
+            ..Default::default()
         }
     }
 
@@ -0,0 +1,96 @@
+/// Cuts the output at the first stop sequence.
+/// Text that might be the start of one is held back until we know it isn't.
+pub struct StopSequences {
+    sequences: Vec<String>,
+    pending: String,
+}
+
+pub enum StopResult {
+    Continue(String),
+    Stop(String),
+}
+
+impl StopSequences {
+    pub fn new(sequences: Vec<String>) -> Self {
+        Self {
+            sequences: sequences.into_iter().filter(|s| !s.is_empty()).collect(),
+            pending: String::new(),
+        }
+    }
+
+    /// Returns what is safe to send so far.
+    pub fn push(&mut self, text: &str) -> StopResult {
+        self.pending.push_str(text);
+
+        let first_match = self
+            .sequences
+            .iter()
+            .filter_map(|s| self.pending.find(s.as_str()))
+            .min();
+
+        if let Some(index) = first_match {
+            self.pending.truncate(index);
+            return StopResult::Stop(std::mem::take(&mut self.pending));
+        }
+
+        let held = self
+            .sequences
+            .iter()
+            .map(|s| longest_suffix_prefix(&self.pending, s))
+            .max()
+            .unwrap_or(0);
+
+        let rest = self.pending.split_off(self.pending.len() - held);
+        StopResult::Continue(std::mem::replace(&mut self.pending, rest))
+    }
+
+    /// Whatever was held back, once the output ends without a stop sequence.
+    pub fn finish(self) -> String {
+        self.pending
+    }
+}
+
+// Length of the longest suffix of `text` that is a proper prefix of `sequence`.
+fn longest_suffix_prefix(text: &str, sequence: &str) -> usize {
+    (1..sequence.len().min(text.len() + 1))
+        .rev()
+        .filter(|&len| sequence.is_char_boundary(len) && text.is_char_boundary(text.len() - len))
+        .find(|&len| text.ends_with(&sequence[..len]))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(sequences: &[&str], chunks: &[&str]) -> (String, bool) {
+        let mut stop = StopSequences::new(sequences.iter().map(|s| s.to_string()).collect());
+        let mut out = String::new();
+
+        for chunk in chunks {
+            match stop.push(chunk) {
+                StopResult::Continue(text) => out += &text,
+                StopResult::Stop(text) => return (out + &text, true),
+            }
+        }
+
+        (out + &stop.finish(), false)
+    }
+
+    #[test]
+    fn test_stop_sequences() {
+        assert_eq!(run(&[], &["a", "b"]), ("ab".to_string(), false));
+        assert_eq!(
+            run(&["</note>"], &["hello </no", "te> world"]),
+            ("hello ".to_string(), true)
+        );
+        assert_eq!(
+            run(&["</note>"], &["a </n", "ope"]),
+            ("a </nope".to_string(), false)
+        );
+        assert_eq!(
+            run(&["END", "##"], &["x #", "# y END"]),
+            ("x ".to_string(), true)
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use crate::{FunctionCall, LlamaChatMessage, Tool, ToolChoice};
+
+// Hermes format, which Qwen models are trained on. The grammar makes other models follow it too.
+const TOOL_CALL_START: &str = "<tool_call>";
+const TOOL_CALL_END: &str = "</tool_call>";
+const THINK_START: &str = "<think>";
+const THINK_END: &str = "</think>";
+
+/// Renders the conversation with the model's own Jinja chat template, which knows how to describe tools to it.
+pub(crate) fn render_template(
+    template: &str,
+    messages: &[LlamaChatMessage],
+    tools: &[Tool],
+) -> Result<String, crate::Error> {
+    let mut env = minijinja::Environment::new();
+    env.set_unknown_method_callback(minijinja_contrib::pycompat::unknown_method_callback);
+    env.add_function("raise_exception", |message: String| -> Result<String, _> {
+        Err(minijinja::Error::new(
+            minijinja::ErrorKind::InvalidOperation,
+            message,
+        ))
+    });
+
+    let tpl = env.template_from_str(template)?;
+    let prompt = tpl.render(minijinja::context! {
+        messages => messages,
+        tools => tools,
+        add_generation_prompt => true,
+        // Added by the tokenizer already.
+        bos_token => "",
+        eos_token => "",
+    })?;
+
+    Ok(prompt)
+}
+
+/// For chat templates that don't know about tools. They are described in the system prompt instead,
+/// and earlier calls and results become plain text.
+pub(crate) fn inline_tools(messages: &[LlamaChatMessage], tools: &[Tool]) -> Vec<LlamaChatMessage> {
+    let definitions = tools
+        .iter()
+        .map(|tool| serde_json::to_string(tool).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let instructions = format!(
+        "# Tools\n\n\
+        You may call one or more functions to assist with the user query.\n\n\
+        You are provided with function signatures within <tools></tools> XML tags:\n\
+        <tools>\n{definitions}\n</tools>\n\n\
+        For each function call, return a json object with function name and arguments within {TOOL_CALL_START}{TOOL_CALL_END} XML tags:\n\
+        {TOOL_CALL_START}\n{{\"name\": <function-name>, \"arguments\": <args-json-object>}}\n{TOOL_CALL_END}"
+    );
+
+    let mut inlined = Vec::with_capacity(messages.len() + 1);
+
+    match messages.first() {
+        Some(first) if first.role == "system" => {
+            inlined.push(LlamaChatMessage {
+                content: format!("{}\n\n{}", first.content, instructions),
+                ..first.clone()
+            });
+        }
+        _ => inlined.push(LlamaChatMessage {
+            role: "system".to_string(),
+            content: instructions,
+            tool_calls: vec![],
+            tool_call_id: None,
+        }),
+    }
+
+    let skip = usize::from(messages.first().is_some_and(|m| m.role == "system"));
+
+    for message in &messages[skip..] {
+        if message.role == "tool" {
+            inlined.push(LlamaChatMessage {
+                role: "user".to_string(),
+                content: format!("<tool_response>\n{}\n</tool_response>", message.content),
+                tool_calls: vec![],
+                tool_call_id: None,
+            });
+            continue;
+        }
+
+        let mut content = message.content.clone();
+        for call in &message.tool_calls {
+            content.push_str(&format!(
+                "\n{}\n{}\n{}",
+                TOOL_CALL_START,
+                serde_json::json!({
+                    "name": call.function.name,
+                    "arguments": serde_json::from_str::<serde_json::Value>(&call.function.arguments)
+                        .unwrap_or_default(),
+                }),
+                TOOL_CALL_END
+            ));
+        }
+
+        inlined.push(LlamaChatMessage {
+            role: message.role.clone(),
+            content: content.trim().to_string(),
+            tool_calls: vec![],
+            tool_call_id: None,
+        });
+    }
+
+    inlined
+}
+
+pub(crate) fn grammar(tools: &[Tool], choice: ToolChoice) -> Result<String, crate::Error> {
+    let tools = tools
+        .iter()
+        .map(|tool| (tool.name.clone(), tool.parameters.clone()))
+        .collect::<Vec<_>>();
+
+    hypr_gbnf::tool_calls(&tools, choice == ToolChoice::Required).map_err(Into::into)
+}
+
+#[derive(serde::Deserialize)]
+struct RawCall {
+    name: String,
+    #[serde(default, alias = "parameters")]
+    arguments: serde_json::Value,
+}
+
+/// Splits the output of a request with tools into its text and the calls in it.
+/// Calls that can't be parsed are left in the text.
+pub fn parse_tool_calls(output: &str) -> (String, Vec<FunctionCall>) {
+    let mut content = String::new();
+    let mut calls = Vec::new();
+    let mut rest = output;
+
+    while let Some(start) = rest.find(TOOL_CALL_START) {
+        content.push_str(&rest[..start]);
+
+        let body_start = start + TOOL_CALL_START.len();
+        let Some(len) = rest[body_start..].find(TOOL_CALL_END) else {
+            rest = &rest[start..];
+            break;
+        };
+        let end = body_start + len + TOOL_CALL_END.len();
+
+        match serde_json::from_str::<RawCall>(rest[body_start..body_start + len].trim()) {
+            Ok(call) => calls.push(FunctionCall {
+                name: call.name,
+                arguments: if call.arguments.is_null() {
+                    "{}".to_string()
+                } else {
+                    call.arguments.to_string()
+                },
+            }),
+            Err(_) => content.push_str(&rest[start..end]),
+        }
+
+        rest = &rest[end..];
+    }
+
+    content.push_str(rest);
+    (content.trim().to_string(), calls)
+}
+
+/// Whether output starting with `prefix` is heading for tool calls, or `None` while that can't be told yet.
+/// The grammar doesn't let text start like a call, so once it is text, the rest can be streamed as is.
+pub fn starts_tool_call(prefix: &str) -> Option<bool> {
+    let rest = match prefix.strip_prefix(THINK_START) {
+        Some(think) => &think[think.find(THINK_END)? + THINK_END.len()..],
+        None if THINK_START.starts_with(prefix) => return None,
+        None => prefix,
+    };
+
+    let rest = rest.trim_start();
+    if rest.starts_with(TOOL_CALL_START) {
+        Some(true)
+    } else if TOOL_CALL_START.starts_with(rest) {
+        None
+    } else {
+        Some(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tool_calls() {
+        let (content, calls) = parse_tool_calls(
+            "<think>\nSearching first.\n</think>\n\n<tool_call>\n{\"name\": \"search_sessions\", \"arguments\": {\"query\": \"roadmap\"}}\n</tool_call>\n<tool_call>\n{\"name\": \"list_participants\"}\n</tool_call>",
+        );
+
+        assert_eq!(content, "<think>\nSearching first.\n</think>");
+        assert_eq!(
+            calls,
+            vec![
+                FunctionCall {
+                    name: "search_sessions".to_string(),
+                    arguments: r#"{"query":"roadmap"}"#.to_string(),
+                },
+                FunctionCall {
+                    name: "list_participants".to_string(),
+                    arguments: "{}".to_string(),
+                },
+            ]
+        );
+
+        let (content, calls) = parse_tool_calls("Nothing to call. <tool_call>\n{broken");
+        assert_eq!(content, "Nothing to call. <tool_call>\n{broken");
+        assert!(calls.is_empty());
+    }
+
+    #[test]
+    fn test_starts_tool_call() {
+        assert_eq!(starts_tool_call(""), None);
+        assert_eq!(starts_tool_call("<thi"), None);
+        assert_eq!(starts_tool_call("<think>\nLet me"), None);
+        assert_eq!(starts_tool_call("<think>\nLook.\n</think>\n\n"), None);
+        assert_eq!(starts_tool_call("<think></think>\n<tool_"), None);
+        assert_eq!(starts_tool_call("<tool_call>\n{"), Some(true));
+        assert_eq!(starts_tool_call("<think></think>\nSure"), Some(false));
+        assert_eq!(starts_tool_call("<b>Sure</b>"), Some(false));
+        assert_eq!(starts_tool_call("Sure"), Some(false));
+    }
+
+    #[test]
+    fn test_inline_tools() {
+        let tool = Tool {
+            name: "search_sessions".to_string(),
+            description: None,
+            parameters: serde_json::json!({ "type": "object" }),
+        };
+
+        let messages = vec![
+            LlamaChatMessage::new("user".into(), "What did we decide?".into()).unwrap(),
+            LlamaChatMessage {
+                tool_calls: vec![crate::ToolCall {
+                    id: "call_1".to_string(),
+                    function: FunctionCall {
+                        name: "search_sessions".to_string(),
+                        arguments: r#"{"query":"decision"}"#.to_string(),
+                    },
+                }],
+                ..LlamaChatMessage::new("assistant".into(), "".into()).unwrap()
+            },
+            LlamaChatMessage {
+                tool_call_id: Some("call_1".to_string()),
+                ..LlamaChatMessage::new("tool".into(), "[]".into()).unwrap()
+            },
+        ];
+
+        let inlined = inline_tools(&messages, &[tool]);
+        let roles = inlined.iter().map(|m| m.role.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(roles, vec!["system", "user", "assistant", "user"]);
+        assert!(inlined[0].content.contains("\"search_sessions\""));
+        assert_eq!(
+            inlined[2].content,
+            "<tool_call>\n{\"arguments\":{\"query\":\"decision\"},\"name\":\"search_sessions\"}\n</tool_call>"
+        );
+        assert_eq!(inlined[3].content, "<tool_response>\n[]\n</tool_response>");
+    }
+}
@@ -1,9 +1,102 @@
 use async_openai::types::{
-    ChatCompletionRequestAssistantMessageContent, ChatCompletionRequestMessage,
-    ChatCompletionRequestSystemMessageContent, ChatCompletionRequestUserMessageContent,
+    ChatCompletionRequestAssistantMessageContent, ChatCompletionRequestAssistantMessageContentPart,
+    ChatCompletionRequestDeveloperMessageContent, ChatCompletionRequestMessage,
+    ChatCompletionRequestSystemMessageContent, ChatCompletionRequestSystemMessageContentPart,
+    ChatCompletionRequestToolMessageContent, ChatCompletionRequestToolMessageContentPart,
+    ChatCompletionRequestUserMessageContent, ChatCompletionRequestUserMessageContentPart,
 };
 
-pub use llama_cpp_2::model::LlamaChatMessage;
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct LlamaChatMessage {
+    pub role: String,
+    pub content: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl LlamaChatMessage {
+    pub fn new(role: String, content: String) -> Result<Self, crate::Error> {
+        let message = Self {
+            role,
+            content,
+            tool_calls: vec![],
+            tool_call_id: None,
+        };
+
+        // Same checks llama.cpp does when templating, so bad input fails here instead.
+        message.to_llama()?;
+        Ok(message)
+    }
+
+    pub(crate) fn to_llama(&self) -> Result<llama_cpp_2::model::LlamaChatMessage, crate::Error> {
+        llama_cpp_2::model::LlamaChatMessage::new(self.role.clone(), self.content.clone())
+            .map_err(Into::into)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    pub id: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionCall {
+    pub name: String,
+    /// JSON-encoded, as in OpenAI's API.
+    pub arguments: String,
+}
+
+// Shaped like OpenAI's, which is what chat templates expect.
+impl serde::Serialize for ToolCall {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let arguments = serde_json::from_str::<serde_json::Value>(&self.function.arguments)
+            .unwrap_or_else(|_| serde_json::Value::String(self.function.arguments.clone()));
+
+        serde_json::json!({
+            "id": self.id,
+            "type": "function",
+            "function": {
+                "name": self.function.name,
+                "arguments": arguments,
+            }
+        })
+        .serialize(serializer)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tool {
+    pub name: String,
+    pub description: Option<String>,
+    /// JSON schema of the arguments.
+    pub parameters: serde_json::Value,
+}
+
+impl serde::Serialize for Tool {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": self.parameters,
+            }
+        })
+        .serialize(serializer)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The model decides between calling tools and answering.
+    #[default]
+    Auto,
+    /// The model must call at least one tool.
+    Required,
+}
 
 pub trait FromOpenAI {
     fn from_openai(message: &ChatCompletionRequestMessage) -> Self;
@@ -11,31 +104,99 @@ pub trait FromOpenAI {
 
 impl FromOpenAI for LlamaChatMessage {
     fn from_openai(message: &ChatCompletionRequestMessage) -> Self {
-        match message {
+        let (role, content, tool_calls, tool_call_id) = match message {
             ChatCompletionRequestMessage::System(system) => {
                 let content = match &system.content {
-                    ChatCompletionRequestSystemMessageContent::Text(text) => text,
-                    _ => todo!(),
+                    ChatCompletionRequestSystemMessageContent::Text(text) => text.clone(),
+                    ChatCompletionRequestSystemMessageContent::Array(parts) => parts
+                        .iter()
+                        .map(|part| match part {
+                            ChatCompletionRequestSystemMessageContentPart::Text(t) => {
+                                t.text.as_str()
+                            }
+                        })
+                        .collect(),
                 };
-
-                LlamaChatMessage::new("system".into(), content.into()).unwrap()
+                ("system", content, vec![], None)
             }
-            ChatCompletionRequestMessage::Assistant(assistant) => {
-                let content = match &assistant.content {
-                    Some(ChatCompletionRequestAssistantMessageContent::Text(text)) => text,
-                    _ => todo!(),
+            ChatCompletionRequestMessage::Developer(developer) => {
+                let content = match &developer.content {
+                    ChatCompletionRequestDeveloperMessageContent::Text(text) => text.clone(),
+                    ChatCompletionRequestDeveloperMessageContent::Array(parts) => {
+                        parts.iter().map(|p| p.text.as_str()).collect()
+                    }
                 };
-                LlamaChatMessage::new("assistant".into(), content.into()).unwrap()
+                ("system", content, vec![], None)
             }
             ChatCompletionRequestMessage::User(user) => {
+                // Images and audio are not supported by the local models.
                 let content = match &user.content {
-                    ChatCompletionRequestUserMessageContent::Text(text) => text,
-                    _ => todo!(),
+                    ChatCompletionRequestUserMessageContent::Text(text) => text.clone(),
+                    ChatCompletionRequestUserMessageContent::Array(parts) => parts
+                        .iter()
+                        .filter_map(|part| match part {
+                            ChatCompletionRequestUserMessageContentPart::Text(t) => {
+                                Some(t.text.as_str())
+                            }
+                            _ => None,
+                        })
+                        .collect(),
                 };
-
-                LlamaChatMessage::new("user".into(), content.into()).unwrap()
+                ("user", content, vec![], None)
+            }
+            ChatCompletionRequestMessage::Assistant(assistant) => {
+                let content = match &assistant.content {
+                    Some(ChatCompletionRequestAssistantMessageContent::Text(text)) => text.clone(),
+                    Some(ChatCompletionRequestAssistantMessageContent::Array(parts)) => parts
+                        .iter()
+                        .filter_map(|part| match part {
+                            ChatCompletionRequestAssistantMessageContentPart::Text(t) => {
+                                Some(t.text.as_str())
+                            }
+                            _ => None,
+                        })
+                        .collect(),
+                    None => String::new(),
+                };
+                let tool_calls = assistant
+                    .tool_calls
+                    .iter()
+                    .flatten()
+                    .map(|call| ToolCall {
+                        id: call.id.clone(),
+                        function: FunctionCall {
+                            name: call.function.name.clone(),
+                            arguments: call.function.arguments.clone(),
+                        },
+                    })
+                    .collect();
+                ("assistant", content, tool_calls, None)
             }
-            _ => todo!(),
+            ChatCompletionRequestMessage::Tool(tool) => {
+                let content = match &tool.content {
+                    ChatCompletionRequestToolMessageContent::Text(text) => text.clone(),
+                    ChatCompletionRequestToolMessageContent::Array(parts) => parts
+                        .iter()
+                        .map(|part| match part {
+                            ChatCompletionRequestToolMessageContentPart::Text(t) => t.text.as_str(),
+                        })
+                        .collect(),
+                };
+                ("tool", content, vec![], Some(tool.tool_call_id.clone()))
+            }
+            ChatCompletionRequestMessage::Function(function) => (
+                "tool",
+                function.content.clone().unwrap_or_default(),
+                vec![],
+                None,
+            ),
+        };
+
+        LlamaChatMessage {
+            role: role.to_string(),
+            content,
+            tool_calls,
+            tool_call_id,
         }
     }
 }
@@ -44,4 +205,49 @@ impl FromOpenAI for LlamaChatMessage {
 pub struct LlamaRequest {
     pub grammar: Option<String>,
     pub messages: Vec<LlamaChatMessage>,
+    /// Defaults to [`crate::DEFAULT_MAX_INPUT_TOKENS`].
+    pub max_input_tokens: Option<u32>,
+    /// Defaults to [`crate::DEFAULT_MAX_OUTPUT_TOKENS`].
+    pub max_output_tokens: Option<u32>,
+    /// Anything left unset falls back to [`crate::ModelName::default_sampling`].
+    pub sampling: SamplingParams,
+    /// Generation ends before the first of these, which is not included in the output.
+    pub stop: Vec<String>,
+    /// Replaces `grammar` with one for calling these, unless empty.
+    pub tools: Vec<Tool>,
+    pub tool_choice: ToolChoice,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SamplingParams {
+    /// `0.0` always picks the most likely token.
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+    pub seed: Option<u32>,
+}
+
+impl SamplingParams {
+    pub fn or(self, defaults: SamplingParams) -> SamplingParams {
+        SamplingParams {
+            temperature: self.temperature.or(defaults.temperature),
+            top_p: self.top_p.or(defaults.top_p),
+            frequency_penalty: self.frequency_penalty.or(defaults.frequency_penalty),
+            presence_penalty: self.presence_penalty.or(defaults.presence_penalty),
+            seed: self.seed.or(defaults.seed),
+        }
+    }
+}
+
+impl LlamaRequest {
+    pub fn max_input_tokens(&self) -> u32 {
+        self.max_input_tokens
+            .unwrap_or(crate::DEFAULT_MAX_INPUT_TOKENS)
+    }
+
+    pub fn max_output_tokens(&self) -> u32 {
+        self.max_output_tokens
+            .unwrap_or(crate::DEFAULT_MAX_OUTPUT_TOKENS)
+    }
 }
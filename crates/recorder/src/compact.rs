@@ -0,0 +1,146 @@
+use std::path::{Path, PathBuf};
+
+use crate::{Error, Format, CHUNKS_DIR};
+
+/// Re-encodes a finished WAV chunk into `format` and deletes the original.
+/// Returns the path of the new file, or `None` if the chunk had no audio and was removed.
+pub fn compact_chunk(path: impl AsRef<Path>, format: Format) -> Result<Option<PathBuf>, Error> {
+    let path = path.as_ref();
+
+    if format == Format::Wav {
+        return Ok(Some(path.to_path_buf()));
+    }
+
+    let (spec, samples) = read_wav(path)?;
+    if samples.is_empty() {
+        std::fs::remove_file(path)?;
+        return Ok(None);
+    }
+
+    let target = path.with_extension(format.extension());
+    // Written under a name the reader ignores, so a crash here never leaves a half-written chunk behind.
+    let tmp = path.with_extension("tmp");
+
+    match format {
+        Format::Flac => write_flac(&tmp, &samples, spec.channels, spec.sample_rate)?,
+        Format::Opus => crate::opus::write(&tmp, &samples, spec.channels, spec.sample_rate)?,
+        Format::Wav => unreachable!(),
+    }
+
+    std::fs::rename(&tmp, &target)?;
+    std::fs::remove_file(path)?;
+
+    Ok(Some(target))
+}
+
+/// WAV chunks of the session that are not compacted yet.
+pub fn uncompacted_chunks(session_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, Error> {
+    let chunks = crate::list_chunks(&session_dir.as_ref().join(CHUNKS_DIR))?;
+
+    let mut pending = Vec::new();
+    for (index, format, path) in &chunks {
+        if *format != Format::Wav {
+            continue;
+        }
+
+        let already_compacted = chunks
+            .iter()
+            .any(|(i, f, _)| i == index && *f != Format::Wav);
+
+        // Left over from a compaction that was interrupted after the rename.
+        if already_compacted {
+            std::fs::remove_file(path)?;
+        } else {
+            pending.push(path.clone());
+        }
+    }
+
+    Ok(pending)
+}
+
+/// Compacts every WAV chunk of the session. Must not run while a [`crate::ChunkedWriter`] is writing to it.
+pub fn compact_dir(session_dir: impl AsRef<Path>, format: Format) -> Result<(), Error> {
+    for path in uncompacted_chunks(session_dir)? {
+        compact_chunk(path, format)?;
+    }
+
+    Ok(())
+}
+
+fn read_wav(path: &Path) -> Result<(hound::WavSpec, Vec<f32>), Error> {
+    let reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .into_samples::<f32>()
+            .collect::<Result<Vec<_>, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+
+    Ok((spec, samples))
+}
+
+fn write_flac(path: &Path, samples: &[f32], channels: u16, sample_rate: u32) -> Result<(), Error> {
+    use flacenc::component::{BitRepr, Stream};
+    use flacenc::error::Verify;
+    use flacenc::source::{Fill, FrameBuf};
+
+    const BITS_PER_SAMPLE: usize = 16;
+    // flacenc doesn't encode shorter frames.
+    const MIN_BLOCK_SIZE: usize = 64;
+
+    let samples = samples
+        .iter()
+        .map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i32)
+        .collect::<Vec<_>>();
+
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, e)| Error::FlacError(e.to_string()))?;
+
+    // `encode_with_fixed_block_size` pads the last frame with silence, which decoders then play.
+    // Frames are encoded one by one instead, with a block size that leaves a last frame flacenc accepts.
+    let channels = channels as usize;
+    let frames = samples.len() / channels;
+    let block_size = (config.block_size / 2..=config.block_size)
+        .rev()
+        .find(|size| frames.is_multiple_of(*size) || frames % size >= MIN_BLOCK_SIZE)
+        .unwrap_or(config.block_size);
+
+    let mut stream = Stream::new(sample_rate as usize, channels, BITS_PER_SAMPLE)
+        .map_err(|e| Error::FlacError(e.to_string()))?;
+
+    for (frame_number, block) in samples.chunks(block_size * channels).enumerate() {
+        // Only a chunk shorter than `MIN_BLOCK_SIZE` ends up padded.
+        let mut framebuf =
+            FrameBuf::with_size(channels, (block.len() / channels).max(MIN_BLOCK_SIZE))
+                .map_err(|e| Error::FlacError(e.to_string()))?;
+        framebuf
+            .fill_interleaved(block)
+            .map_err(|e| Error::FlacError(e.to_string()))?;
+
+        let frame = flacenc::encode_fixed_size_frame(
+            &config,
+            &framebuf,
+            frame_number,
+            stream.stream_info(),
+        )
+        .map_err(|e| Error::FlacError(format!("{:?}", e)))?;
+        stream.add_frame(frame);
+    }
+
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream
+        .write(&mut sink)
+        .map_err(|e| Error::FlacError(e.to_string()))?;
+
+    std::fs::write(path, sink.as_slice())?;
+    Ok(())
+}
@@ -0,0 +1,23 @@
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    WavError(#[from] hound::Error),
+    #[error(transparent)]
+    DecoderError(#[from] rodio::decoder::DecoderError),
+    #[error(transparent)]
+    SeekError(#[from] rodio::source::SeekError),
+    #[error(transparent)]
+    OpusError(#[from] audiopus::Error),
+    #[error(transparent)]
+    OggError(#[from] ogg::OggReadError),
+    #[error("flac: {0}")]
+    FlacError(String),
+    #[error("invalid opus stream")]
+    InvalidOpusStream,
+    #[error("opus supports mono or stereo at 8, 12, 16, 24 or 48kHz")]
+    UnsupportedOpusFormat,
+    #[error("no recording found")]
+    NoRecording,
+}
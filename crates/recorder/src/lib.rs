@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+
+mod compact;
+mod error;
+mod opus;
+mod reader;
+mod writer;
+
+pub use compact::*;
+pub use error::*;
+pub use reader::*;
+pub use writer::*;
+
+/// Where the chunks live, relative to the session directory.
+pub const CHUNKS_DIR: &str = "chunks";
+/// Single-file recording, written before chunking was introduced.
+pub const LEGACY_FILE: &str = "audio.wav";
+/// Optional 2-channel recording, with the microphone on the left and the system audio on the right.
+/// It has the same layout as the session directory itself.
+pub const MULTITRACK_DIR: &str = "multitrack";
+
+pub fn multitrack_dir(session_dir: impl AsRef<Path>) -> PathBuf {
+    session_dir.as_ref().join(MULTITRACK_DIR)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Wav,
+    Flac,
+    Opus,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Wav => "wav",
+            Format::Flac => "flac",
+            Format::Opus => "opus",
+        }
+    }
+
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "wav" => Some(Format::Wav),
+            "flac" => Some(Format::Flac),
+            "opus" => Some(Format::Opus),
+            _ => None,
+        }
+    }
+}
+
+/// Every recording file of the session, in playback order.
+pub fn list_files(session_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, Error> {
+    let session_dir = session_dir.as_ref();
+    let mut files = Vec::new();
+
+    let legacy = session_dir.join(LEGACY_FILE);
+    if legacy.exists() {
+        files.push(legacy);
+    }
+
+    let mut chunks = list_chunks(&session_dir.join(CHUNKS_DIR))?;
+    chunks.sort_by_key(|(index, format, _)| (*index, *format == Format::Wav));
+    // A WAV chunk and its compacted copy can briefly co-exist. The compacted one is complete.
+    chunks.dedup_by_key(|(index, _, _)| *index);
+
+    files.extend(chunks.into_iter().map(|(_, _, path)| path));
+    Ok(files)
+}
+
+pub fn exists(session_dir: impl AsRef<Path>) -> bool {
+    list_files(session_dir).is_ok_and(|files| !files.is_empty())
+}
+
+pub fn remove(session_dir: impl AsRef<Path>) -> Result<(), Error> {
+    let session_dir = session_dir.as_ref();
+
+    let legacy = session_dir.join(LEGACY_FILE);
+    if legacy.exists() {
+        std::fs::remove_file(legacy)?;
+    }
+
+    for dir in [session_dir.join(CHUNKS_DIR), multitrack_dir(session_dir)] {
+        if dir.exists() {
+            std::fs::remove_dir_all(dir)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn list_chunks(chunks_dir: &Path) -> Result<Vec<(u32, Format, PathBuf)>, Error> {
+    if !chunks_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut chunks = Vec::new();
+    for entry in std::fs::read_dir(chunks_dir)? {
+        let path = entry?.path();
+
+        let Some(format) = Format::from_path(&path) else {
+            continue;
+        };
+        let Some(index) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+
+        chunks.push((index, format, path));
+    }
+
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::Source;
+    use std::time::Duration;
+
+    const SAMPLE_RATE: u32 = 16000;
+
+    fn tone(secs: f32) -> Vec<f32> {
+        (0..(secs * SAMPLE_RATE as f32) as usize)
+            .map(|i| {
+                (i as f32 * 440.0 * 2.0 * std::f32::consts::PI / SAMPLE_RATE as f32).sin() * 0.5
+            })
+            .collect()
+    }
+
+    fn record(session_dir: &Path, samples: &[f32]) -> Vec<PathBuf> {
+        let mut writer = ChunkedWriter::new(session_dir, 1, SAMPLE_RATE)
+            .unwrap()
+            .chunk_duration(Duration::from_secs(1));
+
+        let mut finished = Vec::new();
+        for block in samples.chunks(SAMPLE_RATE as usize / 10) {
+            finished.extend(writer.write(block).unwrap());
+        }
+        finished.extend(writer.finish().unwrap());
+        finished
+    }
+
+    #[test]
+    fn test_chunks_are_valid_while_recording() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut writer = ChunkedWriter::new(dir.path(), 1, SAMPLE_RATE).unwrap();
+        for block in tone(3.0).chunks(SAMPLE_RATE as usize / 10) {
+            writer.write(block).unwrap();
+        }
+
+        // Not finalized, as if the app crashed here.
+        let files = list_files(dir.path()).unwrap();
+        let reader = hound::WavReader::open(&files[0]).unwrap();
+        assert!(reader.duration() >= 2 * SAMPLE_RATE);
+    }
+
+    #[test]
+    fn test_write_compact_read() {
+        let samples = tone(3.5);
+
+        for format in [Format::Wav, Format::Flac, Format::Opus] {
+            let dir = tempfile::tempdir().unwrap();
+
+            let finished = record(dir.path(), &samples);
+            assert_eq!(finished.len(), 4);
+
+            compact_dir(dir.path(), format).unwrap();
+
+            let files = list_files(dir.path()).unwrap();
+            assert_eq!(files.len(), 4);
+            assert!(files.iter().all(|f| Format::from_path(f) == Some(format)));
+
+            let expected = Duration::from_secs_f32(3.5);
+            let total = duration(dir.path()).unwrap();
+            assert!(
+                total.abs_diff(expected) < Duration::from_millis(10),
+                "{format:?}"
+            );
+
+            let source = open(dir.path()).unwrap();
+            assert_eq!(source.sample_rate(), SAMPLE_RATE);
+            assert_eq!(source.channels(), 1);
+            assert!(source.count().abs_diff(samples.len()) < 160, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_open_at() {
+        let dir = tempfile::tempdir().unwrap();
+        let samples = tone(3.5);
+        record(dir.path(), &samples);
+
+        // WAV seeks in the file, FLAC decodes up to the position.
+        for compacted in [false, true] {
+            if compacted {
+                compact_dir(dir.path(), Format::Flac).unwrap();
+            }
+
+            for secs in [0.0, 0.5, 1.0, 2.25] {
+                let skipped = (secs * SAMPLE_RATE as f32) as usize;
+                let source = open_at(dir.path(), Duration::from_secs_f32(secs)).unwrap();
+                assert_eq!(source.total_duration(), Some(duration(dir.path()).unwrap()));
+
+                let rest = source.collect::<Vec<_>>();
+                assert_eq!(rest.len(), samples.len() - skipped, "{secs}");
+                assert!(rest
+                    .iter()
+                    .zip(&samples[skipped..])
+                    .all(|(a, b)| (a - b).abs() < 1e-3));
+            }
+
+            let past_end = open_at(dir.path(), Duration::from_secs(10)).unwrap();
+            assert_eq!(past_end.count(), 0);
+        }
+    }
+
+    #[test]
+    fn test_resume_after_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        record(dir.path(), &tone(1.5));
+
+        let mut writer = ChunkedWriter::new(dir.path(), 1, SAMPLE_RATE).unwrap();
+        writer.write(&tone(0.5)).unwrap();
+        drop(writer);
+
+        let names = list_files(dir.path())
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["000000.wav", "000001.wav", "000002.wav"]);
+    }
+
+    #[test]
+    fn test_multitrack() {
+        let dir = tempfile::tempdir().unwrap();
+        let multitrack = multitrack_dir(dir.path());
+
+        let stereo = tone(1.0)
+            .into_iter()
+            .flat_map(|s| [s, -s])
+            .collect::<Vec<_>>();
+
+        let mut writer = ChunkedWriter::new(&multitrack, 2, SAMPLE_RATE).unwrap();
+        writer.write(&stereo).unwrap();
+        writer.finish().unwrap();
+        compact_dir(&multitrack, Format::Opus).unwrap();
+
+        let source = open(&multitrack).unwrap();
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.count(), stereo.len());
+
+        assert!(!exists(dir.path()));
+        remove(dir.path()).unwrap();
+        assert!(!multitrack.exists());
+    }
+}
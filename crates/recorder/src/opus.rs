@@ -0,0 +1,191 @@
+// Ogg Opus, as described in RFC 7845.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::time::Duration;
+
+use audiopus::{
+    coder::{Decoder, Encoder},
+    Application, Channels, SampleRate,
+};
+use ogg::{PacketReader, PacketWriteEndInfo, PacketWriter};
+
+use crate::Error;
+
+const FRAME_MS: usize = 20;
+const MAX_FRAME_MS: usize = 120;
+const MAX_PACKET_SIZE: usize = 4000;
+// Granule positions are always counted at 48kHz, whatever the input rate.
+const GRANULE_RATE: u64 = 48000;
+const STREAM_SERIAL: u32 = 1;
+
+pub struct Decoded {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub samples: Vec<f32>,
+}
+
+pub fn write(path: &Path, samples: &[f32], channels: u16, sample_rate: u32) -> Result<(), Error> {
+    let encoder = Encoder::new(
+        opus_sample_rate(sample_rate)?,
+        opus_channels(channels)?,
+        Application::Voip,
+    )?;
+
+    // The decoder lags the encoder by this many frames, which the reader drops again.
+    let lookahead = encoder.lookahead()? as u64;
+    let to_granule = |frames: u64| frames * GRANULE_RATE / sample_rate as u64;
+
+    let mut writer = PacketWriter::new(File::create(path)?);
+    writer.write_packet(
+        head(channels, sample_rate, to_granule(lookahead) as u16),
+        STREAM_SERIAL,
+        PacketWriteEndInfo::EndPage,
+        0,
+    )?;
+    writer.write_packet(tags(), STREAM_SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+
+    let frame_frames = sample_rate as usize * FRAME_MS / 1000;
+    let frame_len = frame_frames * channels as usize;
+    let mut frame = vec![0.0; frame_len];
+    let mut packet = vec![0u8; MAX_PACKET_SIZE];
+
+    // Silence is fed past the end until the lookahead is flushed out, and trimmed again through the final granule position.
+    let total_frames = (samples.len() / channels as usize) as u64 + lookahead;
+    let frame_count = total_frames.div_ceil(frame_frames as u64);
+
+    for i in 0..frame_count {
+        let start = (i as usize * frame_len).min(samples.len());
+        let block = &samples[start..(start + frame_len).min(samples.len())];
+        frame[..block.len()].copy_from_slice(block);
+        frame[block.len()..].fill(0.0);
+
+        let len = encoder.encode_float(&frame, &mut packet)?;
+
+        let encoded_frames = ((i + 1) * frame_frames as u64).min(total_frames);
+        let end_info = if i + 1 < frame_count {
+            PacketWriteEndInfo::NormalPacket
+        } else {
+            PacketWriteEndInfo::EndStream
+        };
+
+        writer.write_packet(
+            packet[..len].to_vec(),
+            STREAM_SERIAL,
+            end_info,
+            to_granule(encoded_frames),
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn read(path: &Path) -> Result<Decoded, Error> {
+    let mut reader = PacketReader::new(BufReader::new(File::open(path)?));
+    let (channels, sample_rate, pre_skip) = read_headers(&mut reader)?;
+
+    let mut decoder = Decoder::new(opus_sample_rate(sample_rate)?, opus_channels(channels)?)?;
+    let mut buffer = vec![0.0; sample_rate as usize * MAX_FRAME_MS / 1000 * channels as usize];
+    let mut samples = Vec::new();
+    let mut final_granule = None;
+
+    while let Some(packet) = reader.read_packet()? {
+        let decoded = decoder.decode_float(Some(&packet.data[..]), &mut buffer[..], false)?;
+        samples.extend_from_slice(&buffer[..decoded * channels as usize]);
+
+        if packet.last_in_stream() {
+            final_granule = Some(packet.absgp_page());
+        }
+    }
+
+    let skip = (pre_skip * sample_rate as u64 / GRANULE_RATE) as usize * channels as usize;
+    samples.drain(..skip.min(samples.len()));
+
+    if let Some(granule) = final_granule {
+        let frames = granule.saturating_sub(pre_skip) * sample_rate as u64 / GRANULE_RATE;
+        samples.truncate(frames as usize * channels as usize);
+    }
+
+    Ok(Decoded {
+        channels,
+        sample_rate,
+        samples,
+    })
+}
+
+pub fn duration(path: &Path) -> Result<Duration, Error> {
+    let mut reader = PacketReader::new(BufReader::new(File::open(path)?));
+    let (_, _, pre_skip) = read_headers(&mut reader)?;
+
+    let mut granule = 0;
+    while let Some(packet) = reader.read_packet()? {
+        granule = packet.absgp_page();
+    }
+
+    Ok(Duration::from_secs_f64(
+        granule.saturating_sub(pre_skip) as f64 / GRANULE_RATE as f64,
+    ))
+}
+
+// Channel count, input sample rate, and pre-skip at 48kHz.
+fn read_headers<T: std::io::Read + std::io::Seek>(
+    reader: &mut PacketReader<T>,
+) -> Result<(u16, u32, u64), Error> {
+    let head = reader.read_packet()?.ok_or(Error::InvalidOpusStream)?;
+    if head.data.len() < 19 || &head.data[..8] != b"OpusHead" {
+        return Err(Error::InvalidOpusStream);
+    }
+
+    let channels = head.data[9] as u16;
+    let pre_skip = u16::from_le_bytes([head.data[10], head.data[11]]) as u64;
+    let sample_rate =
+        u32::from_le_bytes([head.data[12], head.data[13], head.data[14], head.data[15]]);
+
+    // OpusTags. Nothing in there we need.
+    reader.read_packet()?.ok_or(Error::InvalidOpusStream)?;
+
+    Ok((channels, sample_rate, pre_skip))
+}
+
+fn head(channels: u16, sample_rate: u32, pre_skip: u16) -> Vec<u8> {
+    let mut head = Vec::with_capacity(19);
+    head.extend_from_slice(b"OpusHead");
+    head.push(1); // version
+    head.push(channels as u8);
+    head.extend_from_slice(&pre_skip.to_le_bytes());
+    head.extend_from_slice(&sample_rate.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+    head.push(0); // channel mapping family
+    head
+}
+
+fn tags() -> Vec<u8> {
+    const VENDOR: &[u8] = b"hyprnote";
+
+    let mut tags = Vec::new();
+    tags.extend_from_slice(b"OpusTags");
+    tags.extend_from_slice(&(VENDOR.len() as u32).to_le_bytes());
+    tags.extend_from_slice(VENDOR);
+    tags.extend_from_slice(&0u32.to_le_bytes()); // user comments
+    tags
+}
+
+fn opus_sample_rate(sample_rate: u32) -> Result<SampleRate, Error> {
+    match sample_rate {
+        8000 => Ok(SampleRate::Hz8000),
+        12000 => Ok(SampleRate::Hz12000),
+        16000 => Ok(SampleRate::Hz16000),
+        24000 => Ok(SampleRate::Hz24000),
+        48000 => Ok(SampleRate::Hz48000),
+        _ => Err(Error::UnsupportedOpusFormat),
+    }
+}
+
+fn opus_channels(channels: u16) -> Result<Channels, Error> {
+    match channels {
+        1 => Ok(Channels::Mono),
+        2 => Ok(Channels::Stereo),
+        _ => Err(Error::UnsupportedOpusFormat),
+    }
+}
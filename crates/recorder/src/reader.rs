@@ -0,0 +1,204 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rodio::{source::UniformSourceIterator, Source};
+
+use crate::{Error, Format};
+
+type ChunkSamples = Box<dyn Iterator<Item = f32> + Send>;
+
+/// All files of a recording, played back to back as one `rodio::Source`.
+pub struct RecordingSource {
+    pending: VecDeque<PathBuf>,
+    current: ChunkSamples,
+    channels: u16,
+    sample_rate: u32,
+    total_duration: Duration,
+}
+
+/// Opens the recording of a session. The channel count and sample rate are taken from the first file.
+pub fn open(session_dir: impl AsRef<Path>) -> Result<RecordingSource, Error> {
+    open_at(session_dir, Duration::ZERO)
+}
+
+/// Like `open`, but playback starts at `position`. Past the end, the source is empty.
+pub fn open_at(
+    session_dir: impl AsRef<Path>,
+    position: Duration,
+) -> Result<RecordingSource, Error> {
+    let files = crate::list_files(&session_dir)?;
+    let durations = files
+        .iter()
+        .map(|file| file_duration(file))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Files that end before `position` are never decoded.
+    let mut skipped = 0;
+    let mut offset = position;
+    for duration in &durations {
+        if offset < *duration {
+            break;
+        }
+        offset -= *duration;
+        skipped += 1;
+    }
+
+    let mut pending = VecDeque::from(files);
+    let first = pending.pop_front().ok_or(Error::NoRecording)?;
+
+    let first_offset = if skipped == 0 { offset } else { Duration::ZERO };
+    let (channels, sample_rate, current) = decode_at(&first, first_offset)?;
+
+    let mut source = RecordingSource {
+        pending,
+        current,
+        channels,
+        sample_rate,
+        total_duration: durations.iter().sum(),
+    };
+
+    if skipped > 0 {
+        source.pending.drain(..skipped - 1);
+        source.current = source
+            .next_chunk_at(offset)
+            .unwrap_or_else(|| Box::new(std::iter::empty()));
+    }
+
+    Ok(source)
+}
+
+/// Length of the recording, without decoding it where the container tells.
+pub fn duration(session_dir: impl AsRef<Path>) -> Result<Duration, Error> {
+    total_duration(&crate::list_files(session_dir)?)
+}
+
+fn total_duration(files: &[PathBuf]) -> Result<Duration, Error> {
+    let mut total = Duration::ZERO;
+    for file in files {
+        total += file_duration(file)?;
+    }
+    Ok(total)
+}
+
+fn file_duration(path: &Path) -> Result<Duration, Error> {
+    match Format::from_path(path) {
+        Some(Format::Wav) => {
+            let reader = hound::WavReader::open(path)?;
+            Ok(Duration::from_secs_f64(
+                reader.duration() as f64 / reader.spec().sample_rate as f64,
+            ))
+        }
+        Some(Format::Flac) => {
+            let decoder = rodio::Decoder::new(BufReader::new(File::open(path)?))?;
+            if let Some(duration) = decoder.total_duration() {
+                return Ok(duration);
+            }
+
+            let per_second = decoder.sample_rate() as f64 * decoder.channels() as f64;
+            Ok(Duration::from_secs_f64(decoder.count() as f64 / per_second))
+        }
+        Some(Format::Opus) => crate::opus::duration(path),
+        None => Ok(Duration::ZERO),
+    }
+}
+
+// Samples of `channels` interleaved channels that make up `offset`.
+fn samples_in(offset: Duration, channels: u16, sample_rate: u32) -> usize {
+    (offset.as_secs_f64() * sample_rate as f64) as usize * channels as usize
+}
+
+fn decode_at(path: &Path, offset: Duration) -> Result<(u16, u32, ChunkSamples), Error> {
+    match Format::from_path(path) {
+        Some(Format::Opus) => {
+            let decoded = crate::opus::read(path)?;
+            let skip = samples_in(offset, decoded.channels, decoded.sample_rate);
+            Ok((
+                decoded.channels,
+                decoded.sample_rate,
+                Box::new(decoded.samples.into_iter().skip(skip)),
+            ))
+        }
+        _ => {
+            let mut decoder = rodio::Decoder::new(BufReader::new(File::open(path)?))?;
+            let (channels, sample_rate) = (decoder.channels(), decoder.sample_rate());
+
+            // Decoders that can't seek, like the FLAC one, are decoded up to the offset instead.
+            let skip = match decoder.try_seek(offset) {
+                Ok(()) => 0,
+                Err(rodio::source::SeekError::NotSupported { .. }) => {
+                    samples_in(offset, channels, sample_rate)
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let mut samples = decoder.convert_samples::<f32>();
+            samples.by_ref().take(skip).for_each(drop);
+            Ok((channels, sample_rate, Box::new(samples)))
+        }
+    }
+}
+
+impl RecordingSource {
+    // Chunks that fail to decode are skipped, so one damaged file doesn't cut the rest of the recording.
+    fn next_chunk_at(&mut self, offset: Duration) -> Option<ChunkSamples> {
+        while let Some(path) = self.pending.pop_front() {
+            match decode_at(&path, offset) {
+                Ok((channels, sample_rate, samples)) => {
+                    if channels == self.channels && sample_rate == self.sample_rate {
+                        return Some(samples);
+                    }
+
+                    let buffer = rodio::buffer::SamplesBuffer::new(
+                        channels,
+                        sample_rate,
+                        samples.collect::<Vec<_>>(),
+                    );
+                    return Some(Box::new(UniformSourceIterator::<_, f32>::new(
+                        buffer,
+                        self.channels,
+                        self.sample_rate,
+                    )));
+                }
+                Err(e) => {
+                    tracing::warn!(path = ?path, "recording_chunk_skipped: {:?}", e);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl Iterator for RecordingSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        loop {
+            if let Some(sample) = self.current.next() {
+                return Some(sample);
+            }
+            self.current = self.next_chunk_at(Duration::ZERO)?;
+        }
+    }
+}
+
+impl Source for RecordingSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(self.total_duration)
+    }
+}
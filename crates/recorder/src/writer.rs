@@ -0,0 +1,124 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::{Error, CHUNKS_DIR};
+
+const DEFAULT_CHUNK_DURATION: Duration = Duration::from_secs(60);
+// The WAV header is rewritten on every flush, so at most this much audio is lost on a crash.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+struct OpenChunk {
+    path: PathBuf,
+    wav: hound::WavWriter<BufWriter<File>>,
+    frames: u64,
+    unflushed: u64,
+}
+
+/// Writes a recording as a sequence of WAV files that are each valid on their own, even if the process dies mid-write.
+pub struct ChunkedWriter {
+    dir: PathBuf,
+    spec: hound::WavSpec,
+    chunk_frames: u64,
+    next_index: u32,
+    current: Option<OpenChunk>,
+}
+
+impl ChunkedWriter {
+    /// Chunks are numbered after the ones already in `session_dir`, so a resumed session keeps its earlier audio.
+    pub fn new(
+        session_dir: impl AsRef<Path>,
+        channels: u16,
+        sample_rate: u32,
+    ) -> Result<Self, Error> {
+        let dir = session_dir.as_ref().join(CHUNKS_DIR);
+        std::fs::create_dir_all(&dir)?;
+
+        let next_index = crate::list_chunks(&dir)?
+            .iter()
+            .map(|(index, _, _)| index + 1)
+            .max()
+            .unwrap_or(0);
+
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+
+        Ok(Self {
+            dir,
+            spec,
+            chunk_frames: frames(DEFAULT_CHUNK_DURATION, sample_rate),
+            next_index,
+            current: None,
+        })
+    }
+
+    pub fn chunk_duration(mut self, duration: Duration) -> Self {
+        self.chunk_frames = frames(duration, self.spec.sample_rate).max(1);
+        self
+    }
+
+    /// Writes interleaved samples. Returns the path of the chunk if this write completed it.
+    pub fn write(&mut self, samples: &[f32]) -> Result<Option<PathBuf>, Error> {
+        let mut chunk = match self.current.take() {
+            Some(chunk) => chunk,
+            None => self.open_chunk()?,
+        };
+
+        for sample in samples {
+            chunk.wav.write_sample(*sample)?;
+        }
+
+        let written = (samples.len() / self.spec.channels as usize) as u64;
+        chunk.frames += written;
+        chunk.unflushed += written;
+
+        if chunk.frames >= self.chunk_frames {
+            chunk.wav.finalize()?;
+            return Ok(Some(chunk.path));
+        }
+
+        if chunk.unflushed >= frames(FLUSH_INTERVAL, self.spec.sample_rate) {
+            chunk.wav.flush()?;
+            chunk.unflushed = 0;
+        }
+
+        self.current = Some(chunk);
+        Ok(None)
+    }
+
+    /// Finalizes the chunk in progress and returns its path, if there is one.
+    pub fn finish(mut self) -> Result<Option<PathBuf>, Error> {
+        match self.current.take() {
+            Some(chunk) => {
+                chunk.wav.finalize()?;
+                Ok(Some(chunk.path))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn open_chunk(&mut self) -> Result<OpenChunk, Error> {
+        let path = self.dir.join(format!(
+            "{:06}.{}",
+            self.next_index,
+            crate::Format::Wav.extension()
+        ));
+        self.next_index += 1;
+
+        Ok(OpenChunk {
+            wav: hound::WavWriter::create(&path, self.spec)?,
+            path,
+            frames: 0,
+            unflushed: 0,
+        })
+    }
+}
+
+fn frames(duration: Duration, sample_rate: u32) -> u64 {
+    (duration.as_secs_f64() * sample_rate as f64) as u64
+}
@@ -2,36 +2,209 @@
 
 use codes_iso_639::part_1::LanguageCode;
 use itertools::Itertools;
+use minijinja::value::{Kwargs, Value};
+use minijinja::{Error, ErrorKind};
 use owhisper_interface::{SpeakerIdentity, Word2};
+use serde::Deserialize;
 use std::str::FromStr;
 
-pub fn language(value: String) -> String {
+const FILLER_WORDS: &[&str] = &[
+    "um", "uh", "umm", "uhh", "erm", "er", "ah", "hmm", "mm", "mhm",
+];
+
+pub fn language(value: String) -> Result<String, Error> {
     let lang_str = value.to_lowercase();
-    let lang_code = LanguageCode::from_str(&lang_str).unwrap();
-    lang_code.language_name().to_string()
+    let lang_code = LanguageCode::from_str(&lang_str).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidOperation,
+            format!("unknown language code: {}", value),
+        )
+    })?;
+    Ok(lang_code.language_name().to_string())
+}
+
+/// Words are passed to templates either as a JSON string, or as the output of another filter.
+fn parse_words(value: &Value) -> Result<Vec<Word2>, Error> {
+    if value.is_undefined() || value.is_none() {
+        return Ok(vec![]);
+    }
+
+    match value.as_str() {
+        Some(json) => serde_json::from_str(json).map_err(|e| {
+            Error::new(ErrorKind::InvalidOperation, "words are not valid JSON").with_source(e)
+        }),
+        None => Vec::<Word2>::deserialize(value.clone()),
+    }
+}
+
+fn speaker_label(speaker: &Option<SpeakerIdentity>) -> String {
+    match speaker {
+        Some(SpeakerIdentity::Unassigned { index }) => format!("SPEAKER {}", index),
+        Some(SpeakerIdentity::Assigned { label, .. }) => label.to_string(),
+        None => "UNKNOWN".to_string(),
+    }
 }
 
-pub fn timeline(words: String) -> String {
-    let words: Vec<Word2> = serde_json::from_str(&words).unwrap();
+fn format_timestamp(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}
+
+/// `mm:ss` into a recording. Minutes keep counting past the hour.
+pub fn clock(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric() || *c == '\'')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
 
-    words
+/// `[SPEAKER 0]` followed by what they said, for each speaker turn.
+pub fn timeline(words: &Value) -> Result<String, Error> {
+    let words = parse_words(words)?;
+
+    Ok(words
         .iter()
         .chunk_by(|word| word.speaker.clone())
         .into_iter()
         .map(|(speaker, group)| {
-            let speaker_label = match speaker {
-                Some(SpeakerIdentity::Unassigned { index }) => format!("SPEAKER {}", index),
-                Some(SpeakerIdentity::Assigned { label, .. }) => label.to_string(),
-                None => "UNKNOWN".to_string(),
-            };
-
             format!(
                 "[{}]\n{}",
-                speaker_label,
+                speaker_label(&speaker),
                 group.map(|word| word.text.as_str()).join(" ")
             )
         })
-        .join("\n\n")
+        .join("\n\n"))
+}
+
+/// `[00:12:03] Alice: ...` for each speaker turn, timed by its first word.
+pub fn timestamped_timeline(words: &Value) -> Result<String, Error> {
+    let words = parse_words(words)?;
+
+    Ok(words
+        .iter()
+        .chunk_by(|word| word.speaker.clone())
+        .into_iter()
+        .map(|(speaker, group)| {
+            let group = group.collect::<Vec<_>>();
+            let text = group.iter().map(|word| word.text.as_str()).join(" ");
+
+            match group.iter().find_map(|word| word.start_ms) {
+                Some(ms) => format!(
+                    "[{}] {}: {}",
+                    format_timestamp(ms),
+                    speaker_label(&speaker),
+                    text
+                ),
+                None => format!("{}: {}", speaker_label(&speaker), text),
+            }
+        })
+        .join("\n"))
+}
+
+/// Words said between `start_ms` and `end_ms`. Untimed words are dropped.
+pub fn time_range(words: &Value, start_ms: u64, end_ms: Option<u64>) -> Result<Value, Error> {
+    let words = parse_words(words)?
+        .into_iter()
+        .filter(|word| match (word.start_ms, word.end_ms) {
+            (Some(start), Some(end)) => end >= start_ms && end_ms.is_none_or(|e| start <= e),
+            (Some(start), None) => start >= start_ms && end_ms.is_none_or(|e| start <= e),
+            _ => false,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Value::from_serialize(&words))
+}
+
+#[derive(Deserialize)]
+struct Participant {
+    id: String,
+    full_name: Option<String>,
+}
+
+/// Labels speakers assigned to one of `participants` with their name.
+pub fn resolve_speakers(words: &Value, participants: &Value) -> Result<Value, Error> {
+    let participants = if participants.is_undefined() || participants.is_none() {
+        vec![]
+    } else {
+        Vec::<Participant>::deserialize(participants.clone())?
+    };
+
+    let words = parse_words(words)?
+        .into_iter()
+        .map(|mut word| {
+            if let Some(SpeakerIdentity::Assigned { id, label }) = &mut word.speaker {
+                if let Some(name) = participants
+                    .iter()
+                    .find(|p| &p.id == id)
+                    .and_then(|p| p.full_name.clone())
+                {
+                    *label = name;
+                }
+            }
+            word
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Value::from_serialize(&words))
+}
+
+/// Keeps the beginning of the transcript, up to `max_words` words and/or `max_tokens` (estimated) tokens.
+pub fn truncate_transcript(words: &Value, kwargs: Kwargs) -> Result<Value, Error> {
+    let max_words: Option<usize> = kwargs.get("max_words")?;
+    let max_tokens: Option<usize> = kwargs.get("max_tokens")?;
+    kwargs.assert_all_used()?;
+
+    let mut words = parse_words(words)?;
+
+    if let Some(max_words) = max_words {
+        words.truncate(max_words);
+    }
+
+    if let Some(max_tokens) = max_tokens {
+        let mut tokens = 0;
+        let fits = words
+            .iter()
+            .take_while(|word| {
+                tokens += crate::timeline::estimate_tokens(word);
+                tokens <= max_tokens
+            })
+            .count();
+        words.truncate(fits);
+    }
+
+    Ok(Value::from_serialize(&words))
+}
+
+/// Drops filler words ("um", "uh", ...) and repeats of the same word within a turn.
+pub fn compact(words: &Value) -> Result<Value, Error> {
+    let mut compacted: Vec<Word2> = Vec::new();
+
+    for word in parse_words(words)? {
+        let normalized = normalize(&word.text);
+        if normalized.is_empty() || FILLER_WORDS.contains(&normalized.as_str()) {
+            continue;
+        }
+
+        if let Some(previous) = compacted.last() {
+            if previous.speaker == word.speaker && normalize(&previous.text) == normalized {
+                continue;
+            }
+        }
+
+        compacted.push(word);
+    }
+
+    Ok(Value::from_serialize(&compacted))
 }
 
 #[cfg(test)]
@@ -40,13 +213,14 @@ mod tests {
 
     #[test]
     fn test_language() {
-        assert_eq!(language("en".to_string()), "English");
-        assert_eq!(language("ko".to_string()), "Korean");
+        assert_eq!(language("en".to_string()).unwrap(), "English");
+        assert_eq!(language("ko".to_string()).unwrap(), "Korean");
+        assert!(language("xx".to_string()).is_err());
     }
 
     #[test]
     fn test_timeline() {
-        insta::assert_snapshot!(timeline(hypr_data::english_3::WORDS_JSON.to_string()), @r###"
+        insta::assert_snapshot!(timeline(&Value::from(hypr_data::english_3::WORDS_JSON)).unwrap(), @r###"
         [SPEAKER 0]
         -okay michael why don't you start us off
 
@@ -162,4 +336,89 @@ mod tests {
         a letter opener and stick it in your skull hey this doesn't matter and i don't even care michael you quit the other job or you're fired here
         "###);
     }
+
+    fn word(text: &str, speaker: Option<SpeakerIdentity>, start_ms: u64) -> Word2 {
+        Word2 {
+            text: text.to_string(),
+            speaker,
+            confidence: None,
+            start_ms: Some(start_ms),
+            end_ms: Some(start_ms + 400),
+        }
+    }
+
+    fn words() -> Value {
+        let alice = Some(SpeakerIdentity::Assigned {
+            id: "alice-id".to_string(),
+            label: "Speaker A".to_string(),
+        });
+        let bob = Some(SpeakerIdentity::Unassigned { index: 1 });
+
+        Value::from_serialize(vec![
+            word("um", alice.clone(), 723_000),
+            word("so", alice.clone(), 723_500),
+            word("so", alice.clone(), 724_000),
+            word("let's", alice.clone(), 724_500),
+            word("start", alice.clone(), 725_000),
+            word("Sure,", bob.clone(), 3_725_000),
+            word("uh,", bob.clone(), 3_725_500),
+            word("go", bob.clone(), 3_726_000),
+        ])
+    }
+
+    #[test]
+    fn test_timeline_invalid_json() {
+        assert!(timeline(&Value::from("not json")).is_err());
+        assert_eq!(timeline(&Value::UNDEFINED).unwrap(), "");
+    }
+
+    #[test]
+    fn test_timestamped_timeline() {
+        let participants = Value::from_serialize(serde_json::json!([
+            { "id": "alice-id", "full_name": "Alice" }
+        ]));
+        let words = resolve_speakers(&words(), &participants).unwrap();
+
+        assert_eq!(
+            timestamped_timeline(&words).unwrap(),
+            "[00:12:03] Alice: um so so let's start\n[01:02:05] SPEAKER 1: Sure, uh, go"
+        );
+    }
+
+    #[test]
+    fn test_time_range() {
+        let start = time_range(&words(), 724_000, Some(725_000)).unwrap();
+        assert_eq!(timeline(&start).unwrap(), "[Speaker A]\nso let's start");
+
+        let end = time_range(&words(), 3_000_000, None).unwrap();
+        assert_eq!(timeline(&end).unwrap(), "[SPEAKER 1]\nSure, uh, go");
+    }
+
+    #[test]
+    fn test_compact() {
+        assert_eq!(
+            timeline(&compact(&words()).unwrap()).unwrap(),
+            "[Speaker A]\nso let's start\n\n[SPEAKER 1]\nSure, go"
+        );
+    }
+
+    #[test]
+    fn test_truncate_transcript() {
+        let render = |source: &str| {
+            let mut env = minijinja::Environment::new();
+            env.add_filter("truncate_transcript", truncate_transcript);
+            env.add_filter("timeline", timeline);
+            env.render_str(source, minijinja::context! { words => words() })
+        };
+
+        assert_eq!(
+            render("{{ words | truncate_transcript(max_words=2) | timeline }}").unwrap(),
+            "[Speaker A]\num so"
+        );
+        assert_eq!(
+            render("{{ words | truncate_transcript(max_tokens=6) | timeline }}").unwrap(),
+            "[Speaker A]\num so so"
+        );
+        assert!(render("{{ words | truncate_transcript(max_lines=2) }}").is_err());
+    }
 }
@@ -1,8 +1,11 @@
 use codes_iso_639::part_1::LanguageCode;
+use strum::IntoEnumIterator;
 
 mod filters;
 mod testers;
 
+pub mod timeline;
+
 mod error;
 pub use error::*;
 
@@ -28,12 +31,31 @@ impl From<Template> for String {
     }
 }
 
-#[derive(Debug, strum::AsRefStr, strum::Display)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum::AsRefStr,
+    strum::IntoStaticStr,
+    strum::Display,
+    strum::EnumString,
+    strum::EnumIter,
+)]
 pub enum PredefinedTemplate {
     #[strum(serialize = "enhance.system")]
     EnhanceSystem,
     #[strum(serialize = "enhance.user")]
     EnhanceUser,
+    #[strum(serialize = "enhance_partial.user")]
+    EnhancePartialUser,
+    #[strum(serialize = "enhance_merge.user")]
+    EnhanceMergeUser,
+    #[strum(serialize = "postprocess_enhance.system")]
+    PostprocessEnhanceSystem,
+    #[strum(serialize = "postprocess_enhance.user")]
+    PostprocessEnhanceUser,
     #[strum(serialize = "create_title.system")]
     CreateTitleSystem,
     #[strum(serialize = "create_title.user")]
@@ -42,79 +64,85 @@ pub enum PredefinedTemplate {
     SuggestTagsSystem,
     #[strum(serialize = "suggest_tags.user")]
     SuggestTagsUser,
+    #[strum(serialize = "extract_followups.system")]
+    ExtractFollowupsSystem,
+    #[strum(serialize = "extract_followups.user")]
+    ExtractFollowupsUser,
+    #[strum(serialize = "show_annotation.system")]
+    ShowAnnotationSystem,
+    #[strum(serialize = "show_annotation.user")]
+    ShowAnnotationUser,
     #[strum(serialize = "ai_chat.system")]
     AiChatSystem,
+    #[strum(serialize = "ai_chat_2.system")]
+    AiChat2System,
+}
+
+impl PredefinedTemplate {
+    /// The bundled source, used unless the user overrides it.
+    pub fn source(&self) -> &'static str {
+        match self {
+            PredefinedTemplate::EnhanceSystem => ENHANCE_SYSTEM_TPL,
+            PredefinedTemplate::EnhanceUser => ENHANCE_USER_TPL,
+            PredefinedTemplate::EnhancePartialUser => ENHANCE_PARTIAL_USER_TPL,
+            PredefinedTemplate::EnhanceMergeUser => ENHANCE_MERGE_USER_TPL,
+            PredefinedTemplate::PostprocessEnhanceSystem => POSTPROCESS_ENHANCE_SYSTEM_TPL,
+            PredefinedTemplate::PostprocessEnhanceUser => POSTPROCESS_ENHANCE_USER_TPL,
+            PredefinedTemplate::CreateTitleSystem => CREATE_TITLE_SYSTEM_TPL,
+            PredefinedTemplate::CreateTitleUser => CREATE_TITLE_USER_TPL,
+            PredefinedTemplate::SuggestTagsSystem => SUGGEST_TAGS_SYSTEM_TPL,
+            PredefinedTemplate::SuggestTagsUser => SUGGEST_TAGS_USER_TPL,
+            PredefinedTemplate::ExtractFollowupsSystem => EXTRACT_FOLLOWUPS_SYSTEM_TPL,
+            PredefinedTemplate::ExtractFollowupsUser => EXTRACT_FOLLOWUPS_USER_TPL,
+            PredefinedTemplate::ShowAnnotationSystem => SHOW_ANNOTATION_SYSTEM_TPL,
+            PredefinedTemplate::ShowAnnotationUser => SHOW_ANNOTATION_USER_TPL,
+            PredefinedTemplate::AiChatSystem => AI_CHAT_SYSTEM_TPL,
+            PredefinedTemplate::AiChat2System => AI_CHAT_2_SYSTEM_TPL,
+        }
+    }
 }
 
 impl From<PredefinedTemplate> for Template {
     fn from(value: PredefinedTemplate) -> Self {
-        match value {
-            PredefinedTemplate::EnhanceSystem => {
-                Template::Static(PredefinedTemplate::EnhanceSystem)
-            }
-            PredefinedTemplate::EnhanceUser => Template::Static(PredefinedTemplate::EnhanceUser),
-            PredefinedTemplate::CreateTitleSystem => {
-                Template::Static(PredefinedTemplate::CreateTitleSystem)
-            }
-            PredefinedTemplate::CreateTitleUser => {
-                Template::Static(PredefinedTemplate::CreateTitleUser)
-            }
-            PredefinedTemplate::SuggestTagsSystem => {
-                Template::Static(PredefinedTemplate::SuggestTagsSystem)
-            }
-            PredefinedTemplate::SuggestTagsUser => {
-                Template::Static(PredefinedTemplate::SuggestTagsUser)
-            }
-            PredefinedTemplate::AiChatSystem => Template::Static(PredefinedTemplate::AiChatSystem),
-        }
+        Template::Static(value)
     }
 }
 
 pub const ENHANCE_SYSTEM_TPL: &str = include_str!("../assets/enhance.system.jinja");
 pub const ENHANCE_USER_TPL: &str = include_str!("../assets/enhance.user.jinja");
+pub const ENHANCE_PARTIAL_USER_TPL: &str = include_str!("../assets/enhance_partial.user.jinja");
+pub const ENHANCE_MERGE_USER_TPL: &str = include_str!("../assets/enhance_merge.user.jinja");
+pub const POSTPROCESS_ENHANCE_SYSTEM_TPL: &str =
+    include_str!("../assets/postprocess_enhance.system.jinja");
+pub const POSTPROCESS_ENHANCE_USER_TPL: &str =
+    include_str!("../assets/postprocess_enhance.user.jinja");
 pub const CREATE_TITLE_SYSTEM_TPL: &str = include_str!("../assets/create_title.system.jinja");
 pub const CREATE_TITLE_USER_TPL: &str = include_str!("../assets/create_title.user.jinja");
 pub const SUGGEST_TAGS_SYSTEM_TPL: &str = include_str!("../assets/suggest_tags.system.jinja");
 pub const SUGGEST_TAGS_USER_TPL: &str = include_str!("../assets/suggest_tags.user.jinja");
+pub const EXTRACT_FOLLOWUPS_SYSTEM_TPL: &str =
+    include_str!("../assets/extract_followups.system.jinja");
+pub const EXTRACT_FOLLOWUPS_USER_TPL: &str = include_str!("../assets/extract_followups.user.jinja");
+pub const SHOW_ANNOTATION_SYSTEM_TPL: &str = include_str!("../assets/show_annotation.system.jinja");
+pub const SHOW_ANNOTATION_USER_TPL: &str = include_str!("../assets/show_annotation.user.jinja");
 pub const AI_CHAT_SYSTEM_TPL: &str = include_str!("../assets/ai_chat_system.jinja");
+pub const AI_CHAT_2_SYSTEM_TPL: &str = include_str!("../assets/ai_chat_2.system.jinja");
 
 pub fn init(env: &mut minijinja::Environment) {
     env.set_unknown_method_callback(minijinja_contrib::pycompat::unknown_method_callback);
 
-    env.add_template(
-        PredefinedTemplate::EnhanceSystem.as_ref(),
-        ENHANCE_SYSTEM_TPL,
-    )
-    .unwrap();
-    env.add_template(PredefinedTemplate::EnhanceUser.as_ref(), ENHANCE_USER_TPL)
-        .unwrap();
-    env.add_template(
-        PredefinedTemplate::CreateTitleSystem.as_ref(),
-        CREATE_TITLE_SYSTEM_TPL,
-    )
-    .unwrap();
-    env.add_template(
-        PredefinedTemplate::CreateTitleUser.as_ref(),
-        CREATE_TITLE_USER_TPL,
-    )
-    .unwrap();
-    env.add_template(
-        PredefinedTemplate::SuggestTagsSystem.as_ref(),
-        SUGGEST_TAGS_SYSTEM_TPL,
-    )
-    .unwrap();
-    env.add_template(
-        PredefinedTemplate::SuggestTagsUser.as_ref(),
-        SUGGEST_TAGS_USER_TPL,
-    )
-    .unwrap();
-    env.add_template(
-        PredefinedTemplate::AiChatSystem.as_ref(),
-        AI_CHAT_SYSTEM_TPL,
-    )
-    .unwrap();
+    for template in PredefinedTemplate::iter() {
+        let name: &'static str = template.into();
+        env.add_template(name, template.source()).unwrap();
+    }
 
     env.add_filter("timeline", filters::timeline);
+    env.add_filter("timestamped_timeline", filters::timestamped_timeline);
+    env.add_filter("time_range", filters::time_range);
+    env.add_filter("resolve_speakers", filters::resolve_speakers);
+    env.add_filter("truncate_transcript", filters::truncate_transcript);
+    env.add_filter("compact", filters::compact);
+    env.add_filter("clock", filters::clock);
     env.add_filter("language", filters::language);
 
     [LanguageCode::En, LanguageCode::Ko]
@@ -127,6 +155,34 @@ pub fn init(env: &mut minijinja::Environment) {
         });
 }
 
+/// Checks that `source` compiles, and renders with a context like the ones the app passes.
+/// Used before accepting a user's override of a predefined template.
+pub fn validate(source: &str) -> Result<(), crate::Error> {
+    let mut env = minijinja::Environment::new();
+    init(&mut env);
+
+    let tpl = env.template_from_str(source)?;
+    tpl.render(sample_context())?;
+    Ok(())
+}
+
+fn sample_context() -> serde_json::Value {
+    serde_json::json!({
+        "type": "HyprLocal",
+        "config": {
+            "general": { "display_language": "en" },
+            "ai": { "ai_specificity": 3 },
+        },
+        "words": "[]",
+        "participants": [],
+        "editor": "",
+        "pre_meeting_editor": "",
+        "in_meeting_editor": "",
+        "enhanced_note": "",
+        "sentence": "",
+    })
+}
+
 pub fn render(
     env: &minijinja::Environment<'static>,
     template: Template,
@@ -143,3 +199,60 @@ pub fn render(
         s
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_predefined_templates_are_valid() {
+        for template in PredefinedTemplate::iter() {
+            assert!(validate(template.source()).is_ok(), "invalid: {}", template);
+            assert_eq!(
+                template.as_ref().parse::<PredefinedTemplate>(),
+                Ok(template)
+            );
+        }
+    }
+
+    #[test]
+    fn test_ai_chat_system_related() {
+        let mut env = minijinja::Environment::new();
+        init(&mut env);
+
+        let ctx = serde_json::json!({
+            "type": "HyprLocal",
+            "related": [
+                {
+                    "session_title": "Roadmap",
+                    "session_created_at": "2025-06-02T10:00:00Z",
+                    "start_ms": 3_725_000,
+                    "content": "We ship in July.",
+                },
+                {
+                    "session_title": "Standup",
+                    "session_created_at": "2025-06-03T09:00:00Z",
+                    "start_ms": null,
+                    "content": "Nothing blocking.",
+                },
+            ],
+        });
+
+        let rendered = render(
+            &env,
+            Template::Static(PredefinedTemplate::AiChatSystem),
+            ctx.as_object().unwrap(),
+        )
+        .unwrap();
+
+        assert!(rendered.contains("[1] \"Roadmap\", 2025-06-02, at 62:05\nWe ship in July."));
+        assert!(rendered.contains("[2] \"Standup\", 2025-06-03\nNothing blocking."));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("{{ title }}").is_ok());
+        assert!(validate("{% if title %}").is_err());
+        assert!(validate("{{ title | unknown_filter }}").is_err());
+    }
+}
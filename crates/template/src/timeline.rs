@@ -0,0 +1,204 @@
+use itertools::Itertools;
+use owhisper_interface::Word2;
+
+// Per speaker turn, for the "[SPEAKER n]" line and the blank line around it.
+const TURN_OVERHEAD_TOKENS: usize = 8;
+
+/// Rough token count of a word as rendered by the `timeline` filter.
+/// Errs on the high side, since we don't have the tokenizer here.
+pub(crate) fn estimate_tokens(word: &Word2) -> usize {
+    word.text.len().div_ceil(3) + 1
+}
+
+/// Splits the transcript into consecutive windows of at most `max_tokens` (estimated) each.
+/// Windows end between speaker turns, unless a single turn is too long to fit. That one is cut at its longest pause.
+pub fn split(words: &[Word2], max_tokens: usize) -> Vec<Vec<Word2>> {
+    let mut windows = Vec::new();
+    let mut current: Vec<Word2> = Vec::new();
+    let mut current_tokens = 0;
+
+    let turns = words.iter().chunk_by(|word| word.speaker.clone());
+
+    for (_, turn) in &turns {
+        let turn = turn.cloned().collect::<Vec<_>>();
+        let turn_tokens = TURN_OVERHEAD_TOKENS + turn.iter().map(estimate_tokens).sum::<usize>();
+
+        if current_tokens + turn_tokens <= max_tokens {
+            current.extend(turn);
+            current_tokens += turn_tokens;
+            continue;
+        }
+
+        if !current.is_empty() {
+            windows.push(std::mem::take(&mut current));
+            current_tokens = 0;
+        }
+
+        if turn_tokens <= max_tokens {
+            current = turn;
+            current_tokens = turn_tokens;
+            continue;
+        }
+
+        let mut pieces = split_turn(turn, max_tokens.saturating_sub(TURN_OVERHEAD_TOKENS));
+        if let Some(last) = pieces.pop() {
+            current_tokens = TURN_OVERHEAD_TOKENS + last.iter().map(estimate_tokens).sum::<usize>();
+            current = last;
+        }
+        windows.extend(pieces);
+    }
+
+    if !current.is_empty() {
+        windows.push(current);
+    }
+
+    windows
+}
+
+fn split_turn(mut turn: Vec<Word2>, max_tokens: usize) -> Vec<Vec<Word2>> {
+    let mut pieces = Vec::new();
+
+    while !turn.is_empty() {
+        let mut fits = 0;
+        let mut tokens = 0;
+        for word in &turn {
+            tokens += estimate_tokens(word);
+            if tokens > max_tokens && fits > 0 {
+                break;
+            }
+            fits += 1;
+        }
+
+        if fits == turn.len() {
+            pieces.push(turn);
+            break;
+        }
+
+        // Looking only at the second half, so we don't end up with tiny pieces.
+        let cut = (fits / 2..fits)
+            .max_by_key(|&i| pause_before(&turn, i))
+            .filter(|&i| i > 0 && pause_before(&turn, i) > 0)
+            .unwrap_or(fits);
+
+        let rest = turn.split_off(cut);
+        pieces.push(turn);
+        turn = rest;
+    }
+
+    pieces
+}
+
+fn pause_before(words: &[Word2], index: usize) -> u64 {
+    if index == 0 {
+        return 0;
+    }
+
+    match (words[index - 1].end_ms, words[index].start_ms) {
+        (Some(end), Some(start)) => start.saturating_sub(end),
+        _ => 0,
+    }
+}
+
+// Shorter matches are too likely to be a coincidence.
+const MIN_LOCATE_WORDS: usize = 3;
+
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Finds where `quote` was said, as the range of `words` (end exclusive) sharing the longest run of words with it.
+/// Case and punctuation are ignored, since models rarely copy them exactly.
+pub fn locate(words: &[Word2], quote: &str) -> Option<(usize, usize)> {
+    let quote = quote
+        .split_whitespace()
+        .map(normalize)
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>();
+    let words = words.iter().map(|w| normalize(&w.text)).collect::<Vec<_>>();
+
+    // Length of the common run ending at each word of the quote, for the previous transcript word.
+    let mut previous = vec![0usize; quote.len() + 1];
+    let mut best = (0, 0);
+
+    for (i, word) in words.iter().enumerate() {
+        let mut current = vec![0usize; quote.len() + 1];
+        for (j, q) in quote.iter().enumerate() {
+            if !word.is_empty() && word == q {
+                current[j + 1] = previous[j] + 1;
+                if current[j + 1] > best.0 {
+                    best = (current[j + 1], i + 1);
+                }
+            }
+        }
+        previous = current;
+    }
+
+    let (len, end) = best;
+    (len >= MIN_LOCATE_WORDS.min(quote.len()) && len > 0).then(|| (end - len, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use owhisper_interface::SpeakerIdentity;
+
+    fn words() -> Vec<Word2> {
+        serde_json::from_str(hypr_data::english_3::WORDS_JSON).unwrap()
+    }
+
+    #[test]
+    fn test_split_keeps_everything_in_order() {
+        let words = words();
+        let windows = split(&words, 200);
+
+        assert!(windows.len() > 1);
+        assert_eq!(windows.concat(), words);
+    }
+
+    #[test]
+    fn test_split_fits_in_one_window() {
+        let words = words();
+        assert_eq!(split(&words, usize::MAX), vec![words]);
+    }
+
+    #[test]
+    fn test_locate() {
+        let words = "okay so we agree to push the launch to April right"
+            .split(' ')
+            .map(|text| Word2 {
+                text: text.to_string(),
+                speaker: None,
+                confidence: None,
+                start_ms: None,
+                end_ms: None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(locate(&words, "We agree to push the launch."), Some((2, 8)));
+        assert_eq!(locate(&words, "push the launch to May"), Some((5, 9)));
+        assert_eq!(locate(&words, "the launch"), Some((6, 8)));
+        assert_eq!(locate(&words, "launch the rocket"), None);
+        assert_eq!(locate(&words, ""), None);
+    }
+
+    #[test]
+    fn test_split_long_turn_at_pause() {
+        let words = (0..20u64)
+            .map(|i| Word2 {
+                text: "word".to_string(),
+                speaker: Some(SpeakerIdentity::Unassigned { index: 0 }),
+                confidence: None,
+                // Long pause before the 7th word.
+                start_ms: Some(i * 500 + if i >= 6 { 5000 } else { 0 }),
+                end_ms: Some(i * 500 + 400 + if i >= 6 { 5000 } else { 0 }),
+            })
+            .collect::<Vec<_>>();
+
+        let windows = split(&words, TURN_OVERHEAD_TOKENS + 3 * 8);
+        assert_eq!(windows[0].len(), 6);
+        assert_eq!(windows.concat(), words);
+    }
+}
@@ -25,6 +25,8 @@ use std::{
 };
 use tower::Service;
 
+use owhisper_interface::ListenOutputControl;
+
 use aws_config::{meta::region::RegionProviderChain, BehaviorVersion};
 use aws_sdk_transcribestreaming::primitives::Blob;
 use aws_sdk_transcribestreaming::types::{
@@ -125,9 +127,13 @@ impl TranscribeService {
                 };
 
                 if sender.send(Message::Text(json.into())).await.is_err() {
-                    break;
+                    return;
                 }
             }
+
+            // Results close once transcription is over, so nothing is left to flush.
+            let msg = serde_json::to_string(&ListenOutputControl::Finalized).unwrap();
+            let _ = sender.send(Message::Text(msg.into())).await;
         });
 
         // Start transcription
@@ -137,7 +143,7 @@ impl TranscribeService {
 
         // Clean up tasks
         audio_handler.abort();
-        result_sender.abort();
+        let _ = result_sender.await;
     }
 
     /// Start AWS Transcribe streaming
@@ -20,7 +20,7 @@ use deepgram::{
     Deepgram,
 };
 
-use owhisper_interface::ListenParams;
+use owhisper_interface::{ListenInputChunk, ListenOutputControl, ListenParams};
 
 #[derive(Clone)]
 pub struct TranscribeService {
@@ -65,6 +65,11 @@ impl TranscribeService {
                             break;
                         }
                     }
+                    Message::Text(text) => {
+                        if let Ok(ListenInputChunk::End) = serde_json::from_str(&text) {
+                            break;
+                        }
+                    }
                     Message::Close(_) => break,
                     _ => {}
                 }
@@ -99,6 +104,10 @@ impl TranscribeService {
                         }
                     }
                 }
+
+                // Deepgram closes its stream once the audio ends and everything before it is transcribed.
+                let msg = serde_json::to_string(&ListenOutputControl::Finalized).unwrap();
+                let _ = sender.send(Message::Text(msg.into())).await;
             }
             Err(e) => {
                 tracing::error!("Failed to start Deepgram stream: {:?}", e);
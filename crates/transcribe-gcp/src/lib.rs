@@ -10,6 +10,8 @@ use axum::{
 };
 use futures_util::{SinkExt, StreamExt};
 
+use owhisper_interface::ListenOutputControl;
+
 mod error;
 pub use error::*;
 
@@ -75,9 +77,13 @@ impl TranscribeService {
                 };
 
                 if sender.send(Message::Text(json.into())).await.is_err() {
-                    break;
+                    return;
                 }
             }
+
+            // Results close once transcription is over, so nothing is left to flush.
+            let msg = serde_json::to_string(&ListenOutputControl::Finalized).unwrap();
+            let _ = sender.send(Message::Text(msg.into())).await;
         });
 
         // Start transcription
@@ -87,7 +93,7 @@ impl TranscribeService {
 
         // Clean up tasks
         audio_handler.abort();
-        result_sender.abort();
+        let _ = result_sender.await;
     }
 
     /// Start AWS Transcribe streaming
@@ -6,6 +6,8 @@ use axum::{
 };
 use std::sync::{Arc, Mutex};
 
+use owhisper_interface::{ListenInputChunk, ListenOutputControl};
+
 use crate::MoonshineOnnxModel;
 
 pub struct TranscribeService {
@@ -38,6 +40,16 @@ impl TranscribeService {
                         error!("Error processing audio: {}", e);
                     }
                 }
+                Ok(Message::Text(text)) => {
+                    // Audio is transcribed as it arrives, so by `End` every result has been sent.
+                    if let Ok(ListenInputChunk::End) = serde_json::from_str(&text) {
+                        let msg = serde_json::to_string(&ListenOutputControl::Finalized).unwrap();
+                        if let Err(e) = socket.send(Message::Text(msg.into())).await {
+                            error!("Error sending finalized: {}", e);
+                        }
+                        break;
+                    }
+                }
                 Ok(Message::Close(_)) => {
                     info!("WebSocket connection closed");
                     break;
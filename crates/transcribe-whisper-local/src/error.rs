@@ -1,2 +1,13 @@
 #[derive(Debug, thiserror::Error)]
-pub enum Error {}
+pub enum Error {
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    DecoderError(#[from] rodio::decoder::DecoderError),
+    #[error(transparent)]
+    AudioUtilsError(#[from] hypr_audio_utils::Error),
+    #[error(transparent)]
+    SegmentationError(#[from] hypr_pyannote_local::Error),
+    #[error(transparent)]
+    WhisperError(#[from] hypr_whisper_local::Error),
+}
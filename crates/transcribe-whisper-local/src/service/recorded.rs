@@ -1,26 +1,49 @@
+use std::ops::Range;
+
 use owhisper_interface::Word2;
 
+const SAMPLE_RATE: u32 = 16000;
+
 pub fn process_recorded(
     model_path: impl AsRef<std::path::Path>,
     audio_path: impl AsRef<std::path::Path>,
 ) -> Result<Vec<Word2>, crate::Error> {
-    use rodio::Source;
+    process_recorded_spans(model_path, audio_path, &[0..u64::MAX], |_| {})
+}
 
-    let decoder = rodio::Decoder::new(std::io::BufReader::new(
-        std::fs::File::open(audio_path.as_ref()).unwrap(),
-    ))
-    .unwrap();
+/// Transcribes only the given millisecond ranges of the recording. Returned words use the recording's timeline.
+pub fn process_recorded_spans(
+    model_path: impl AsRef<std::path::Path>,
+    audio_path: impl AsRef<std::path::Path>,
+    spans: &[Range<u64>],
+    on_word: impl FnMut(&Word2),
+) -> Result<Vec<Word2>, crate::Error> {
+    let decoder = rodio::Decoder::new(std::io::BufReader::new(std::fs::File::open(
+        audio_path.as_ref(),
+    )?))?;
 
-    let original_sample_rate = decoder.sample_rate();
+    process_recorded_source(model_path, decoder, spans, on_word)
+}
 
-    let resampled_samples = if original_sample_rate != 16000 {
-        hypr_audio_utils::resample_audio(decoder, 16000).unwrap()
+/// Same as [`process_recorded_spans`], for audio that is not a single file.
+pub fn process_recorded_source<S, T>(
+    model_path: impl AsRef<std::path::Path>,
+    source: S,
+    spans: &[Range<u64>],
+    mut on_word: impl FnMut(&Word2),
+) -> Result<Vec<Word2>, crate::Error>
+where
+    S: rodio::Source<Item = T> + Iterator<Item = T>,
+    T: rodio::Sample,
+{
+    let original_sample_rate = source.sample_rate();
+
+    let resampled_samples = if original_sample_rate != SAMPLE_RATE {
+        hypr_audio_utils::resample_audio(source, SAMPLE_RATE)?
     } else {
-        decoder.convert_samples().collect()
+        source.convert_samples().collect()
     };
 
-    let samples_i16 = hypr_audio_utils::f32_to_i16_samples(&resampled_samples);
-
     let mut model = hypr_whisper_local::Whisper::builder()
         .model_path(model_path.as_ref().to_str().unwrap())
         .languages(vec![])
@@ -28,34 +51,50 @@ pub fn process_recorded(
         .dynamic_prompt("")
         .build();
 
-    let mut segmenter = hypr_pyannote_local::segmentation::Segmenter::new(16000).unwrap();
-    let segments = segmenter.process(&samples_i16, 16000).unwrap();
+    let mut segmenter = hypr_pyannote_local::segmentation::Segmenter::new(SAMPLE_RATE)?;
 
     let mut words = Vec::new();
 
-    for segment in segments {
-        let audio_f32 = hypr_audio_utils::i16_to_f32_samples(&segment.samples);
+    for span in spans {
+        let from = ms_to_sample(span.start).min(resampled_samples.len());
+        let to = ms_to_sample(span.end).min(resampled_samples.len());
+        if from >= to {
+            continue;
+        }
+
+        let span_offset_sec = from as f64 / SAMPLE_RATE as f64;
+        let samples_i16 = hypr_audio_utils::f32_to_i16_samples(&resampled_samples[from..to]);
+        let segments = segmenter.process(&samples_i16, SAMPLE_RATE)?;
 
-        let whisper_segments = model.transcribe(&audio_f32).unwrap();
+        for segment in segments {
+            let audio_f32 = hypr_audio_utils::i16_to_f32_samples(&segment.samples);
 
-        for whisper_segment in whisper_segments {
-            let start_sec: f64 = segment.start + (whisper_segment.start() as f64);
-            let end_sec: f64 = segment.start + (whisper_segment.end() as f64);
-            let start_ms = (start_sec * 1000.0) as u64;
-            let end_ms = (end_sec * 1000.0) as u64;
+            let whisper_segments = model.transcribe(&audio_f32)?;
 
-            let word = Word2 {
-                text: whisper_segment.text().to_string(),
-                speaker: None,
-                confidence: Some(whisper_segment.confidence()),
-                start_ms: Some(start_ms),
-                end_ms: Some(end_ms),
-            };
+            for whisper_segment in whisper_segments {
+                let segment_start_sec = span_offset_sec + segment.start;
+                let start_sec: f64 = segment_start_sec + (whisper_segment.start() as f64);
+                let end_sec: f64 = segment_start_sec + (whisper_segment.end() as f64);
+                let start_ms = (start_sec * 1000.0) as u64;
+                let end_ms = (end_sec * 1000.0) as u64;
 
-            // TODO
-            words.push(word.clone());
+                let word = Word2 {
+                    text: whisper_segment.text().to_string(),
+                    speaker: None,
+                    confidence: Some(whisper_segment.confidence()),
+                    start_ms: Some(start_ms),
+                    end_ms: Some(end_ms),
+                };
+
+                on_word(&word);
+                words.push(word);
+            }
         }
     }
 
     Ok(words)
 }
+
+fn ms_to_sample(ms: u64) -> usize {
+    (ms.saturating_mul(SAMPLE_RATE as u64) / 1000) as usize
+}
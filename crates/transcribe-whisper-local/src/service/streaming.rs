@@ -17,11 +17,14 @@ use axum::{
 use futures_util::{SinkExt, StreamExt};
 use tower::Service;
 
-use hypr_chunker::VadExt;
-use owhisper_interface::{ListenOutputChunk, ListenParams, Word2};
+use hypr_chunker::{VadChunkConfig, VadExt};
+use owhisper_interface::{ListenOutputChunk, ListenOutputControl, ListenParams, Word2};
 
 use crate::manager::{ConnectionGuard, ConnectionManager};
 
+// Shorter segments are too little audio to transcribe on their own.
+const MIN_MAX_SPEECH_TIME: Duration = Duration::from_secs(1);
+
 #[derive(Clone)]
 pub struct TranscribeService {
     model_path: PathBuf,
@@ -82,6 +85,13 @@ where
                 }
             };
 
+            let vad_config = match vad_config(&params) {
+                Ok(config) => config,
+                Err(e) => {
+                    return Ok((StatusCode::BAD_REQUEST, e).into_response());
+                }
+            };
+
             let (mut parts, _body) = req.into_parts();
             let ws_upgrade = match WebSocketUpgrade::from_request_parts(&mut parts, &()).await {
                 Ok(ws) => ws,
@@ -93,7 +103,7 @@ where
             let guard = connection_manager.acquire_connection();
 
             let response = ws_upgrade.on_upgrade(move |socket| async move {
-                handle_websocket_connection(socket, params, model_path, guard).await
+                handle_websocket_connection(socket, params, vad_config, model_path, guard).await
             });
 
             Ok(response.into_response())
@@ -104,6 +114,7 @@ where
 async fn handle_websocket_connection(
     socket: WebSocket,
     params: ListenParams,
+    vad_config: VadChunkConfig,
     model_path: PathBuf,
     guard: ConnectionGuard,
 ) {
@@ -124,37 +135,54 @@ async fn handle_websocket_connection(
 
     match params.audio_mode {
         owhisper_interface::AudioMode::Single => {
-            handle_single_channel(
-                ws_sender,
-                ws_receiver,
-                model,
-                guard,
-                Duration::from_millis(params.redemption_time_ms),
-            )
-            .await;
+            handle_single_channel(ws_sender, ws_receiver, model, guard, vad_config).await;
         }
         owhisper_interface::AudioMode::Dual => {
-            handle_dual_channel(
-                ws_sender,
-                ws_receiver,
-                model,
-                guard,
-                Duration::from_millis(params.redemption_time_ms),
-            )
-            .await;
+            handle_dual_channel(ws_sender, ws_receiver, model, guard, vad_config).await;
         }
     }
 }
 
+fn vad_config(params: &ListenParams) -> Result<VadChunkConfig, &'static str> {
+    let default =
+        VadChunkConfig::from_redemption_time(Duration::from_millis(params.redemption_time_ms));
+
+    let positive_speech_threshold = params
+        .vad_threshold
+        .unwrap_or(default.positive_speech_threshold);
+    if !(0.0..=1.0).contains(&positive_speech_threshold) {
+        return Err("vad_threshold must be between 0 and 1");
+    }
+
+    Ok(VadChunkConfig {
+        positive_speech_threshold,
+        pre_speech_pad: params
+            .vad_pre_speech_pad_ms
+            .map_or(default.pre_speech_pad, Duration::from_millis),
+        post_speech_pad: params
+            .vad_post_speech_pad_ms
+            .map_or(default.post_speech_pad, Duration::from_millis),
+        min_speech_time: params
+            .vad_min_speech_ms
+            .map_or(default.min_speech_time, Duration::from_millis),
+        max_speech_time: match params.vad_max_speech_ms {
+            None => default.max_speech_time,
+            Some(0) => None,
+            Some(ms) => Some(Duration::from_millis(ms).max(MIN_MAX_SPEECH_TIME)),
+        },
+        ..default
+    })
+}
+
 async fn handle_single_channel(
     ws_sender: futures_util::stream::SplitSink<WebSocket, Message>,
     ws_receiver: futures_util::stream::SplitStream<WebSocket>,
     model: hypr_whisper_local::Whisper,
     guard: ConnectionGuard,
-    redemption_time: Duration,
+    vad_config: VadChunkConfig,
 ) {
     let audio_source = hypr_ws_utils::WebSocketAudioSource::new(ws_receiver, 16 * 1000);
-    let vad_chunks = audio_source.vad_chunks(redemption_time);
+    let vad_chunks = audio_source.vad_chunks_with(vad_config);
 
     let chunked = hypr_whisper_local::AudioChunkStream(process_vad_stream(vad_chunks, "mixed"));
 
@@ -167,18 +195,18 @@ async fn handle_dual_channel(
     ws_receiver: futures_util::stream::SplitStream<WebSocket>,
     model: hypr_whisper_local::Whisper,
     guard: ConnectionGuard,
-    redemption_time: Duration,
+    vad_config: VadChunkConfig,
 ) {
     let (mic_source, speaker_source) =
         hypr_ws_utils::split_dual_audio_sources(ws_receiver, 16 * 1000);
 
     let mic_chunked = {
-        let mic_vad_chunks = mic_source.vad_chunks(redemption_time);
+        let mic_vad_chunks = mic_source.vad_chunks_with(vad_config);
         hypr_whisper_local::AudioChunkStream(process_vad_stream(mic_vad_chunks, "mic"))
     };
 
     let speaker_chunked = {
-        let speaker_vad_chunks = speaker_source.vad_chunks(redemption_time);
+        let speaker_vad_chunks = speaker_source.vad_chunks_with(vad_config);
         hypr_whisper_local::AudioChunkStream(process_vad_stream(speaker_vad_chunks, "speaker"))
     };
 
@@ -205,7 +233,14 @@ async fn process_transcription_stream(
                 break;
             }
             chunk_opt = stream.next() => {
-                let Some(chunk) = chunk_opt else { break };
+                let Some(chunk) = chunk_opt else {
+                    // Input ended (client sent `End` or went away), and every pending chunk has been transcribed.
+                    let msg = Message::Text(serde_json::to_string(&ListenOutputControl::Finalized).unwrap().into());
+                    if let Err(e) = ws_sender.send(msg).await {
+                        tracing::warn!("websocket_send_error: {}", e);
+                    }
+                    break;
+                };
 
                 let meta = chunk.meta();
                 let text = chunk.text().to_string();
@@ -281,3 +316,32 @@ where
             })
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vad_config() {
+        let params = |f: fn(&mut ListenParams)| {
+            let mut params = ListenParams {
+                redemption_time_ms: 500,
+                ..Default::default()
+            };
+            f(&mut params);
+            params
+        };
+
+        assert!(vad_config(&params(|p| p.vad_threshold = Some(1.5))).is_err());
+        assert!(vad_config(&params(|p| p.vad_threshold = Some(f32::NAN))).is_err());
+
+        let config = vad_config(&params(|_| {})).unwrap();
+        assert_eq!(config.max_speech_time, Some(Duration::from_secs(25)));
+
+        let config = vad_config(&params(|p| p.vad_max_speech_ms = Some(0))).unwrap();
+        assert_eq!(config.max_speech_time, None);
+
+        let config = vad_config(&params(|p| p.vad_max_speech_ms = Some(10))).unwrap();
+        assert_eq!(config.max_speech_time, Some(MIN_MAX_SPEECH_TIME));
+    }
+}
@@ -14,8 +14,24 @@ pub trait WebSocketIO: Send + 'static {
     fn to_input(data: Self::Data) -> Self::Input;
     fn to_message(input: Self::Input) -> Message;
     fn from_message(msg: Message) -> Option<Self::Output>;
+
+    /// Sent after the input stream is exhausted, asking the server to flush what it has buffered.
+    fn finalize_message() -> Option<Message> {
+        None
+    }
+
+    /// Whether `msg` is the server's reply to `finalize_message`, after which nothing else will arrive.
+    fn is_terminal(_msg: &Message) -> bool {
+        false
+    }
 }
 
+/// Longest the client waits for the server's reply to `finalize_message` before closing the socket.
+pub const FINALIZE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+// Servers that never reply to `finalize_message` are given this long to go quiet instead.
+const FINALIZE_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
 pub struct WebSocketClient {
     request: ClientRequestBuilder,
 }
@@ -43,6 +59,8 @@ impl WebSocketClient {
             .await?;
 
         let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+        // Ticks on every server message, and closes once the receiving side is done (finalized or closed).
+        let (activity_tx, mut activity_rx) = tokio::sync::mpsc::unbounded_channel::<()>();
 
         let _send_task = tokio::spawn(async move {
             while let Some(data) = audio_stream.next().await {
@@ -51,13 +69,41 @@ impl WebSocketClient {
 
                 if let Err(e) = ws_sender.send(msg).await {
                     tracing::error!("ws_send_failed: {:?}", e);
-                    break;
+                    let _ = ws_sender.close().await;
+                    return;
+                }
+            }
+
+            match T::finalize_message() {
+                Some(msg) => {
+                    if let Err(e) = ws_sender.send(msg).await {
+                        tracing::error!("ws_finalize_send_failed: {:?}", e);
+                    } else {
+                        let deadline = tokio::time::Instant::now() + FINALIZE_TIMEOUT;
+                        loop {
+                            let idle =
+                                (tokio::time::Instant::now() + FINALIZE_IDLE_TIMEOUT).min(deadline);
+                            match tokio::time::timeout_at(idle, activity_rx.recv()).await {
+                                Ok(Some(())) => continue,
+                                Ok(None) => break,
+                                Err(_) if idle < deadline => {
+                                    tracing::debug!("ws_finalize_idle");
+                                    break;
+                                }
+                                Err(_) => {
+                                    tracing::warn!("ws_finalize_timeout");
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+                None => {
+                    // Without a finalize handshake, all we can do is give the server some time.
+                    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
                 }
             }
 
-            // Wait 5 seconds before closing the connection
-            // TODO: This might not be enough to ensure receiving remaining transcripts from the server.
-            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
             let _ = ws_sender.close().await;
         });
 
@@ -67,11 +113,17 @@ impl WebSocketClient {
                     Ok(msg) => {
                         match msg {
                             Message::Text(_) | Message::Binary(_) => {
-                            if let Some(output) = T::from_message(msg) {
-                                yield output;
-                            }
-                        },
-                        Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => continue,
+                                if T::is_terminal(&msg) {
+                                    break;
+                                }
+
+                                let _ = activity_tx.send(());
+
+                                if let Some(output) = T::from_message(msg) {
+                                    yield output;
+                                }
+                            },
+                            Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => continue,
                             Message::Close(_) => break,
                         }
                     }
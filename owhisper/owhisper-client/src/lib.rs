@@ -4,7 +4,7 @@ use hypr_audio::AsyncSource;
 use hypr_audio_utils::AudioFormatExt;
 use hypr_ws::client::{ClientRequestBuilder, Message, WebSocketClient, WebSocketIO};
 
-use owhisper_interface::{ListenInputChunk, ListenOutputChunk};
+use owhisper_interface::{ListenInputChunk, ListenOutputChunk, ListenOutputControl};
 
 #[derive(Default)]
 pub struct ListenClientBuilder {
@@ -61,6 +61,31 @@ impl ListenClientBuilder {
                 .append_pair("static_prompt", &params.static_prompt)
                 .append_pair("dynamic_prompt", &params.dynamic_prompt)
                 .append_pair("redemption_time_ms", &params.redemption_time_ms.to_string());
+
+            let vad_params = [
+                ("vad_threshold", params.vad_threshold.map(|v| v.to_string())),
+                (
+                    "vad_pre_speech_pad_ms",
+                    params.vad_pre_speech_pad_ms.map(|v| v.to_string()),
+                ),
+                (
+                    "vad_post_speech_pad_ms",
+                    params.vad_post_speech_pad_ms.map(|v| v.to_string()),
+                ),
+                (
+                    "vad_min_speech_ms",
+                    params.vad_min_speech_ms.map(|v| v.to_string()),
+                ),
+                (
+                    "vad_max_speech_ms",
+                    params.vad_max_speech_ms.map(|v| v.to_string()),
+                ),
+            ];
+            for (key, value) in vad_params {
+                if let Some(value) = value {
+                    query_pairs.append_pair(key, &value);
+                }
+            }
         }
 
         let host = url.host_str().unwrap();
@@ -128,6 +153,14 @@ impl WebSocketIO for ListenClient {
             _ => None,
         }
     }
+
+    fn finalize_message() -> Option<Message> {
+        Some(Self::to_message(ListenInputChunk::End))
+    }
+
+    fn is_terminal(msg: &Message) -> bool {
+        is_finalized(msg)
+    }
 }
 
 #[derive(Clone)]
@@ -157,6 +190,24 @@ impl WebSocketIO for ListenClientDual {
             _ => None,
         }
     }
+
+    fn finalize_message() -> Option<Message> {
+        Some(Self::to_message(ListenInputChunk::End))
+    }
+
+    fn is_terminal(msg: &Message) -> bool {
+        is_finalized(msg)
+    }
+}
+
+fn is_finalized(msg: &Message) -> bool {
+    match msg {
+        Message::Text(text) => matches!(
+            serde_json::from_str::<ListenOutputControl>(text),
+            Ok(ListenOutputControl::Finalized)
+        ),
+        _ => false,
+    }
 }
 
 impl ListenClient {
@@ -189,7 +240,7 @@ impl ListenClientDual {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use futures_util::StreamExt;
+    use futures_util::{SinkExt, StreamExt};
 
     #[tokio::test]
     #[ignore]
@@ -240,4 +291,68 @@ mod tests {
             println!("{:?}", result);
         }
     }
+
+    #[tokio::test]
+    async fn test_end_finalized_round_trip() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // Holds back its transcript until `End`, then flushes it and replies `Finalized` without closing.
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+            let mut received = 0;
+            while let Some(Ok(msg)) = ws.next().await {
+                let Message::Text(text) = msg else { continue };
+
+                match serde_json::from_str::<ListenInputChunk>(&text).unwrap() {
+                    ListenInputChunk::Audio { .. } => received += 1,
+                    ListenInputChunk::End => {
+                        let chunk = ListenOutputChunk {
+                            meta: None,
+                            words: vec![owhisper_interface::Word2 {
+                                text: format!("{}", received),
+                                ..Default::default()
+                            }],
+                        };
+                        let control = ListenOutputControl::Finalized;
+
+                        for msg in [
+                            serde_json::to_string(&chunk).unwrap(),
+                            serde_json::to_string(&control).unwrap(),
+                        ] {
+                            ws.send(Message::Text(msg.into())).await.unwrap();
+                        }
+                    }
+                    ListenInputChunk::DualAudio { .. } => unreachable!(),
+                }
+            }
+        });
+
+        let client = ListenClient::builder()
+            .api_base(format!("http://{}", addr))
+            .build_single();
+
+        let audio = futures_util::stream::iter(vec![bytes::Bytes::from(vec![0u8; 320]); 3]);
+        let stream = WebSocketClient::new(client.request.clone())
+            .from_audio::<ListenClient>(audio)
+            .await
+            .unwrap();
+
+        // Both sides finish well before the client would give up on a server that never finalizes.
+        let chunks = tokio::time::timeout(
+            std::time::Duration::from_secs(2),
+            stream.collect::<Vec<_>>(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].words[0].text, "3");
+
+        tokio::time::timeout(std::time::Duration::from_secs(2), server)
+            .await
+            .unwrap()
+            .unwrap();
+    }
 }
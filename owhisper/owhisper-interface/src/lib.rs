@@ -63,6 +63,15 @@ common_derives! {
     }
 }
 
+// Sent by the server once all audio received before `ListenInputChunk::End` has been transcribed.
+common_derives! {
+    #[serde(tag = "type", content = "value")]
+    pub enum ListenOutputControl {
+        #[serde(rename = "finalized")]
+        Finalized,
+    }
+}
+
 common_derives! {
     #[serde(tag = "type", content = "value")]
     pub enum ListenInputChunk {
@@ -113,6 +122,18 @@ common_derives! {
         pub static_prompt: String,
         pub dynamic_prompt: String,
         pub redemption_time_ms: u64,
+        /// Speech probability above which audio counts as speech. Server default when unset.
+        #[serde(default)]
+        pub vad_threshold: Option<f32>,
+        #[serde(default)]
+        pub vad_pre_speech_pad_ms: Option<u64>,
+        #[serde(default)]
+        pub vad_post_speech_pad_ms: Option<u64>,
+        #[serde(default)]
+        pub vad_min_speech_ms: Option<u64>,
+        /// Longer speech is split into segments of at most this length. `0` never splits.
+        #[serde(default)]
+        pub vad_max_speech_ms: Option<u64>,
     }
 }
 
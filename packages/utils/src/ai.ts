@@ -37,6 +37,8 @@ const getModel = async ({ onboarding }: { onboarding: boolean }) => {
     headers: {
       "Origin": "http://localhost:1420",
     },
+    // `generateObject` sends its schema as `response_format: { type: "json_schema" }`, not just `json_object`.
+    supportsStructuredOutputs: true,
   });
 
   const customModel = await connectorCommands.getCustomLlmModel();
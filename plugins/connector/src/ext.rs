@@ -21,6 +21,9 @@ pub trait ConnectorPluginExt<R: tauri::Runtime> {
     fn set_custom_llm_connection(&self, connection: Connection) -> Result<(), crate::Error>;
 
     fn get_llm_connection(&self) -> impl Future<Output = Result<ConnectionLLM, crate::Error>>;
+
+    fn get_local_stt_connection(&self)
+        -> impl Future<Output = Result<ConnectionSTT, crate::Error>>;
     fn get_stt_connection(&self) -> impl Future<Output = Result<ConnectionSTT, crate::Error>>;
 
     fn get_admin_connection(&self) -> Result<Option<Connection>, crate::Error>;
@@ -169,6 +172,24 @@ impl<R: tauri::Runtime, T: tauri::Manager<R>> ConnectorPluginExt<R> for T {
         }
     }
 
+    async fn get_local_stt_connection(&self) -> Result<ConnectionSTT, crate::Error> {
+        use tauri_plugin_local_stt::{LocalSttPluginExt, SharedState};
+
+        let api_base = if self.is_server_running().await {
+            let state = self.state::<SharedState>();
+            let guard = state.lock().await;
+            guard.api_base.clone().unwrap()
+        } else {
+            self.start_server().await?
+        };
+
+        let conn = ConnectionSTT::HyprLocal(Connection {
+            api_base,
+            api_key: None,
+        });
+        Ok(conn)
+    }
+
     async fn get_stt_connection(&self) -> Result<ConnectionSTT, crate::Error> {
         {
             use tauri_plugin_flags::{FlagsPluginExt, StoreKey as FlagsStoreKey};
@@ -211,23 +232,7 @@ impl<R: tauri::Runtime, T: tauri::Manager<R>> ConnectorPluginExt<R> for T {
             }
         }
 
-        {
-            use tauri_plugin_local_stt::{LocalSttPluginExt, SharedState};
-
-            let api_base = if self.is_server_running().await {
-                let state = self.state::<SharedState>();
-                let guard = state.lock().await;
-                guard.api_base.clone().unwrap()
-            } else {
-                self.start_server().await?
-            };
-
-            let conn = ConnectionSTT::HyprLocal(Connection {
-                api_base,
-                api_key: None,
-            });
-            Ok(conn)
-        }
+        self.get_local_stt_connection().await
     }
 
     fn get_admin_connection(&self) -> Result<Option<Connection>, crate::Error> {
@@ -54,6 +54,15 @@ const COMMANDS: &[&str] = &[
     "list_session_tags",
     "assign_tag_to_session",
     "unassign_tag_from_session",
+    "upsert_action_item",
+    "set_action_item_completed",
+    "delete_action_item",
+    "list_session_action_items",
+    "list_open_action_items",
+    "upsert_decision",
+    "delete_decision",
+    "list_session_decisions",
+    "get_session_talk_time",
 ];
 
 fn main() {
@@ -144,6 +144,33 @@ async upsertTag(tag: Tag) : Promise<Tag> {
 },
 async deleteTag(tagId: string) : Promise<null> {
     return await TAURI_INVOKE("plugin:db|delete_tag", { tagId });
+},
+async upsertActionItem(item: ActionItem) : Promise<ActionItem> {
+    return await TAURI_INVOKE("plugin:db|upsert_action_item", { item });
+},
+async setActionItemCompleted(id: string, completed: boolean) : Promise<null> {
+    return await TAURI_INVOKE("plugin:db|set_action_item_completed", { id, completed });
+},
+async deleteActionItem(id: string) : Promise<null> {
+    return await TAURI_INVOKE("plugin:db|delete_action_item", { id });
+},
+async listSessionActionItems(sessionId: string) : Promise<ActionItem[]> {
+    return await TAURI_INVOKE("plugin:db|list_session_action_items", { sessionId });
+},
+async listOpenActionItems(ownerId: string | null) : Promise<ActionItem[]> {
+    return await TAURI_INVOKE("plugin:db|list_open_action_items", { ownerId });
+},
+async upsertDecision(decision: Decision) : Promise<Decision> {
+    return await TAURI_INVOKE("plugin:db|upsert_decision", { decision });
+},
+async deleteDecision(id: string) : Promise<null> {
+    return await TAURI_INVOKE("plugin:db|delete_decision", { id });
+},
+async listSessionDecisions(sessionId: string) : Promise<Decision[]> {
+    return await TAURI_INVOKE("plugin:db|list_session_decisions", { sessionId });
+},
+async getSessionTalkTime(sessionId: string) : Promise<TalkTimeStats> {
+    return await TAURI_INVOKE("plugin:db|get_session_talk_time", { sessionId });
 }
 }
 
@@ -157,15 +184,36 @@ async deleteTag(tagId: string) : Promise<null> {
 
 /** user-defined types **/
 
+export type ActionItem = { id: string; session_id: string; created_at: string; text: string; 
+/**
+ * The `Human` responsible for it, if known.
+ */
+owner_id: string | null; due_date: string | null; 
+/**
+ * Open while `None`.
+ */
+completed_at: string | null; 
+/**
+ * Where it came from in `Session::words`. `end_word` is exclusive.
+ */
+start_word: number | null; end_word: number | null; start_ms: number | null; end_ms: number | null }
 export type Calendar = { id: string; tracking_id: string; user_id: string; platform: Platform; name: string; selected: boolean; source: string | null }
 export type ChatGroup = { id: string; user_id: string; name: string | null; created_at: string; session_id: string }
 export type ChatMessage = { id: string; group_id: string; created_at: string; role: ChatMessageRole; content: string }
 export type ChatMessageRole = "User" | "Assistant"
 export type Config = { id: string; user_id: string; general: ConfigGeneral; notification: ConfigNotification; ai: ConfigAI }
-export type ConfigAI = { api_base: string | null; api_key: string | null; ai_specificity: number | null; redemption_time_ms: number | null }
-export type ConfigGeneral = { autostart: boolean; display_language: string; spoken_languages?: string[]; jargons?: string[]; telemetry_consent: boolean; save_recordings: boolean | null; selected_template_id: string | null }
+export type ConfigAI = { api_base: string | null; api_key: string | null; ai_specificity: number | null; redemption_time_ms: number | null; vad?: ConfigVad | null }
+export type ConfigAudioProcessing = { mic_gain: number | null; speaker_gain: number | null; noise_suppression: boolean | null; gain_control: GainControlMode | null }
+export type ConfigGeneral = { autostart: boolean; display_language: string; spoken_languages?: string[]; jargons?: string[]; telemetry_consent: boolean; save_recordings: boolean | null; selected_template_id: string | null; recording_format?: RecordingFormat | null; save_multitrack?: boolean | null; audio_processing?: ConfigAudioProcessing | null }
 export type ConfigNotification = { before: boolean; auto: boolean; ignoredPlatforms: string[] | null }
+export type ConfigVad = { threshold: number | null; pre_speech_pad_ms: number | null; post_speech_pad_ms: number | null; min_speech_ms: number | null; max_speech_ms: number | null }
+export type Decision = { id: string; session_id: string; created_at: string; text: string; 
+/**
+ * Where it came from in `Session::words`. `end_word` is exclusive.
+ */
+start_word: number | null; end_word: number | null; start_ms: number | null; end_ms: number | null }
 export type Event = { id: string; user_id: string; tracking_id: string; calendar_id: string | null; name: string; note: string; start_date: string; end_date: string; google_event_url: string | null; participants: string | null }
+export type GainControlMode = "off" | "agc" | "loudness"
 export type GetSessionFilter = { id: string } | { calendarEventId: string } | { tagId: string }
 export type Human = { id: string; organization_id: string | null; is_user: boolean; full_name: string | null; email: string | null; job_title: string | null; linkedin_username: string | null }
 export type ListEventFilter = ({ user_id: string; limit: number | null }) & ({ type: "simple" } | { type: "search"; query: string } | { type: "dateRange"; start: string; end: string } | { type: "not-assigned-past" })
@@ -174,9 +222,29 @@ export type ListOrganizationFilter = { search: [number, string] }
 export type ListSessionFilter = ({ user_id: string; limit: number | null }) & ({ type: "search"; query: string } | { type: "recentlyVisited" } | { type: "dateRange"; start: string; end: string } | { type: "tagFilter"; tag_ids: string[] })
 export type Organization = { id: string; name: string; description: string | null }
 export type Platform = "Apple" | "Google" | "Outlook"
+export type RecordingFormat = "wav" | "flac" | "opus"
 export type Session = { id: string; created_at: string; visited_at: string; user_id: string; calendar_event_id: string | null; title: string; raw_memo_html: string; enhanced_memo_html: string | null; words: Word2[]; record_start: string | null; record_end: string | null; pre_meeting_memo_html: string | null }
 export type SpeakerIdentity = { type: "unassigned"; value: { index: number } } | { type: "assigned"; value: { id: string; label: string } }
+export type SpeakerTalkTime = { channel: SpeechChannel; speaker: SpeakerIdentity | null; talk_ms: number; 
+/**
+ * Of all talk time in the session, between 0 and 1.
+ */
+share: number; longest_monologue_ms: number; 
+/**
+ * Times they started speaking while someone else kept going.
+ */
+interruptions: number }
+export type SpeechChannel = "Mic" | "Speaker"
 export type Tag = { id: string; name: string }
+export type TalkTimeStats = { 
+/**
+ * Up to the end of the last speech.
+ */
+duration_ms: number; speech_ms: number; silence_ratio: number; overlap_ms: number; overlap_count: number; interruption_count: number; 
+/**
+ * In order of first appearance.
+ */
+speakers: SpeakerTalkTime[] }
 export type Template = { id: string; user_id: string; title: string; description: string; sections: TemplateSection[]; tags: string[] }
 export type TemplateSection = { title: string; description: string }
 export type Word2 = { text: string; speaker: SpeakerIdentity | null; confidence: number | null; start_ms: number | null; end_ms: number | null }
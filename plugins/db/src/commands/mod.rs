@@ -1,9 +1,12 @@
+pub mod action_items;
 pub mod calendars;
 pub mod chats;
 pub mod configs;
+pub mod decisions;
 pub mod events;
 pub mod humans;
 pub mod organizations;
 pub mod sessions;
+pub mod speech_segments;
 pub mod tags;
 pub mod templates;
@@ -106,7 +106,12 @@ pub async fn upsert_session(
         .ok_or(crate::Error::NoneDatabase)
         .map_err(|e| e.to_string())?;
 
-    db.upsert_session(session).await.map_err(|e| e.to_string())
+    let session = db
+        .upsert_session(session)
+        .await
+        .map_err(|e| e.to_string())?;
+    guard.session_updates.send_replace(());
+    Ok(session)
 }
 
 #[tauri::command]
@@ -0,0 +1,19 @@
+#[tauri::command]
+#[specta::specta]
+#[tracing::instrument(skip(state))]
+pub async fn get_session_talk_time(
+    state: tauri::State<'_, crate::ManagedState>,
+    session_id: String,
+) -> Result<hypr_db_user::TalkTimeStats, String> {
+    let guard = state.lock().await;
+
+    let db = guard
+        .db
+        .as_ref()
+        .ok_or(crate::Error::NoneDatabase)
+        .map_err(|e| e.to_string())?;
+
+    db.get_session_talk_time(session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -26,7 +26,37 @@ pub trait DatabasePluginExt<R: tauri::Runtime> {
         session: hypr_db_user::Session,
     ) -> impl Future<Output = Result<(), crate::Error>>;
 
+    /// Changes after every session upsert. `None` until the plugin is set up.
+    fn db_session_updates(&self) -> impl Future<Output = Option<tokio::sync::watch::Receiver<()>>>;
+
     fn db_onboarding_session_id(&self) -> impl Future<Output = Result<String, crate::Error>>;
+
+    fn db_sync_stale_session_chunks(
+        &self,
+        limit: u32,
+    ) -> impl Future<Output = Result<usize, crate::Error>>;
+    fn db_list_unembedded_session_chunks(
+        &self,
+        model: impl Into<String>,
+        limit: u32,
+    ) -> impl Future<Output = Result<Vec<hypr_db_user::SessionChunk>, crate::Error>>;
+    fn db_set_session_chunk_embedding(
+        &self,
+        id: impl Into<String>,
+        model: impl Into<String>,
+        embedding: &[f32],
+    ) -> impl Future<Output = Result<(), crate::Error>>;
+    fn db_search_session_chunks(
+        &self,
+        model: impl Into<String>,
+        embedding: &[f32],
+        limit: u8,
+    ) -> impl Future<Output = Result<Vec<hypr_db_user::SessionChunkMatch>, crate::Error>>;
+
+    fn db_insert_speech_segments(
+        &self,
+        segments: Vec<hypr_db_user::SpeechSegment>,
+    ) -> impl Future<Output = Result<(), crate::Error>>;
 }
 
 impl<R: tauri::Runtime, T: tauri::Manager<R>> DatabasePluginExt<R> for T {
@@ -118,10 +148,18 @@ impl<R: tauri::Runtime, T: tauri::Manager<R>> DatabasePluginExt<R> for T {
 
         let db = guard.db.as_ref().ok_or(crate::Error::NoneDatabase)?;
         db.upsert_session(session).await?;
+        guard.session_updates.send_replace(());
 
         Ok(())
     }
 
+    async fn db_session_updates(&self) -> Option<tokio::sync::watch::Receiver<()>> {
+        let state = self.try_state::<crate::ManagedState>()?;
+        let guard = state.lock().await;
+
+        Some(guard.session_updates.subscribe())
+    }
+
     async fn db_get_config(
         &self,
         user_id: impl Into<String>,
@@ -142,4 +180,69 @@ impl<R: tauri::Runtime, T: tauri::Manager<R>> DatabasePluginExt<R> for T {
         let id = db.onboarding_session_id();
         Ok(id)
     }
+
+    async fn db_sync_stale_session_chunks(&self, limit: u32) -> Result<usize, crate::Error> {
+        let state = self.state::<crate::ManagedState>();
+        let guard = state.lock().await;
+
+        let db = guard.db.as_ref().ok_or(crate::Error::NoneDatabase)?;
+        let synced = db.sync_stale_session_chunks(limit).await?;
+        Ok(synced)
+    }
+
+    async fn db_list_unembedded_session_chunks(
+        &self,
+        model: impl Into<String>,
+        limit: u32,
+    ) -> Result<Vec<hypr_db_user::SessionChunk>, crate::Error> {
+        let state = self.state::<crate::ManagedState>();
+        let guard = state.lock().await;
+
+        let db = guard.db.as_ref().ok_or(crate::Error::NoneDatabase)?;
+        let chunks = db.list_unembedded_session_chunks(model, limit).await?;
+        Ok(chunks)
+    }
+
+    async fn db_set_session_chunk_embedding(
+        &self,
+        id: impl Into<String>,
+        model: impl Into<String>,
+        embedding: &[f32],
+    ) -> Result<(), crate::Error> {
+        let state = self.state::<crate::ManagedState>();
+        let guard = state.lock().await;
+
+        let db = guard.db.as_ref().ok_or(crate::Error::NoneDatabase)?;
+        db.set_session_chunk_embedding(id, model, embedding).await?;
+        Ok(())
+    }
+
+    async fn db_search_session_chunks(
+        &self,
+        model: impl Into<String>,
+        embedding: &[f32],
+        limit: u8,
+    ) -> Result<Vec<hypr_db_user::SessionChunkMatch>, crate::Error> {
+        let state = self.state::<crate::ManagedState>();
+        let guard = state.lock().await;
+
+        let db = guard.db.as_ref().ok_or(crate::Error::NoneDatabase)?;
+        let user_id = guard.user_id.as_ref().ok_or(crate::Error::NoneUser)?;
+        let matches = db
+            .search_session_chunks(user_id, model, embedding, limit)
+            .await?;
+        Ok(matches)
+    }
+
+    async fn db_insert_speech_segments(
+        &self,
+        segments: Vec<hypr_db_user::SpeechSegment>,
+    ) -> Result<(), crate::Error> {
+        let state = self.state::<crate::ManagedState>();
+        let guard = state.lock().await;
+
+        let db = guard.db.as_ref().ok_or(crate::Error::NoneDatabase)?;
+        db.insert_speech_segments(segments).await?;
+        Ok(())
+    }
 }
@@ -10,10 +10,21 @@ pub use ext::DatabasePluginExt;
 
 pub type ManagedState = Mutex<State>;
 
-#[derive(Default)]
 pub struct State {
     pub user_id: Option<String>,
     pub db: Option<hypr_db_user::UserDatabase>,
+    /// Ticks after every session upsert.
+    pub session_updates: tokio::sync::watch::Sender<()>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            user_id: None,
+            db: None,
+            session_updates: tokio::sync::watch::channel(()).0,
+        }
+    }
 }
 
 const PLUGIN_NAME: &str = "db";
@@ -68,6 +79,15 @@ fn make_specta_builder<R: tauri::Runtime>() -> tauri_specta::Builder<R> {
             commands::tags::unassign_tag_from_session,
             commands::tags::upsert_tag,
             commands::tags::delete_tag,
+            commands::action_items::upsert_action_item,
+            commands::action_items::set_action_item_completed,
+            commands::action_items::delete_action_item,
+            commands::action_items::list_session_action_items,
+            commands::action_items::list_open_action_items,
+            commands::decisions::upsert_decision,
+            commands::decisions::delete_decision,
+            commands::decisions::list_session_decisions,
+            commands::speech_segments::get_session_talk_time,
         ])
         .error_handling(tauri_specta::ErrorHandlingMode::Throw)
 }
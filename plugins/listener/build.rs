@@ -12,11 +12,19 @@ const COMMANDS: &[&str] = &[
     "set_mic_muted",
     "get_speaker_muted",
     "set_speaker_muted",
+    "get_aec_delay_ms",
     "start_session",
     "stop_session",
     "pause_session",
     "resume_session",
     "get_state",
+    "retranscribe_session",
+    "player_open",
+    "player_play",
+    "player_pause",
+    "player_seek",
+    "player_set_rate",
+    "player_close",
 ];
 
 fn main() {
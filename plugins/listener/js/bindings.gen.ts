@@ -46,6 +46,9 @@ async getSpeakerMuted() : Promise<boolean> {
 async setSpeakerMuted(muted: boolean) : Promise<null> {
     return await TAURI_INVOKE("plugin:listener|set_speaker_muted", { muted });
 },
+async getAecDelayMs() : Promise<number | null> {
+    return await TAURI_INVOKE("plugin:listener|get_aec_delay_ms");
+},
 async startSession(sessionId: string) : Promise<null> {
     return await TAURI_INVOKE("plugin:listener|start_session", { sessionId });
 },
@@ -60,6 +63,27 @@ async resumeSession() : Promise<null> {
 },
 async getState() : Promise<string> {
     return await TAURI_INVOKE("plugin:listener|get_state");
+},
+async retranscribeSession(sessionId: string) : Promise<Word[]> {
+    return await TAURI_INVOKE("plugin:listener|retranscribe_session", { sessionId });
+},
+async playerOpen(sessionId: string) : Promise<null> {
+    return await TAURI_INVOKE("plugin:listener|player_open", { sessionId });
+},
+async playerPlay() : Promise<null> {
+    return await TAURI_INVOKE("plugin:listener|player_play");
+},
+async playerPause() : Promise<null> {
+    return await TAURI_INVOKE("plugin:listener|player_pause");
+},
+async playerSeek(positionMs: number) : Promise<null> {
+    return await TAURI_INVOKE("plugin:listener|player_seek", { positionMs });
+},
+async playerSetRate(rate: number) : Promise<null> {
+    return await TAURI_INVOKE("plugin:listener|player_set_rate", { rate });
+},
+async playerClose() : Promise<null> {
+    return await TAURI_INVOKE("plugin:listener|player_close");
 }
 }
 
@@ -67,8 +91,10 @@ async getState() : Promise<string> {
 
 
 export const events = __makeEvents__<{
+playerEvent: PlayerEvent,
 sessionEvent: SessionEvent
 }>({
+playerEvent: "plugin:listener:player-event",
 sessionEvent: "plugin:listener:session-event"
 })
 
@@ -78,7 +104,15 @@ sessionEvent: "plugin:listener:session-event"
 
 /** user-defined types **/
 
-export type SessionEvent = { type: "inactive" } | { type: "running_active" } | { type: "running_paused" } | { type: "words"; words: Word[] } | { type: "audioAmplitude"; mic: number; speaker: number } | { type: "micMuted"; value: boolean } | { type: "speakerMuted"; value: boolean }
+/**
+ * Emitted while a recorded session is played back.
+ */
+export type PlayerEvent = { session_id: string; position_ms: number; duration_ms: number; playing: boolean; rate: number; 
+/**
+ * Index into the session's words of the one being spoken, if any.
+ */
+word_index: number | null }
+export type SessionEvent = { type: "inactive" } | { type: "running_active" } | { type: "running_paused" } | { type: "words"; words: Word[] } | { type: "audioAmplitude"; mic: number; speaker: number } | { type: "micMuted"; value: boolean } | { type: "speakerMuted"; value: boolean } | { type: "sttFallback" } | { type: "sttRestored" }
 export type SpeakerIdentity = { type: "unassigned"; value: { index: number } } | { type: "assigned"; value: { id: string; label: string } }
 export type Word = { text: string; speaker: SpeakerIdentity | null; confidence: number | null; start_ms: number | null; end_ms: number | null }
 
@@ -105,6 +105,14 @@ pub async fn get_speaker_muted<R: tauri::Runtime>(
     Ok(app.get_speaker_muted().await)
 }
 
+#[tauri::command]
+#[specta::specta]
+pub async fn get_aec_delay_ms<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+) -> Result<Option<f32>, String> {
+    Ok(app.get_aec_delay_ms().await)
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn set_mic_muted<R: tauri::Runtime>(
@@ -175,3 +183,62 @@ pub async fn get_state<R: tauri::Runtime>(
 ) -> Result<crate::fsm::State, String> {
     Ok(app.get_state().await)
 }
+
+#[tauri::command]
+#[specta::specta]
+pub async fn retranscribe_session<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    session_id: String,
+) -> Result<Vec<owhisper_interface::Word2>, String> {
+    app.retranscribe_session(session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn player_open<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    session_id: String,
+) -> Result<(), String> {
+    app.player_open(session_id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn player_play<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    app.player_play().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn player_pause<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    app.player_pause().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn player_seek<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    position_ms: u64,
+) -> Result<(), String> {
+    app.player_seek(position_ms)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn player_set_rate<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    rate: f32,
+) -> Result<(), String> {
+    app.player_set_rate(rate).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn player_close<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    app.player_close().await;
+    Ok(())
+}
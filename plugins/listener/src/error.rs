@@ -14,8 +14,22 @@ pub enum Error {
     DatabaseError(#[from] tauri_plugin_db::Error),
     #[error(transparent)]
     ConnectorError(#[from] tauri_plugin_connector::Error),
+    #[error(transparent)]
+    LocalSttError(#[from] tauri_plugin_local_stt::Error),
+    #[error(transparent)]
+    RecorderError(#[from] hypr_recorder::Error),
+    #[error(transparent)]
+    ListenClientError(#[from] hypr_ws::Error),
+    #[error(transparent)]
+    JoinError(#[from] tokio::task::JoinError),
+    #[error("system audio unavailable: {0}")]
+    SpeakerUnavailable(String),
     #[error("no session")]
     NoneSession,
+    #[error("no player")]
+    NonePlayer,
+    #[error("no multitrack recording")]
+    NoMultitrackRecording,
     #[error("start session failed")]
     StartSessionFailed,
     #[error("stop session failed")]
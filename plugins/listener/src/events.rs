@@ -23,6 +23,23 @@ common_event_derives! {
         MicMuted { value: bool },
         #[serde(rename = "speakerMuted")]
         SpeakerMuted { value: bool },
+        #[serde(rename = "sttFallback")]
+        SttFallback {},
+        #[serde(rename = "sttRestored")]
+        SttRestored {},
+    }
+}
+
+common_event_derives! {
+    /// Emitted while a recorded session is played back.
+    pub struct PlayerEvent {
+        pub session_id: String,
+        pub position_ms: u64,
+        pub duration_ms: u64,
+        pub playing: bool,
+        pub rate: f32,
+        /// Index into the session's words of the one being spoken, if any.
+        pub word_index: Option<usize>,
     }
 }
 
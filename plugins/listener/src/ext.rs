@@ -29,12 +29,28 @@ pub trait ListenerPluginExt<R: tauri::Runtime> {
     fn get_speaker_muted(&self) -> impl Future<Output = bool>;
     fn set_mic_muted(&self, muted: bool) -> impl Future<Output = ()>;
     fn set_speaker_muted(&self, muted: bool) -> impl Future<Output = ()>;
+    fn get_aec_delay_ms(&self) -> impl Future<Output = Option<f32>>;
 
     fn get_state(&self) -> impl Future<Output = crate::fsm::State>;
     fn stop_session(&self) -> impl Future<Output = ()>;
     fn start_session(&self, id: impl Into<String>) -> impl Future<Output = ()>;
     fn pause_session(&self) -> impl Future<Output = ()>;
     fn resume_session(&self) -> impl Future<Output = ()>;
+
+    fn retranscribe_session(
+        &self,
+        session_id: impl Into<String>,
+    ) -> impl Future<Output = Result<Vec<owhisper_interface::Word2>, crate::Error>>;
+
+    fn player_open(
+        &self,
+        session_id: impl Into<String>,
+    ) -> impl Future<Output = Result<(), crate::Error>>;
+    fn player_play(&self) -> impl Future<Output = Result<(), crate::Error>>;
+    fn player_pause(&self) -> impl Future<Output = Result<(), crate::Error>>;
+    fn player_seek(&self, position_ms: u64) -> impl Future<Output = Result<(), crate::Error>>;
+    fn player_set_rate(&self, rate: f32) -> impl Future<Output = Result<(), crate::Error>>;
+    fn player_close(&self) -> impl Future<Output = ()>;
 }
 
 impl<R: tauri::Runtime, T: tauri::Manager<R>> ListenerPluginExt<R> for T {
@@ -46,8 +62,8 @@ impl<R: tauri::Runtime, T: tauri::Manager<R>> ListenerPluginExt<R> for T {
     #[tracing::instrument(skip_all)]
     async fn get_current_microphone_device(&self) -> Result<Option<String>, crate::Error> {
         let state = self.state::<crate::SharedState>();
-        let s = state.lock().await;
-        Ok(s.fsm.get_current_mic_device())
+        let current = state.lock().await.fsm.get_current_mic_device();
+        Ok(current.await)
     }
 
     #[tracing::instrument(skip_all)]
@@ -203,6 +219,16 @@ impl<R: tauri::Runtime, T: tauri::Manager<R>> ListenerPluginExt<R> for T {
         }
     }
 
+    #[tracing::instrument(skip_all)]
+    async fn get_aec_delay_ms(&self) -> Option<f32> {
+        let state = self.state::<crate::SharedState>();
+
+        {
+            let guard = state.lock().await;
+            guard.fsm.get_aec_delay_ms()
+        }
+    }
+
     #[tracing::instrument(skip_all)]
     async fn set_mic_muted(&self, muted: bool) {
         let state = self.state::<crate::SharedState>();
@@ -268,4 +294,81 @@ impl<R: tauri::Runtime, T: tauri::Manager<R>> ListenerPluginExt<R> for T {
             guard.fsm.handle(&event).await;
         }
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn retranscribe_session(
+        &self,
+        session_id: impl Into<String>,
+    ) -> Result<Vec<owhisper_interface::Word2>, crate::Error> {
+        crate::retranscribe::run(self.app_handle(), &session_id.into()).await
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn player_open(&self, session_id: impl Into<String>) -> Result<(), crate::Error> {
+        use tauri_plugin_db::DatabasePluginExt;
+
+        let session_id = session_id.into();
+        let session = self
+            .db_get_session(&session_id)
+            .await?
+            .ok_or(crate::Error::NoneSession)?;
+
+        let state = self.state::<crate::player::SharedPlayer>();
+        // The previous player has to release the output first.
+        state.lock().await.take();
+
+        // Opening reads the recording, and waits for the output device.
+        let app = self.app_handle().clone();
+        let player = tokio::task::spawn_blocking(move || {
+            crate::player::SessionPlayer::open(&app, session_id, session.words)
+        })
+        .await??;
+        *state.lock().await = Some(player);
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn player_play(&self) -> Result<(), crate::Error> {
+        let state = self.state::<crate::player::SharedPlayer>();
+        let guard = state.lock().await;
+        guard.as_ref().ok_or(crate::Error::NonePlayer)?.play();
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn player_pause(&self) -> Result<(), crate::Error> {
+        let state = self.state::<crate::player::SharedPlayer>();
+        let guard = state.lock().await;
+        guard.as_ref().ok_or(crate::Error::NonePlayer)?.pause();
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn player_seek(&self, position_ms: u64) -> Result<(), crate::Error> {
+        let state = self.state::<crate::player::SharedPlayer>();
+        let guard = state.lock().await;
+        guard
+            .as_ref()
+            .ok_or(crate::Error::NonePlayer)?
+            .seek(position_ms);
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn player_set_rate(&self, rate: f32) -> Result<(), crate::Error> {
+        let state = self.state::<crate::player::SharedPlayer>();
+        let guard = state.lock().await;
+        guard
+            .as_ref()
+            .ok_or(crate::Error::NonePlayer)?
+            .set_rate(rate);
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn player_close(&self) {
+        let state = self.state::<crate::player::SharedPlayer>();
+        state.lock().await.take();
+    }
 }
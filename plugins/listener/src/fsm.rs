@@ -2,18 +2,18 @@ use std::time::{Duration, Instant};
 
 use statig::prelude::*;
 
-use tauri::Manager;
-use tauri_specta::Event;
-
 use futures_util::StreamExt;
 use tokio::task::JoinSet;
 
-use hypr_audio::AsyncSource;
-
 use crate::SessionEvent;
 
-const SAMPLE_RATE: u32 = 16000;
+pub(crate) const SAMPLE_RATE: u32 = 16000;
 const AUDIO_AMPLITUDE_THROTTLE: Duration = Duration::from_millis(100);
+// Outlasts the listen client's own wait for `Finalized`, so it normally closes the socket itself.
+const LISTEN_FINALIZE_TIMEOUT: Duration =
+    Duration::from_secs(hypr_ws::client::FINALIZE_TIMEOUT.as_secs() + 5);
+const RECORDING_FINALIZE_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_MIC_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 const WAV_SPEC: hound::WavSpec = hound::WavSpec {
     channels: 1,
@@ -27,20 +27,13 @@ struct AudioSaver;
 impl AudioSaver {
     async fn save_to_wav(
         rx: flume::Receiver<Vec<f32>>,
-        session_id: &str,
-        app_dir: &std::path::Path,
-        filename: &str,
-        append: bool,
+        path: std::path::PathBuf,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let dir = app_dir.join(session_id);
-        std::fs::create_dir_all(&dir)?;
-        let path = dir.join(filename);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
 
-        let mut wav = if append && path.exists() {
-            hound::WavWriter::append(path)?
-        } else {
-            hound::WavWriter::create(path, WAV_SPEC)?
-        };
+        let mut wav = hound::WavWriter::create(path, WAV_SPEC)?;
 
         while let Ok(chunk) = rx.recv_async().await {
             for sample in chunk {
@@ -51,6 +44,110 @@ impl AudioSaver {
         wav.finalize()?;
         Ok(())
     }
+
+    // Chunks are compacted in the background as soon as they are complete, so that a crash leaves at most one WAV chunk behind.
+    async fn save_chunked(
+        rx: flume::Receiver<Vec<f32>>,
+        session_dir: std::path::PathBuf,
+        channels: u16,
+        format: hypr_recorder::Format,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut compactions = JoinSet::new();
+        let mut compact = |path: std::path::PathBuf| {
+            if format != hypr_recorder::Format::Wav {
+                compactions.spawn_blocking(move || hypr_recorder::compact_chunk(path, format));
+            }
+        };
+
+        // Left over from a session that did not stop cleanly.
+        for path in hypr_recorder::uncompacted_chunks(&session_dir)? {
+            compact(path);
+        }
+
+        let mut writer = hypr_recorder::ChunkedWriter::new(&session_dir, channels, SAMPLE_RATE)?;
+
+        while let Ok(chunk) = rx.recv_async().await {
+            if let Some(path) = writer.write(&chunk)? {
+                compact(path);
+            }
+        }
+
+        if let Some(path) = writer.finish()? {
+            compact(path);
+        }
+
+        while let Some(res) = compactions.join_next().await {
+            if let Ok(Err(e)) = res {
+                tracing::error!("recording_compaction_failed: {:?}", e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Speech on each channel, in the same timeline as the recording and the transcript.
+async fn track_speech(
+    store: std::sync::Arc<dyn crate::pipeline::SessionStore>,
+    session_id: String,
+    rx: flume::Receiver<(Vec<f32>, Vec<f32>)>,
+) {
+    use hypr_chunker::{SpeechSpan, SpeechTracker, VadChunkConfig};
+    use hypr_db_user::{SpeechChannel, SpeechSegment};
+
+    // Padding would make every turn change look like an overlap.
+    let config = VadChunkConfig {
+        pre_speech_pad: Duration::ZERO,
+        ..Default::default()
+    };
+
+    let (Ok(mut mic), Ok(mut speaker)) = (
+        SpeechTracker::new(config, SAMPLE_RATE),
+        SpeechTracker::new(config, SAMPLE_RATE),
+    ) else {
+        tracing::error!("speech_tracker_creation_failed");
+        return;
+    };
+
+    let segment = |channel: SpeechChannel, span: SpeechSpan| SpeechSegment {
+        id: uuid::Uuid::new_v4().to_string(),
+        session_id: session_id.clone(),
+        channel,
+        start_ms: span.start.as_millis() as u64,
+        end_ms: span.end.as_millis() as u64,
+    };
+
+    while let Ok((mic_chunk, speaker_chunk)) = rx.recv_async().await {
+        let mut segments = vec![];
+
+        for (channel, tracker, chunk) in [
+            (SpeechChannel::Mic, &mut mic, mic_chunk),
+            (SpeechChannel::Speaker, &mut speaker, speaker_chunk),
+        ] {
+            match tracker.process(&chunk) {
+                Ok(spans) => {
+                    segments.extend(spans.into_iter().map(|span| segment(channel.clone(), span)))
+                }
+                Err(e) => tracing::error!(channel = %channel, "speech_tracking_failed: {:?}", e),
+            }
+        }
+
+        if !segments.is_empty() {
+            store.speech_segments(segments).await;
+        }
+    }
+
+    let ongoing = [
+        (SpeechChannel::Mic, mic.finish()),
+        (SpeechChannel::Speaker, speaker.finish()),
+    ]
+    .into_iter()
+    .filter_map(|(channel, span)| Some(segment(channel, span?)))
+    .collect::<Vec<_>>();
+
+    if !ongoing.is_empty() {
+        store.speech_segments(ongoing).await;
+    }
 }
 
 struct AudioChannels {
@@ -60,6 +157,8 @@ struct AudioChannels {
     speaker_rx: flume::Receiver<Vec<f32>>,
     save_mixed_tx: flume::Sender<Vec<f32>>,
     save_mixed_rx: flume::Receiver<Vec<f32>>,
+    save_multitrack_tx: Option<flume::Sender<Vec<f32>>>,
+    save_multitrack_rx: Option<flume::Receiver<Vec<f32>>>,
     save_mic_raw_tx: Option<flume::Sender<Vec<f32>>>,
     save_mic_raw_rx: Option<flume::Receiver<Vec<f32>>>,
     save_speaker_raw_tx: Option<flume::Sender<Vec<f32>>>,
@@ -68,10 +167,12 @@ struct AudioChannels {
     process_mic_rx: flume::Receiver<Vec<f32>>,
     process_speaker_tx: flume::Sender<Vec<f32>>,
     process_speaker_rx: flume::Receiver<Vec<f32>>,
+    activity_tx: flume::Sender<(Vec<f32>, Vec<f32>)>,
+    activity_rx: flume::Receiver<(Vec<f32>, Vec<f32>)>,
 }
 
 impl AudioChannels {
-    fn new() -> Self {
+    fn new(multitrack: bool) -> Self {
         const CHUNK_BUFFER_SIZE: usize = 64;
 
         let (mic_tx, mic_rx) = flume::bounded::<Vec<f32>>(CHUNK_BUFFER_SIZE);
@@ -80,6 +181,14 @@ impl AudioChannels {
         let (process_mic_tx, process_mic_rx) = flume::bounded::<Vec<f32>>(CHUNK_BUFFER_SIZE);
         let (process_speaker_tx, process_speaker_rx) =
             flume::bounded::<Vec<f32>>(CHUNK_BUFFER_SIZE);
+        let (activity_tx, activity_rx) = flume::bounded::<(Vec<f32>, Vec<f32>)>(CHUNK_BUFFER_SIZE);
+
+        let (save_multitrack_tx, save_multitrack_rx) = if multitrack {
+            let (tx, rx) = flume::bounded::<Vec<f32>>(CHUNK_BUFFER_SIZE);
+            (Some(tx), Some(rx))
+        } else {
+            (None, None)
+        };
 
         let (save_mic_raw_tx, save_mic_raw_rx) = if cfg!(debug_assertions) {
             let (tx, rx) = flume::bounded::<Vec<f32>>(CHUNK_BUFFER_SIZE);
@@ -102,6 +211,8 @@ impl AudioChannels {
             speaker_rx,
             save_mixed_tx,
             save_mixed_rx,
+            save_multitrack_tx,
+            save_multitrack_rx,
             save_mic_raw_tx,
             save_mic_raw_rx,
             save_speaker_raw_tx,
@@ -110,31 +221,96 @@ impl AudioChannels {
             process_mic_rx,
             process_speaker_tx,
             process_speaker_rx,
+            activity_tx,
+            activity_rx,
         }
     }
 
+    // Switches devices in place when asked to, or when the system default changes while it is followed.
+    // The new stream feeds the same channel, so AEC, the recording and the upstream carry on as they are.
     async fn process_mic_stream(
-        mut mic_stream: impl futures_util::Stream<Item = Vec<f32>> + Unpin,
+        sources: std::sync::Arc<dyn crate::pipeline::AudioSources>,
+        mut mic_stream: crate::pipeline::AudioStream,
+        mut current_device: Option<String>,
+        mut mic_device_rx: tokio::sync::watch::Receiver<Option<String>>,
         mic_muted_rx: tokio::sync::watch::Receiver<bool>,
         mic_tx: flume::Sender<Vec<f32>>,
+        mic_switched_tx: tokio::sync::watch::Sender<()>,
     ) {
         let mut is_muted = *mic_muted_rx.borrow();
         let watch_rx = mic_muted_rx.clone();
 
-        while let Some(actual) = mic_stream.next().await {
-            if watch_rx.has_changed().unwrap_or(false) {
-                is_muted = *watch_rx.borrow();
-            }
+        let mut default_poll = tokio::time::interval(DEFAULT_MIC_POLL_INTERVAL);
+        default_poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut failed_device = None;
+
+        loop {
+            let wanted = tokio::select! {
+                actual = mic_stream.next() => {
+                    let Some(actual) = actual else {
+                        // The device went away, e.g. a USB mic was unplugged. Carry on with the default.
+                        match sources.open_mic(None).await {
+                            Ok(stream) => {
+                                let fallback = sources.default_mic_device().await;
+                                tracing::info!(from = ?current_device, to = ?fallback, "mic_lost");
+                                mic_stream = stream;
+                                current_device = fallback;
+                                failed_device = None;
+                                mic_switched_tx.send_replace(());
+                                continue;
+                            }
+                            Err(e) => {
+                                tracing::error!(device = ?current_device, "mic_lost_without_fallback: {:?}", e);
+                                break;
+                            }
+                        }
+                    };
 
-            let maybe_muted = if is_muted {
-                vec![0.0; actual.len()]
-            } else {
-                actual
+                    if watch_rx.has_changed().unwrap_or(false) {
+                        is_muted = *watch_rx.borrow();
+                    }
+
+                    let maybe_muted = if is_muted {
+                        vec![0.0; actual.len()]
+                    } else {
+                        actual
+                    };
+
+                    if let Err(e) = mic_tx.send_async(maybe_muted).await {
+                        tracing::error!("mic_tx_send_error: {:?}", e);
+                        break;
+                    }
+                    continue;
+                }
+                Ok(()) = mic_device_rx.changed() => {
+                    failed_device = None;
+                    let selected = mic_device_rx.borrow_and_update().clone();
+                    match selected {
+                        Some(device) => Some(device),
+                        None => sources.default_mic_device().await,
+                    }
+                }
+                _ = default_poll.tick(), if mic_device_rx.borrow().is_none() => {
+                    sources.default_mic_device().await
+                }
             };
 
-            if let Err(e) = mic_tx.send_async(maybe_muted).await {
-                tracing::error!("mic_tx_send_error: {:?}", e);
-                break;
+            if wanted == current_device || wanted == failed_device {
+                continue;
+            }
+
+            match sources.open_mic(wanted.clone()).await {
+                Ok(stream) => {
+                    tracing::info!(from = ?current_device, to = ?wanted, "mic_switched");
+                    mic_stream = stream;
+                    current_device = wanted;
+                    mic_switched_tx.send_replace(());
+                }
+                Err(e) => {
+                    // The previous device keeps going, if it still can.
+                    tracing::error!(device = ?wanted, "mic_switch_failed: {:?}", e);
+                    failed_device = wanted;
+                }
             }
         }
     }
@@ -167,128 +343,108 @@ impl AudioChannels {
 }
 
 pub struct Session {
-    app: tauri::AppHandle,
+    host: crate::pipeline::Host,
+    stop_tx: tokio::sync::mpsc::UnboundedSender<()>,
     session_id: Option<String>,
+    // `None` follows the system default.
     mic_device_name: Option<String>,
+    mic_device_tx: Option<tokio::sync::watch::Sender<Option<String>>>,
     mic_muted_tx: Option<tokio::sync::watch::Sender<bool>>,
     mic_muted_rx: Option<tokio::sync::watch::Receiver<bool>>,
     speaker_muted_tx: Option<tokio::sync::watch::Sender<bool>>,
     speaker_muted_rx: Option<tokio::sync::watch::Receiver<bool>>,
-    silence_stream_tx: Option<std::sync::mpsc::Sender<()>>,
+    aec_delay_rx: Option<tokio::sync::watch::Receiver<Option<f32>>>,
     session_state_tx: Option<tokio::sync::watch::Sender<State>>,
     tasks: Option<JoinSet<()>>,
+    listen_task: Option<tokio::task::JoinHandle<crate::upstream::UpstreamReport>>,
+    recording_tasks: Vec<tokio::task::JoinHandle<()>>,
 }
 
 impl Session {
-    pub fn new(app: tauri::AppHandle) -> Self {
-        let mic_device_name = hypr_audio::AudioInput::get_default_mic_device_name();
-
+    /// Sends on `stop_tx` once the upstream is done with the session, which should then be stopped.
+    pub fn new(
+        host: crate::pipeline::Host,
+        stop_tx: tokio::sync::mpsc::UnboundedSender<()>,
+    ) -> Self {
         Self {
-            app,
+            host,
+            stop_tx,
             session_id: None,
-            mic_device_name: Some(mic_device_name),
+            mic_device_name: None,
+            mic_device_tx: None,
             mic_muted_tx: None,
             mic_muted_rx: None,
             speaker_muted_tx: None,
             speaker_muted_rx: None,
-            silence_stream_tx: None,
+            aec_delay_rx: None,
             tasks: None,
+            listen_task: None,
+            recording_tasks: vec![],
             session_state_tx: None,
         }
     }
 
     #[tracing::instrument(skip_all)]
     async fn setup_resources(&mut self, id: impl Into<String>) -> Result<(), crate::Error> {
-        use tauri_plugin_db::DatabasePluginExt;
-
         let session_id = id.into();
-        let onboarding_session_id = self.app.db_onboarding_session_id().await?;
-
-        let user_id = self.app.db_user_id().await?.unwrap();
         self.session_id = Some(session_id.clone());
 
-        let (record, languages, jargons, redemption_time_ms) = {
-            let config = self.app.db_get_config(&user_id).await?;
-
-            let record = config
-                .as_ref()
-                .is_none_or(|c| c.general.save_recordings.unwrap_or(true));
-
-            let languages = config.as_ref().map_or_else(
-                || vec![hypr_language::ISO639::En.into()],
-                |c| c.general.spoken_languages.clone(),
-            );
-
-            let jargons = config
-                .as_ref()
-                .map_or_else(Vec::new, |c| c.general.jargons.clone());
-
-            let redemption_time_ms = config
-                .as_ref()
-                .map_or_else(|| 500, |c| c.ai.redemption_time_ms.unwrap_or(500));
-
-            (record, languages, jargons, redemption_time_ms)
-        };
-
-        let session = self
-            .app
-            .db_get_session(&session_id)
-            .await?
-            .ok_or(crate::Error::NoneSession)?;
-
+        let crate::pipeline::SessionConfig {
+            record,
+            multitrack,
+            recording_format,
+            mic_processing,
+            speaker_processing,
+            listen_params,
+            session_dir,
+        } = self.host.store.config(&session_id).await?;
+
+        let (mic_device_tx, mic_device_rx) =
+            tokio::sync::watch::channel(self.mic_device_name.clone());
+        let (mic_switched_tx, mut mic_switched_rx) = tokio::sync::watch::channel(());
         let (mic_muted_tx, mic_muted_rx_main) = tokio::sync::watch::channel(false);
         let (speaker_muted_tx, speaker_muted_rx_main) = tokio::sync::watch::channel(false);
+        let (aec_delay_tx, aec_delay_rx) = tokio::sync::watch::channel(None);
         let (session_state_tx, session_state_rx) =
             tokio::sync::watch::channel(State::RunningActive {});
 
         let (stop_tx, mut stop_rx) = tokio::sync::mpsc::channel::<()>(1);
 
+        self.mic_device_tx = Some(mic_device_tx);
         self.mic_muted_tx = Some(mic_muted_tx);
         self.mic_muted_rx = Some(mic_muted_rx_main.clone());
         self.speaker_muted_tx = Some(speaker_muted_tx);
         self.speaker_muted_rx = Some(speaker_muted_rx_main.clone());
+        self.aec_delay_rx = Some(aec_delay_rx);
         self.session_state_tx = Some(session_state_tx);
 
-        let listen_client = setup_listen_client(
-            &self.app,
-            languages,
-            jargons,
-            session_id == onboarding_session_id,
-            redemption_time_ms,
-        )
-        .await?;
-
-        let mic_sample_stream = {
-            let mut input = hypr_audio::AudioInput::from_mic(self.mic_device_name.clone())?;
-            input.stream()
+        let stt_connection = self.host.stt.connection().await?;
+
+        let mic_device = match self.mic_device_name.clone() {
+            Some(device) => Some(device),
+            None => self.host.sources.default_mic_device().await,
         };
-        let mic_stream = mic_sample_stream
-            .resample(SAMPLE_RATE)
-            .chunks(hypr_aec::BLOCK_SIZE);
+        let mic_stream = self.host.sources.open_mic(mic_device.clone()).await?;
 
         // https://github.com/fastrepl/hyprnote/commit/7c8cf1c
         tokio::time::sleep(Duration::from_millis(65)).await;
         // We need some delay here for Airpod transition.
-        // But if the delay is too long, AEC will not work.
-
-        let speaker_sample_stream = hypr_audio::AudioInput::from_speaker().stream();
-        let speaker_stream = speaker_sample_stream
-            .resample(SAMPLE_RATE)
-            .chunks(hypr_aec::BLOCK_SIZE);
+        // The remaining offset between mic and speaker is measured and compensated before AEC.
 
-        let channels = AudioChannels::new();
+        let speaker_stream = self.host.sources.open_speaker().await?;
 
-        {
-            let silence_stream_tx = hypr_audio::AudioOutput::silence();
-            self.silence_stream_tx = Some(silence_stream_tx);
-        }
+        let channels = AudioChannels::new(multitrack);
 
         let mut tasks = JoinSet::new();
 
         tasks.spawn(AudioChannels::process_mic_stream(
+            self.host.sources.clone(),
             mic_stream,
+            mic_device,
+            mic_device_rx,
             mic_muted_rx_main.clone(),
             channels.mic_tx.clone(),
+            mic_switched_tx,
         ));
 
         tasks.spawn(AudioChannels::process_speaker_stream(
@@ -297,45 +453,78 @@ impl Session {
             channels.speaker_tx.clone(),
         ));
 
-        let app_dir = self.app.path().app_data_dir().unwrap();
-
         tasks.spawn({
-            let app = self.app.clone();
+            let events = self.host.events.clone();
             let mic_rx = channels.mic_rx.clone();
             let speaker_rx = channels.speaker_rx.clone();
             let save_mixed_tx = channels.save_mixed_tx.clone();
+            let save_multitrack_tx = channels.save_multitrack_tx.clone();
             let save_mic_raw_tx = channels.save_mic_raw_tx.clone();
             let save_speaker_raw_tx = channels.save_speaker_raw_tx.clone();
             let process_mic_tx = channels.process_mic_tx.clone();
             let process_speaker_tx = channels.process_speaker_tx.clone();
+            let activity_tx = channels.activity_tx.clone();
+
+            let mut mic_chain = mic_processing.build(SAMPLE_RATE)?;
+            let mut speaker_chain = speaker_processing.build(SAMPLE_RATE)?;
 
             async move {
                 let mut aec = hypr_aec::AEC::new().unwrap();
+                let mut drift_compensator = hypr_audio::DriftCompensator::new();
+                let mut delay_compensator = hypr_aec::DelayCompensator::new(SAMPLE_RATE);
                 let mut last_broadcast = Instant::now();
 
-                // TODO: AGC might be needed.
+                // Tuned for the AEC model. Everything after it is up to the processing chains.
                 const PRE_MIC_GAIN: f32 = 1.0;
                 const PRE_SPEAKER_GAIN: f32 = 0.8;
-                const POST_MIC_GAIN: f32 = 1.5;
-                const POST_SPEAKER_GAIN: f32 = 1.0;
 
                 loop {
-                    let (mic_chunk_raw, speaker_chunk): (Vec<f32>, Vec<f32>) =
-                        match tokio::join!(mic_rx.recv_async(), speaker_rx.recv_async()) {
-                            (Ok(mic), Ok(speaker)) => (
+                    // A new mic comes with its own clock and latency.
+                    if mic_switched_rx.has_changed().unwrap_or(false) {
+                        mic_switched_rx.borrow_and_update();
+                        drift_compensator = hypr_audio::DriftCompensator::new();
+                        delay_compensator = hypr_aec::DelayCompensator::new(SAMPLE_RATE);
+                    }
+
+                    // The devices run on independent clocks, so chunks are not paired one-to-one.
+                    let (mic_chunk_raw, speaker_chunk_raw): (Vec<f32>, Vec<f32>) =
+                        match drift_compensator.pop(hypr_aec::BLOCK_SIZE) {
+                            Some((mic, speaker)) => (
                                 mic.iter().map(|x| *x * PRE_MIC_GAIN).collect(),
                                 speaker.iter().map(|x| *x * PRE_SPEAKER_GAIN).collect(),
                             ),
-                            _ => break,
+                            None => {
+                                tokio::select! {
+                                    mic = mic_rx.recv_async() => match mic {
+                                        Ok(mic) => drift_compensator.push_mic(&mic),
+                                        Err(_) => break,
+                                    },
+                                    speaker = speaker_rx.recv_async() => match speaker {
+                                        Ok(speaker) => drift_compensator.push_reference(&speaker),
+                                        Err(_) => break,
+                                    },
+                                }
+                                continue;
+                            }
                         };
 
-                    let maybe_mic_chunk = aec.process_streaming(&mic_chunk_raw, &speaker_chunk);
+                    // Raw chunks are saved before delay compensation. Only what goes through AEC and after is shifted.
+                    let (mic_chunk_aligned, speaker_chunk) =
+                        delay_compensator.process(&mic_chunk_raw, &speaker_chunk_raw);
+
+                    let delay_ms = delay_compensator.delay_ms();
+                    if *aec_delay_tx.borrow() != delay_ms {
+                        tracing::info!(delay_ms = ?delay_ms, "aec_delay_changed");
+                        let _ = aec_delay_tx.send(delay_ms);
+                    }
+
+                    let maybe_mic_chunk = aec.process_streaming(&mic_chunk_aligned, &speaker_chunk);
 
                     let mic_chunk = match maybe_mic_chunk {
                         Ok(mic_chunk) => mic_chunk,
                         Err(e) => {
                             tracing::error!("aec_error: {:?}", e);
-                            mic_chunk_raw.clone()
+                            mic_chunk_aligned
                         }
                     };
 
@@ -345,19 +534,14 @@ impl Session {
                         continue;
                     }
 
-                    let processed_mic: Vec<f32> =
-                        mic_chunk.iter().map(|x| x * POST_MIC_GAIN).collect();
-                    let processed_speaker: Vec<f32> = speaker_chunk
-                        .iter()
-                        .map(|x| x * POST_SPEAKER_GAIN)
-                        .collect();
+                    let mut processed_mic = mic_chunk.clone();
+                    mic_chain.process(&mut processed_mic);
+                    let mut processed_speaker = speaker_chunk.clone();
+                    speaker_chain.process(&mut processed_speaker);
 
                     let now = Instant::now();
                     if now.duration_since(last_broadcast) >= AUDIO_AMPLITUDE_THROTTLE {
-                        if let Err(e) = SessionEvent::from((&mic_chunk, &speaker_chunk)).emit(&app)
-                        {
-                            tracing::error!("broadcast_error: {:?}", e);
-                        }
+                        events.emit(SessionEvent::from((&mic_chunk, &speaker_chunk)));
                         last_broadcast = now;
                     }
 
@@ -365,7 +549,39 @@ impl Session {
                         let _ = tx.send_async(mic_chunk_raw.clone()).await;
                     }
                     if let Some(ref tx) = save_speaker_raw_tx {
-                        let _ = tx.send_async(speaker_chunk.clone()).await;
+                        let _ = tx.send_async(speaker_chunk_raw.clone()).await;
+                    }
+
+                    if record {
+                        let mixed: Vec<f32> = processed_mic
+                            .iter()
+                            .zip(processed_speaker.iter())
+                            .map(|(mic, speaker)| (mic + speaker).clamp(-1.0, 1.0))
+                            .collect();
+                        if save_mixed_tx.send_async(mixed).await.is_err() {
+                            tracing::error!("save_mixed_tx_send_error");
+                        }
+                    }
+
+                    if let Some(ref tx) = save_multitrack_tx {
+                        let interleaved: Vec<f32> = processed_mic
+                            .iter()
+                            .zip(processed_speaker.iter())
+                            .flat_map(|(mic, speaker)| {
+                                [mic.clamp(-1.0, 1.0), speaker.clamp(-1.0, 1.0)]
+                            })
+                            .collect();
+                        if tx.send_async(interleaved).await.is_err() {
+                            tracing::error!("save_multitrack_tx_send_error");
+                        }
+                    }
+
+                    if activity_tx
+                        .send_async((processed_mic.clone(), processed_speaker.clone()))
+                        .await
+                        .is_err()
+                    {
+                        tracing::error!("activity_tx_send_error");
                     }
 
                     if let Err(_) = process_mic_tx.send_async(processed_mic).await {
@@ -376,60 +592,56 @@ impl Session {
                         tracing::error!("process_speaker_tx_send_error");
                         return;
                     }
-
-                    if record {
-                        let mixed: Vec<f32> = mic_chunk
-                            .iter()
-                            .zip(speaker_chunk.iter())
-                            .map(|(mic, speaker)| {
-                                (mic * POST_MIC_GAIN + speaker * POST_SPEAKER_GAIN).clamp(-1.0, 1.0)
-                            })
-                            .collect();
-                        if save_mixed_tx.send_async(mixed).await.is_err() {
-                            tracing::error!("save_mixed_tx_send_error");
-                        }
-                    }
                 }
             }
         });
 
+        // Not part of `tasks`, so the last chunk can be finalized once the audio senders are gone.
+        let mut recording_tasks = vec![];
+
         if record {
-            tasks.spawn({
-                let app_dir = app_dir.clone();
-                let session_id = session_id.clone();
-                let save_mixed_rx = channels.save_mixed_rx.clone();
+            let session_dir = session_dir.clone();
+            let save_mixed_rx = channels.save_mixed_rx.clone();
+
+            recording_tasks.push(tokio::spawn(async move {
+                if let Err(e) =
+                    AudioSaver::save_chunked(save_mixed_rx, session_dir, 1, recording_format).await
+                {
+                    tracing::error!("failed_to_save_mixed_audio: {:?}", e);
+                }
+            }));
+        }
 
-                async move {
-                    if let Err(e) = AudioSaver::save_to_wav(
-                        save_mixed_rx,
-                        &session_id,
-                        &app_dir,
-                        "audio.wav",
-                        true,
-                    )
-                    .await
-                    {
-                        tracing::error!("failed_to_save_mixed_audio: {:?}", e);
-                    }
+        // Like the recording, the speech still going on is closed once the audio senders are gone.
+        recording_tasks.push(tokio::spawn(track_speech(
+            self.host.store.clone(),
+            session_id.clone(),
+            channels.activity_rx.clone(),
+        )));
+
+        if let Some(save_multitrack_rx) = channels.save_multitrack_rx.clone() {
+            let multitrack_dir = hypr_recorder::multitrack_dir(&session_dir);
+
+            recording_tasks.push(tokio::spawn(async move {
+                if let Err(e) = AudioSaver::save_chunked(
+                    save_multitrack_rx,
+                    multitrack_dir,
+                    2,
+                    recording_format,
+                )
+                .await
+                {
+                    tracing::error!("failed_to_save_multitrack_audio: {:?}", e);
                 }
-            });
+            }));
         }
 
         if let Some(save_mic_raw_rx) = channels.save_mic_raw_rx.clone() {
             tasks.spawn({
-                let session_id = session_id.clone();
-                let app_dir = app_dir.clone();
+                let path = session_dir.join("audio_mic.wav");
 
                 async move {
-                    if let Err(e) = AudioSaver::save_to_wav(
-                        save_mic_raw_rx,
-                        &session_id,
-                        &app_dir,
-                        "audio_mic.wav",
-                        false,
-                    )
-                    .await
-                    {
+                    if let Err(e) = AudioSaver::save_to_wav(save_mic_raw_rx, path).await {
                         tracing::error!("failed_to_save_raw_mic_audio: {:?}", e);
                     }
                 }
@@ -438,93 +650,64 @@ impl Session {
 
         if let Some(save_speaker_raw_rx) = channels.save_speaker_raw_rx.clone() {
             tasks.spawn({
-                let session_id = session_id.clone();
-                let app_dir = app_dir.clone();
+                let path = session_dir.join("audio_speaker.wav");
 
                 async move {
-                    if let Err(e) = AudioSaver::save_to_wav(
-                        save_speaker_raw_rx,
-                        &session_id,
-                        &app_dir,
-                        "audio_speaker.wav",
-                        false,
-                    )
-                    .await
-                    {
+                    if let Err(e) = AudioSaver::save_to_wav(save_speaker_raw_rx, path).await {
                         tracing::error!("failed_to_save_raw_speaker_audio: {:?}", e);
                     }
                 }
             });
         }
 
-        let mic_audio_stream = channels
-            .process_mic_rx
-            .into_stream()
-            .map(hypr_audio_utils::f32_to_i16_bytes);
-        let speaker_audio_stream = channels
-            .process_speaker_rx
-            .into_stream()
-            .map(hypr_audio_utils::f32_to_i16_bytes);
-
-        tasks.spawn({
-            let app = self.app.clone();
+        let listen_task = tokio::spawn({
+            let host = self.host.clone();
             let stop_tx = stop_tx.clone();
-            let session = session.clone();
+            let session_id = session_id.clone();
+            let process_mic_rx = channels.process_mic_rx.clone();
+            let process_speaker_rx = channels.process_speaker_rx.clone();
 
             async move {
-                let listen_stream = listen_client
-                    .from_realtime_audio(mic_audio_stream, speaker_audio_stream)
-                    .await
-                    .unwrap();
-
-                futures_util::pin_mut!(listen_stream);
-
-                while let Some(result) = listen_stream.next().await {
-                    let _meta = result.meta.clone();
-
-                    // We don't have to do this, and inefficient. But this is what works at the moment.
-                    {
-                        let updated_words = update_session(&app, &session.id, result.words)
-                            .await
-                            .unwrap();
-
-                        SessionEvent::Words {
-                            words: updated_words,
-                        }
-                        .emit(&app)
-                    }
-                    .unwrap();
-                }
+                let report = crate::upstream::run(
+                    host,
+                    session_id,
+                    stt_connection,
+                    listen_params,
+                    process_mic_rx,
+                    process_speaker_rx,
+                )
+                .await;
 
                 tracing::info!("listen_stream_ended");
                 if stop_tx.send(()).await.is_err() {
                     tracing::warn!("failed_to_send_stop_signal");
                 }
+
+                report
             }
         });
 
-        let app_handle = self.app.clone();
+        // Part of `tasks`, so a listen task ended by teardown does not stop the next session.
+        let session_stop_tx = self.stop_tx.clone();
         tasks.spawn(async move {
             if stop_rx.recv().await.is_some() {
-                if let Some(state) = app_handle.try_state::<crate::SharedState>() {
-                    let mut guard = state.lock().await;
-                    guard.fsm.handle(&crate::fsm::StateEvent::Stop).await;
-                }
+                let _ = session_stop_tx.send(());
             }
         });
 
         self.tasks = Some(tasks);
+        self.listen_task = Some(listen_task);
+        self.recording_tasks = recording_tasks;
 
         Ok(())
     }
 
+    /// Returns what the upstream left for later, unless it had to be aborted.
     #[tracing::instrument(skip_all)]
-    async fn teardown_resources(&mut self) {
+    async fn teardown_resources(&mut self) -> Option<crate::upstream::UpstreamReport> {
         self.session_id = None;
-
-        if let Some(tx) = self.silence_stream_tx.take() {
-            let _ = tx.send(());
-        }
+        self.mic_device_tx = None;
+        self.aec_delay_rx = None;
 
         if let Some(mut tasks) = self.tasks.take() {
             tasks.abort_all();
@@ -532,6 +715,32 @@ impl Session {
                 let _ = res;
             }
         }
+
+        // With every audio sender dropped, the listen client sends `End` and waits for the server to flush.
+        let mut report = None;
+        if let Some(listen_task) = self.listen_task.take() {
+            let abort_handle = listen_task.abort_handle();
+            match tokio::time::timeout(LISTEN_FINALIZE_TIMEOUT, listen_task).await {
+                Ok(res) => report = res.ok(),
+                Err(_) => {
+                    tracing::warn!("listen_finalize_timeout");
+                    abort_handle.abort();
+                }
+            }
+        }
+
+        for recording_task in std::mem::take(&mut self.recording_tasks) {
+            let abort_handle = recording_task.abort_handle();
+            if tokio::time::timeout(RECORDING_FINALIZE_TIMEOUT, recording_task)
+                .await
+                .is_err()
+            {
+                tracing::warn!("recording_finalize_timeout");
+                abort_handle.abort();
+            }
+        }
+
+        report
     }
 
     pub fn is_mic_muted(&self) -> bool {
@@ -548,74 +757,26 @@ impl Session {
         }
     }
 
-    pub fn get_available_mic_devices() -> Vec<String> {
-        hypr_audio::AudioInput::list_mic_devices()
+    pub fn get_aec_delay_ms(&self) -> Option<f32> {
+        self.aec_delay_rx.as_ref().and_then(|rx| *rx.borrow())
     }
 
-    pub fn get_current_mic_device(&self) -> Option<String> {
-        self.mic_device_name.clone()
+    pub fn get_available_mic_devices() -> Vec<String> {
+        hypr_audio::AudioInput::list_mic_devices()
     }
-}
 
-async fn setup_listen_client<R: tauri::Runtime>(
-    app: &tauri::AppHandle<R>,
-    languages: Vec<hypr_language::Language>,
-    _jargons: Vec<String>,
-    is_onboarding: bool,
-    redemption_time_ms: u32,
-) -> Result<owhisper_client::ListenClientDual, crate::Error> {
-    let api_base = {
-        use tauri_plugin_connector::{Connection, ConnectorPluginExt};
-        let conn: Connection = app.get_stt_connection().await?.into();
-        conn.api_base
-    };
-
-    let api_key = {
-        use tauri_plugin_auth::AuthPluginExt;
-        app.get_from_vault(tauri_plugin_auth::VaultKey::RemoteServer)
-            .unwrap_or_default()
-            .unwrap_or_default()
-    };
+    // Doesn't borrow the session, so the lock around it can be let go before waiting on the device.
+    pub fn get_current_mic_device(&self) -> impl std::future::Future<Output = Option<String>> {
+        let selected = self.mic_device_name.clone();
+        let sources = self.host.sources.clone();
 
-    tracing::info!(api_base = ?api_base, api_key = ?api_key, languages = ?languages, "listen_client");
-
-    // Disabled static prompt since it seems to degrade transcription quality.
-    let static_prompt = "".to_string();
-
-    Ok(owhisper_client::ListenClient::builder()
-        .api_base(api_base)
-        .api_key(api_key)
-        .params(owhisper_interface::ListenParams {
-            languages,
-            static_prompt,
-            redemption_time_ms: if is_onboarding {
-                70
-            } else {
-                redemption_time_ms.into()
-            },
-            ..Default::default()
-        })
-        .build_dual())
-}
-
-async fn update_session<R: tauri::Runtime>(
-    app: &tauri::AppHandle<R>,
-    session_id: impl Into<String>,
-    words: Vec<owhisper_interface::Word2>,
-) -> Result<Vec<owhisper_interface::Word2>, crate::Error> {
-    use tauri_plugin_db::DatabasePluginExt;
-
-    // TODO: not ideal. We might want to only do "update" everywhere instead of upserts.
-    // We do this because it is highly likely that the session fetched in the listener is stale (session can be updated on the React side).
-    let mut session = app
-        .db_get_session(session_id)
-        .await?
-        .ok_or(crate::Error::NoneSession)?;
-
-    session.words.extend(words);
-    app.db_upsert_session(session.clone()).await.unwrap();
-
-    Ok(session.words)
+        async move {
+            match selected {
+                Some(device) => Some(device),
+                None => sources.default_mic_device().await,
+            }
+        }
+    }
 }
 
 pub enum StateEvent {
@@ -640,25 +801,26 @@ impl Session {
             StateEvent::MicMuted(muted) => {
                 if let Some(tx) = &self.mic_muted_tx {
                     let _ = tx.send(*muted);
-                    let _ = SessionEvent::MicMuted { value: *muted }.emit(&self.app);
+                    self.host
+                        .events
+                        .emit(SessionEvent::MicMuted { value: *muted });
                 }
                 Handled
             }
             StateEvent::SpeakerMuted(muted) => {
                 if let Some(tx) = &self.speaker_muted_tx {
                     let _ = tx.send(*muted);
-                    let _ = SessionEvent::SpeakerMuted { value: *muted }.emit(&self.app);
+                    self.host
+                        .events
+                        .emit(SessionEvent::SpeakerMuted { value: *muted });
                 }
                 Handled
             }
             StateEvent::MicChange(device_name) => {
                 self.mic_device_name = device_name.clone();
 
-                if self.session_id.is_some() && self.tasks.is_some() {
-                    if let Some(session_id) = self.session_id.clone() {
-                        self.teardown_resources().await;
-                        self.setup_resources(&session_id).await.unwrap();
-                    }
+                if let Some(tx) = &self.mic_device_tx {
+                    tx.send_replace(device_name.clone());
                 }
 
                 Handled
@@ -699,11 +861,7 @@ impl Session {
         }
     }
 
-    #[state(
-        superstate = "common",
-        entry_action = "enter_inactive",
-        exit_action = "exit_inactive"
-    )]
+    #[state(superstate = "common", entry_action = "enter_inactive")]
     async fn inactive(&mut self, event: &StateEvent) -> Response<State> {
         match event {
             StateEvent::Start(id) => match self.setup_resources(id).await {
@@ -723,32 +881,20 @@ impl Session {
 
     #[action]
     async fn enter_inactive(&mut self) {
-        {
-            use tauri_plugin_tray::TrayPluginExt;
-            let _ = self.app.set_start_disabled(false);
-        }
+        let session_id = self.session_id.clone();
 
-        {
-            use tauri_plugin_windows::{HyprWindow, WindowsPluginExt};
-            let _ = self.app.window_hide(HyprWindow::Control);
+        if let Some(session_id) = &session_id {
+            self.host.store.record_ended(session_id).await;
         }
 
-        if let Some(session_id) = &self.session_id {
-            use tauri_plugin_db::DatabasePluginExt;
+        let report = self.teardown_resources().await;
 
-            if let Ok(Some(mut session)) = self.app.db_get_session(session_id).await {
-                session.record_end = Some(chrono::Utc::now());
-                let _ = self.app.db_upsert_session(session).await;
-            }
+        if let Some(session_id) = &session_id {
+            self.host
+                .store
+                .recording_finalized(session_id, report)
+                .await;
         }
-
-        self.teardown_resources().await;
-    }
-
-    #[action]
-    async fn exit_inactive(&mut self) {
-        use tauri_plugin_tray::TrayPluginExt;
-        let _ = self.app.set_start_disabled(true);
     }
 
     #[action]
@@ -759,12 +905,7 @@ impl Session {
         // }
 
         if let Some(session_id) = &self.session_id {
-            use tauri_plugin_db::DatabasePluginExt;
-
-            if let Ok(Some(mut session)) = self.app.db_get_session(session_id).await {
-                session.record_start = Some(chrono::Utc::now());
-                let _ = self.app.db_upsert_session(session).await;
-            }
+            self.host.store.record_started(session_id).await;
         }
     }
 
@@ -773,9 +914,9 @@ impl Session {
         tracing::info!("transitioned from `{:?}` to `{:?}`", source, target);
 
         match target {
-            State::RunningActive {} => SessionEvent::RunningActive {}.emit(&self.app).unwrap(),
-            State::RunningPaused {} => SessionEvent::RunningPaused {}.emit(&self.app).unwrap(),
-            State::Inactive {} => SessionEvent::Inactive {}.emit(&self.app).unwrap(),
+            State::RunningActive {} => self.host.events.emit(SessionEvent::RunningActive {}),
+            State::RunningPaused {} => self.host.events.emit(SessionEvent::RunningPaused {}),
+            State::Inactive {} => self.host.events.emit(SessionEvent::Inactive {}),
         }
 
         if let Some(tx) = &self.session_state_tx {
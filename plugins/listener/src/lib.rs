@@ -7,6 +7,11 @@ mod error;
 mod events;
 mod ext;
 mod fsm;
+mod pipeline;
+mod player;
+mod recovery;
+mod retranscribe;
+mod upstream;
 
 pub use error::*;
 pub use events::*;
@@ -39,13 +44,21 @@ fn make_specta_builder<R: tauri::Runtime>() -> tauri_specta::Builder<R> {
             commands::set_mic_muted::<tauri::Wry>,
             commands::get_speaker_muted::<tauri::Wry>,
             commands::set_speaker_muted::<tauri::Wry>,
+            commands::get_aec_delay_ms::<tauri::Wry>,
             commands::start_session::<tauri::Wry>,
             commands::stop_session::<tauri::Wry>,
             commands::pause_session::<tauri::Wry>,
             commands::resume_session::<tauri::Wry>,
             commands::get_state::<tauri::Wry>,
+            commands::retranscribe_session::<tauri::Wry>,
+            commands::player_open::<tauri::Wry>,
+            commands::player_play::<tauri::Wry>,
+            commands::player_pause::<tauri::Wry>,
+            commands::player_seek::<tauri::Wry>,
+            commands::player_set_rate::<tauri::Wry>,
+            commands::player_close::<tauri::Wry>,
         ])
-        .events(tauri_specta::collect_events![SessionEvent])
+        .events(tauri_specta::collect_events![SessionEvent, PlayerEvent])
         .error_handling(tauri_specta::ErrorHandlingMode::Throw)
 }
 
@@ -58,9 +71,22 @@ pub fn init() -> tauri::plugin::TauriPlugin<tauri::Wry> {
             specta_builder.mount_events(app);
 
             let handle = app.app_handle();
-            let fsm = fsm::Session::new(handle.clone()).state_machine();
+            let (stop_tx, mut stop_rx) = tokio::sync::mpsc::unbounded_channel();
+            let fsm =
+                fsm::Session::new(pipeline::Host::app(handle.clone()), stop_tx).state_machine();
             let state: SharedState = Mutex::new(State { fsm });
             app.manage(state);
+            app.manage(player::SharedPlayer::default());
+
+            let handle = handle.clone();
+            tauri::async_runtime::spawn(async move {
+                while stop_rx.recv().await.is_some() {
+                    let state = handle.state::<SharedState>();
+                    let mut guard = state.lock().await;
+                    guard.fsm.handle(&fsm::StateEvent::Stop).await;
+                }
+            });
+
             Ok(())
         })
         .build()
@@ -0,0 +1,410 @@
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use tauri::Manager;
+use tauri_specta::Event;
+
+use hypr_audio::AsyncSource;
+use owhisper_interface::{ListenParams, Word2};
+use tauri_plugin_connector::ConnectionSTT;
+
+use super::{
+    AudioSources, AudioStream, EventEmitter, Host, SessionConfig, SessionStore, SttProvider,
+    TranscriptSink,
+};
+use crate::fsm::SAMPLE_RATE;
+use crate::upstream::UpstreamReport;
+use crate::SessionEvent;
+
+impl Host {
+    pub fn app(app: tauri::AppHandle) -> Self {
+        let backend = Arc::new(AppBackend { app });
+
+        Self {
+            sources: Arc::new(DeviceSources),
+            store: backend.clone(),
+            stt: backend.clone(),
+            transcripts: backend.clone(),
+            events: backend,
+        }
+    }
+}
+
+pub struct DeviceSources;
+
+// Stops the silent output once the speaker stream is dropped.
+struct Silence(std::sync::mpsc::Sender<()>);
+
+impl Drop for Silence {
+    fn drop(&mut self) {
+        let _ = self.0.send(());
+    }
+}
+
+impl AudioSources for DeviceSources {
+    // Enumerating devices blocks, for a noticeable while on some systems.
+    fn default_mic_device(&self) -> BoxFuture<'_, Option<String>> {
+        Box::pin(async move {
+            tokio::task::spawn_blocking(hypr_audio::AudioInput::get_default_mic_device_name)
+                .await
+                .ok()
+        })
+    }
+
+    fn open_mic(
+        &self,
+        device_name: Option<String>,
+    ) -> BoxFuture<'_, Result<AudioStream, crate::Error>> {
+        Box::pin(async move {
+            let mic_sample_stream = {
+                let mut input = hypr_audio::AudioInput::from_mic(device_name)?;
+                input.stream()
+            };
+
+            Ok(mic_sample_stream
+                .resample(SAMPLE_RATE)
+                .chunks(hypr_aec::BLOCK_SIZE)
+                .boxed())
+        })
+    }
+
+    fn open_speaker(&self) -> BoxFuture<'_, Result<AudioStream, crate::Error>> {
+        Box::pin(async move {
+            let speaker_sample_stream = hypr_audio::SpeakerInput::new()
+                .and_then(|input| input.stream())
+                .map_err(|e| crate::Error::SpeakerUnavailable(e.to_string()))?;
+            let silence = Silence(hypr_audio::AudioOutput::silence());
+
+            Ok(speaker_sample_stream
+                .resample(SAMPLE_RATE)
+                .chunks(hypr_aec::BLOCK_SIZE)
+                .map(move |chunk| {
+                    let _ = &silence;
+                    chunk
+                })
+                .boxed())
+        })
+    }
+}
+
+pub struct AppBackend {
+    app: tauri::AppHandle,
+}
+
+impl SessionStore for AppBackend {
+    fn config(&self, session_id: &str) -> BoxFuture<'_, Result<SessionConfig, crate::Error>> {
+        use tauri_plugin_db::DatabasePluginExt;
+
+        let session_id = session_id.to_string();
+
+        Box::pin(async move {
+            let onboarding_session_id = self.app.db_onboarding_session_id().await?;
+            let user_id = self.app.db_user_id().await?.unwrap();
+            let config = self.app.db_get_config(&user_id).await?;
+
+            self.app
+                .db_get_session(&session_id)
+                .await?
+                .ok_or(crate::Error::NoneSession)?;
+
+            let record = config
+                .as_ref()
+                .is_none_or(|c| c.general.save_recordings.unwrap_or(true));
+
+            let multitrack = record
+                && config
+                    .as_ref()
+                    .is_some_and(|c| c.general.save_multitrack.unwrap_or(false));
+
+            // Lossy compression is opt-in. Recordings were always WAV before.
+            let recording_format = match config
+                .as_ref()
+                .and_then(|c| c.general.recording_format.clone())
+            {
+                Some(hypr_db_user::RecordingFormat::Opus) => hypr_recorder::Format::Opus,
+                Some(hypr_db_user::RecordingFormat::Flac) => hypr_recorder::Format::Flac,
+                Some(hypr_db_user::RecordingFormat::Wav) | None => hypr_recorder::Format::Wav,
+            };
+
+            let (mic_processing, speaker_processing) = processing_chains(
+                config
+                    .as_ref()
+                    .and_then(|c| c.general.audio_processing.as_ref()),
+            );
+
+            let languages = config.as_ref().map_or_else(
+                || vec![hypr_language::ISO639::En.into()],
+                |c| c.general.spoken_languages.clone(),
+            );
+
+            let jargons = config
+                .as_ref()
+                .map_or_else(Vec::new, |c| c.general.jargons.clone());
+
+            let redemption_time_ms = config
+                .as_ref()
+                .map_or_else(|| 500, |c| c.ai.redemption_time_ms.unwrap_or(500));
+
+            let listen_params = listen_params(
+                languages,
+                jargons,
+                session_id == onboarding_session_id,
+                redemption_time_ms,
+                config.as_ref().and_then(|c| c.ai.vad.as_ref()),
+            );
+
+            let session_dir = self.app.path().app_data_dir().unwrap().join(&session_id);
+
+            Ok(SessionConfig {
+                record,
+                multitrack,
+                recording_format,
+                mic_processing,
+                speaker_processing,
+                listen_params,
+                session_dir,
+            })
+        })
+    }
+
+    fn record_started(&self, session_id: &str) -> BoxFuture<'_, ()> {
+        use tauri_plugin_db::DatabasePluginExt;
+
+        let session_id = session_id.to_string();
+
+        Box::pin(async move {
+            if let Ok(Some(mut session)) = self.app.db_get_session(&session_id).await {
+                session.record_start = Some(chrono::Utc::now());
+                let _ = self.app.db_upsert_session(session).await;
+            }
+        })
+    }
+
+    fn record_ended(&self, session_id: &str) -> BoxFuture<'_, ()> {
+        use tauri_plugin_db::DatabasePluginExt;
+
+        let session_id = session_id.to_string();
+
+        Box::pin(async move {
+            if let Ok(Some(mut session)) = self.app.db_get_session(&session_id).await {
+                session.record_end = Some(chrono::Utc::now());
+                let _ = self.app.db_upsert_session(session).await;
+            }
+        })
+    }
+
+    fn recording_finalized(
+        &self,
+        session_id: &str,
+        report: Option<UpstreamReport>,
+    ) -> BoxFuture<'_, ()> {
+        use tauri_plugin_connector::ConnectorPluginExt;
+
+        let session_id = session_id.to_string();
+
+        Box::pin(async move {
+            let session_dir = self.app.path().app_data_dir().unwrap().join(&session_id);
+
+            let lost = report.as_ref().map(|r| r.lost.clone());
+            if let Err(e) =
+                crate::recovery::schedule(&self.app, &session_id, session_dir, lost).await
+            {
+                tracing::error!("recovery_schedule_failed: {:?}", e);
+            }
+
+            let Some(report) = report.filter(|r| !r.fallback.is_empty()) else {
+                return;
+            };
+
+            // The cloud was back before the session ended, so it should transcribe what the local fallback did.
+            let app = self.app.clone();
+            tauri::async_runtime::spawn(async move {
+                let conn = match app.get_stt_connection().await {
+                    Ok(conn @ ConnectionSTT::HyprCloud(_)) => conn,
+                    _ => return,
+                };
+
+                for span in report.fallback {
+                    if let Err(e) =
+                        crate::retranscribe::run_span(&app, &session_id, &conn, span.clone()).await
+                    {
+                        tracing::error!(span = ?span, "fallback_retranscribe_failed: {:?}", e);
+                    }
+                }
+            });
+        })
+    }
+
+    fn speech_segments(&self, segments: Vec<hypr_db_user::SpeechSegment>) -> BoxFuture<'_, ()> {
+        use tauri_plugin_db::DatabasePluginExt;
+
+        Box::pin(async move {
+            if let Err(e) = self.app.db_insert_speech_segments(segments).await {
+                tracing::error!("speech_segments_insert_failed: {:?}", e);
+            }
+        })
+    }
+}
+
+impl SttProvider for AppBackend {
+    fn connection(&self) -> BoxFuture<'_, Result<ConnectionSTT, crate::Error>> {
+        use tauri_plugin_connector::ConnectorPluginExt;
+
+        Box::pin(async move { Ok(self.app.get_stt_connection().await?) })
+    }
+
+    fn fallback(&self) -> BoxFuture<'_, Result<ConnectionSTT, crate::Error>> {
+        use tauri_plugin_connector::ConnectorPluginExt;
+
+        Box::pin(async move { Ok(self.app.get_local_stt_connection().await?) })
+    }
+
+    fn listen_client(
+        &self,
+        conn: &ConnectionSTT,
+        params: ListenParams,
+    ) -> owhisper_client::ListenClientDual {
+        build_listen_client(&self.app, conn, params)
+    }
+}
+
+impl TranscriptSink for AppBackend {
+    fn append(
+        &self,
+        session_id: &str,
+        words: Vec<Word2>,
+    ) -> BoxFuture<'_, Result<Vec<Word2>, crate::Error>> {
+        use tauri_plugin_db::DatabasePluginExt;
+
+        let session_id = session_id.to_string();
+
+        Box::pin(async move {
+            // TODO: not ideal. We might want to only do "update" everywhere instead of upserts.
+            // We do this because it is highly likely that the session fetched in the listener is stale (session can be updated on the React side).
+            let mut session = self
+                .app
+                .db_get_session(session_id)
+                .await?
+                .ok_or(crate::Error::NoneSession)?;
+
+            session.words.extend(words);
+            self.app.db_upsert_session(session.clone()).await?;
+
+            Ok(session.words)
+        })
+    }
+}
+
+impl EventEmitter for AppBackend {
+    fn emit(&self, event: SessionEvent) {
+        // The tray and the control window follow the state of the session.
+        match event {
+            SessionEvent::Inactive {} => {
+                use tauri_plugin_tray::TrayPluginExt;
+                use tauri_plugin_windows::{HyprWindow, WindowsPluginExt};
+
+                let _ = self.app.set_start_disabled(false);
+                let _ = self.app.window_hide(HyprWindow::Control);
+            }
+            SessionEvent::RunningActive {} | SessionEvent::RunningPaused {} => {
+                use tauri_plugin_tray::TrayPluginExt;
+                let _ = self.app.set_start_disabled(true);
+            }
+            _ => {}
+        }
+
+        if let Err(e) = event.emit(&self.app) {
+            tracing::error!("broadcast_error: {:?}", e);
+        }
+    }
+}
+
+fn listen_params(
+    languages: Vec<hypr_language::Language>,
+    _jargons: Vec<String>,
+    is_onboarding: bool,
+    redemption_time_ms: u32,
+    vad: Option<&hypr_db_user::ConfigVad>,
+) -> ListenParams {
+    let vad = vad.cloned().unwrap_or_default();
+
+    // Disabled static prompt since it seems to degrade transcription quality.
+    let static_prompt = "".to_string();
+
+    ListenParams {
+        languages,
+        static_prompt,
+        redemption_time_ms: if is_onboarding {
+            70
+        } else {
+            redemption_time_ms.into()
+        },
+        vad_threshold: vad.threshold,
+        vad_pre_speech_pad_ms: vad.pre_speech_pad_ms.map(Into::into),
+        vad_post_speech_pad_ms: vad.post_speech_pad_ms.map(Into::into),
+        vad_min_speech_ms: vad.min_speech_ms.map(Into::into),
+        vad_max_speech_ms: vad.max_speech_ms.map(Into::into),
+        ..Default::default()
+    }
+}
+
+fn processing_chains(
+    config: Option<&hypr_db_user::ConfigAudioProcessing>,
+) -> (hypr_audio::dsp::ChainConfig, hypr_audio::dsp::ChainConfig) {
+    use hypr_audio::dsp::{ChainConfig, GainControl};
+    use hypr_db_user::GainControlMode;
+
+    // Audio passes through untouched unless processing is turned on in the settings.
+    let default = hypr_db_user::ConfigAudioProcessing::default();
+    let config = config.unwrap_or(&default);
+
+    let gain_control = match config.gain_control.clone().unwrap_or(GainControlMode::Off) {
+        GainControlMode::Off => GainControl::Off,
+        GainControlMode::Agc => GainControl::Agc {
+            target_db: -20.0,
+            max_gain_db: 30.0,
+        },
+        GainControlMode::Loudness => GainControl::Loudness { target_lufs: -23.0 },
+    };
+
+    let mic = ChainConfig {
+        gain: config.mic_gain.unwrap_or(1.0),
+        noise_suppression_db: config.noise_suppression.unwrap_or(false).then_some(12.0),
+        gain_control,
+        ..Default::default()
+    };
+
+    // System audio is already clean, and usually mastered.
+    let speaker = ChainConfig {
+        gain: config.speaker_gain.unwrap_or(1.0),
+        noise_suppression_db: None,
+        gain_control,
+        ..Default::default()
+    };
+
+    (mic, speaker)
+}
+
+pub(crate) fn build_listen_client<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    conn: &ConnectionSTT,
+    params: ListenParams,
+) -> owhisper_client::ListenClientDual {
+    let api_base = conn.as_ref().api_base.clone();
+
+    let api_key = {
+        use tauri_plugin_auth::AuthPluginExt;
+        app.get_from_vault(tauri_plugin_auth::VaultKey::RemoteServer)
+            .unwrap_or_default()
+            .unwrap_or_default()
+    };
+
+    tracing::info!(api_base = ?api_base, api_key = ?api_key, languages = ?params.languages, "listen_client");
+
+    owhisper_client::ListenClient::builder()
+        .api_base(api_base)
+        .api_key(api_key)
+        .params(params)
+        .build_dual()
+}
@@ -0,0 +1,398 @@
+// cargo test -p tauri-plugin-listener test_session -- --nocapture
+// Runs against the local Whisper model, and is skipped where it isn't downloaded.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use statig::awaitable::IntoStateMachineExt;
+
+use hypr_audio::{FileSource, Pacing};
+use tauri_plugin_connector::Connection;
+
+use super::*;
+use crate::fsm::{Session, State, StateEvent};
+
+const SAMPLE_RATE: usize = 16000;
+const FIXTURE_SECONDS: usize = 20;
+// The far end leaks back into the mic, as it would from laptop speakers.
+const ECHO_DELAY: usize = 1600;
+const ECHO_GAIN: f32 = 0.3;
+// A mic that stops partway, like a USB mic being pulled out.
+const UNPLUGGED_MIC: &str = "fixture-unplugged";
+const UNPLUGGED_AFTER_SECONDS: usize = 2;
+
+fn samples(pcm: &[u8]) -> Vec<f32> {
+    pcm.chunks_exact(2)
+        .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / 32768.0)
+        .take(SAMPLE_RATE * FIXTURE_SECONDS)
+        .collect()
+}
+
+fn pcm(samples: &[f32]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|s| ((s.clamp(-1.0, 1.0) * 32767.0) as i16).to_le_bytes())
+        .collect()
+}
+
+// Mic and speaker recordings of the same call.
+fn fixtures() -> (Vec<u8>, Vec<u8>) {
+    let near = samples(hypr_data::english_1::AUDIO);
+    let far = samples(hypr_data::english_2::AUDIO);
+
+    let mic = near
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| {
+            let echo = i
+                .checked_sub(ECHO_DELAY)
+                .and_then(|j| far.get(j))
+                .copied()
+                .unwrap_or_default();
+            sample + echo * ECHO_GAIN
+        })
+        .collect::<Vec<_>>();
+
+    (pcm(&mic), pcm(&far))
+}
+
+struct FixtureSources {
+    default_mic: Mutex<String>,
+    opened: Mutex<Vec<Option<String>>>,
+}
+
+impl FixtureSources {
+    fn new() -> Self {
+        Self {
+            default_mic: Mutex::new("fixture".to_string()),
+            opened: Mutex::new(vec![]),
+        }
+    }
+
+    fn stream(pcm: Vec<u8>) -> AudioStream {
+        FileSource::from_pcm_s16le(pcm, SAMPLE_RATE as u32, Pacing::Realtime)
+            .stream()
+            .chunks(hypr_aec::BLOCK_SIZE)
+            .boxed()
+    }
+}
+
+impl AudioSources for FixtureSources {
+    fn default_mic_device(&self) -> BoxFuture<'_, Option<String>> {
+        let device = self.default_mic.lock().unwrap().clone();
+        Box::pin(async move { Some(device) })
+    }
+
+    // Every mic replays its side of the call from the start.
+    fn open_mic(
+        &self,
+        device_name: Option<String>,
+    ) -> BoxFuture<'_, Result<AudioStream, crate::Error>> {
+        let mut pcm = fixtures().0;
+        if device_name.as_deref() == Some(UNPLUGGED_MIC) {
+            pcm.truncate(SAMPLE_RATE * UNPLUGGED_AFTER_SECONDS * 2);
+        }
+
+        self.opened.lock().unwrap().push(device_name);
+        Box::pin(async { Ok(Self::stream(pcm)) })
+    }
+
+    fn open_speaker(&self) -> BoxFuture<'_, Result<AudioStream, crate::Error>> {
+        Box::pin(async { Ok(Self::stream(fixtures().1)) })
+    }
+}
+
+struct MemoryStore {
+    dir: tempfile::TempDir,
+    log: Mutex<Vec<&'static str>>,
+    segments: Mutex<Vec<hypr_db_user::SpeechSegment>>,
+}
+
+impl MemoryStore {
+    fn new() -> Self {
+        Self {
+            dir: tempfile::tempdir().unwrap(),
+            log: Mutex::new(vec![]),
+            segments: Mutex::new(vec![]),
+        }
+    }
+
+    fn session_dir(&self, session_id: &str) -> std::path::PathBuf {
+        self.dir.path().join(session_id)
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn config(&self, session_id: &str) -> BoxFuture<'_, Result<SessionConfig, crate::Error>> {
+        let config = SessionConfig {
+            record: true,
+            multitrack: false,
+            recording_format: hypr_recorder::Format::Wav,
+            mic_processing: Default::default(),
+            speaker_processing: Default::default(),
+            listen_params: ListenParams {
+                languages: vec![hypr_language::ISO639::En.into()],
+                ..Default::default()
+            },
+            session_dir: self.session_dir(session_id),
+        };
+
+        Box::pin(async move { Ok(config) })
+    }
+
+    fn record_started(&self, _session_id: &str) -> BoxFuture<'_, ()> {
+        self.log.lock().unwrap().push("record_started");
+        Box::pin(async {})
+    }
+
+    fn record_ended(&self, _session_id: &str) -> BoxFuture<'_, ()> {
+        self.log.lock().unwrap().push("record_ended");
+        Box::pin(async {})
+    }
+
+    fn recording_finalized(
+        &self,
+        _session_id: &str,
+        _report: Option<UpstreamReport>,
+    ) -> BoxFuture<'_, ()> {
+        self.log.lock().unwrap().push("recording_finalized");
+        Box::pin(async {})
+    }
+
+    fn speech_segments(&self, segments: Vec<hypr_db_user::SpeechSegment>) -> BoxFuture<'_, ()> {
+        self.segments.lock().unwrap().extend(segments);
+        Box::pin(async {})
+    }
+}
+
+// An in-process owhisper server, running the local Whisper model.
+struct LocalStt {
+    api_base: String,
+    clients: AtomicUsize,
+}
+
+impl LocalStt {
+    async fn start() -> Option<Self> {
+        let model_path = dirs::data_dir()?
+            .join("com.hyprnote.dev")
+            .join("stt/ggml-small-q8_0.bin");
+        if !model_path.exists() {
+            return None;
+        }
+
+        let service = hypr_transcribe_whisper_local::TranscribeService::builder()
+            .model_path(model_path)
+            .build();
+        let router = axum::Router::new().route_service("/v1/listen", service);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        Some(Self {
+            api_base: format!("http://{}", addr),
+            clients: AtomicUsize::new(0),
+        })
+    }
+}
+
+impl SttProvider for LocalStt {
+    fn connection(&self) -> BoxFuture<'_, Result<ConnectionSTT, crate::Error>> {
+        let conn = ConnectionSTT::HyprLocal(Connection {
+            api_base: self.api_base.clone(),
+            api_key: None,
+        });
+
+        Box::pin(async move { Ok(conn) })
+    }
+
+    fn fallback(&self) -> BoxFuture<'_, Result<ConnectionSTT, crate::Error>> {
+        Box::pin(async { unreachable!("a local upstream has no fallback") })
+    }
+
+    fn listen_client(
+        &self,
+        conn: &ConnectionSTT,
+        params: ListenParams,
+    ) -> owhisper_client::ListenClientDual {
+        self.clients.fetch_add(1, Ordering::SeqCst);
+
+        owhisper_client::ListenClient::builder()
+            .api_base(conn.as_ref().api_base.clone())
+            .params(params)
+            .build_dual()
+    }
+}
+
+#[derive(Default)]
+struct MemoryTranscripts {
+    words: Mutex<Vec<Word2>>,
+}
+
+impl TranscriptSink for MemoryTranscripts {
+    fn append(
+        &self,
+        _session_id: &str,
+        words: Vec<Word2>,
+    ) -> BoxFuture<'_, Result<Vec<Word2>, crate::Error>> {
+        let mut all = self.words.lock().unwrap();
+        all.extend(words);
+        let all = all.clone();
+
+        Box::pin(async move { Ok(all) })
+    }
+}
+
+#[derive(Default)]
+struct EventLog {
+    events: Mutex<Vec<SessionEvent>>,
+}
+
+impl EventEmitter for EventLog {
+    fn emit(&self, event: SessionEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_session() {
+    let Some(stt) = LocalStt::start().await else {
+        eprintln!("skipping test_session: the local Whisper model is not downloaded");
+        return;
+    };
+
+    let sources = Arc::new(FixtureSources::new());
+    let store = Arc::new(MemoryStore::new());
+    let stt = Arc::new(stt);
+    let transcripts = Arc::new(MemoryTranscripts::default());
+    let events = Arc::new(EventLog::default());
+
+    let host = Host {
+        sources: sources.clone(),
+        store: store.clone(),
+        stt: stt.clone(),
+        transcripts: transcripts.clone(),
+        events: events.clone(),
+    };
+
+    let (stop_tx, mut stop_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut fsm = Session::new(host, stop_tx).state_machine();
+
+    fsm.handle(&StateEvent::Start("session".to_string())).await;
+    assert_eq!(fsm.state(), &State::RunningActive {});
+
+    // Like AirPods connecting, while the session follows the system default.
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    *sources.default_mic.lock().unwrap() = "fixture-2".to_string();
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    fsm.handle(&StateEvent::Pause).await;
+    assert_eq!(fsm.state(), &State::RunningPaused {});
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    fsm.handle(&StateEvent::Resume).await;
+    fsm.handle(&StateEvent::MicMuted(true)).await;
+    fsm.handle(&StateEvent::MicMuted(false)).await;
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    fsm.handle(&StateEvent::MicChange(Some("fixture-3".to_string())))
+        .await;
+    assert_eq!(fsm.state(), &State::RunningActive {});
+
+    // The picked mic is pulled out partway, and the session goes back to the default.
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    fsm.handle(&StateEvent::MicChange(Some(UNPLUGGED_MIC.to_string())))
+        .await;
+    tokio::time::sleep(Duration::from_secs(UNPLUGGED_AFTER_SECONDS as u64 + 1)).await;
+    assert_eq!(fsm.state(), &State::RunningActive {});
+
+    // The speaker runs out, the upstream finishes, and the session asks to be stopped.
+    tokio::time::timeout(Duration::from_secs(60), stop_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    fsm.handle(&StateEvent::Stop).await;
+    assert_eq!(fsm.state(), &State::Inactive {});
+
+    assert_eq!(
+        *sources.opened.lock().unwrap(),
+        [
+            Some("fixture".to_string()),
+            Some("fixture-2".to_string()),
+            Some("fixture-3".to_string()),
+            Some(UNPLUGGED_MIC.to_string()),
+            None,
+        ]
+    );
+    assert_eq!(
+        *store.log.lock().unwrap(),
+        ["record_started", "record_ended", "recording_finalized"]
+    );
+
+    // Switching mics kept the upstream connection.
+    assert_eq!(stt.clients.load(Ordering::SeqCst), 1);
+
+    let words = transcripts.words.lock().unwrap().clone();
+    assert!(!words.is_empty());
+    assert!(words.iter().all(|w| !w.text.trim().is_empty()));
+
+    // Every mic went into the same recording, without gaps.
+    let recorded = hypr_recorder::duration(store.session_dir("session")).unwrap();
+    assert!(recorded > Duration::from_secs(18), "{:?}", recorded);
+
+    // Both sides of the call speak, within the recording.
+    let segments = store.segments.lock().unwrap().clone();
+    for channel in [
+        hypr_db_user::SpeechChannel::Mic,
+        hypr_db_user::SpeechChannel::Speaker,
+    ] {
+        let spans = segments
+            .iter()
+            .filter(|s| s.channel == channel)
+            .map(|s| (s.start_ms, s.end_ms))
+            .collect::<Vec<_>>();
+        assert!(!spans.is_empty(), "{:?}", channel);
+        assert!(spans.windows(2).all(|w| w[0].1 <= w[1].0), "{:?}", spans);
+        assert!(spans
+            .iter()
+            .all(|(start, end)| start < end && *end <= recorded.as_millis() as u64));
+    }
+
+    let events = events.events.lock().unwrap();
+    let states = events
+        .iter()
+        .filter_map(|e| match e {
+            SessionEvent::Inactive {} => Some("inactive"),
+            SessionEvent::RunningActive {} => Some("running_active"),
+            SessionEvent::RunningPaused {} => Some("running_paused"),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        states,
+        [
+            "running_active",
+            "running_paused",
+            "running_active",
+            "inactive"
+        ]
+    );
+
+    let mic_muted = events
+        .iter()
+        .filter_map(|e| match e {
+            SessionEvent::MicMuted { value } => Some(*value),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(mic_muted, [true, false]);
+
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, SessionEvent::AudioAmplitude { .. })));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        SessionEvent::Words { words: all } if all.len() == words.len()
+    )));
+}
@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use futures_util::stream::BoxStream;
+
+use owhisper_interface::{ListenParams, Word2};
+use tauri_plugin_connector::ConnectionSTT;
+
+use crate::upstream::UpstreamReport;
+use crate::SessionEvent;
+
+mod app;
+#[cfg(test)]
+mod harness;
+
+pub use app::*;
+
+/// Audio at `SAMPLE_RATE`, in chunks of `hypr_aec::BLOCK_SIZE`.
+pub type AudioStream = BoxStream<'static, Vec<f32>>;
+
+pub struct SessionConfig {
+    pub record: bool,
+    pub multitrack: bool,
+    pub recording_format: hypr_recorder::Format,
+    pub mic_processing: hypr_audio::dsp::ChainConfig,
+    pub speaker_processing: hypr_audio::dsp::ChainConfig,
+    pub listen_params: ListenParams,
+    pub session_dir: PathBuf,
+}
+
+pub trait AudioSources: Send + Sync {
+    /// The system default, which can change at any time.
+    fn default_mic_device(&self) -> BoxFuture<'_, Option<String>>;
+    fn open_mic(
+        &self,
+        device_name: Option<String>,
+    ) -> BoxFuture<'_, Result<AudioStream, crate::Error>>;
+    fn open_speaker(&self) -> BoxFuture<'_, Result<AudioStream, crate::Error>>;
+}
+
+pub trait SessionStore: Send + Sync {
+    fn config(&self, session_id: &str) -> BoxFuture<'_, Result<SessionConfig, crate::Error>>;
+    fn record_started(&self, session_id: &str) -> BoxFuture<'_, ()>;
+    fn record_ended(&self, session_id: &str) -> BoxFuture<'_, ()>;
+    /// Called once every recording task of the session is done writing, with what the upstream left for later.
+    /// There is no report if the upstream never started, or had to be aborted.
+    fn recording_finalized(
+        &self,
+        session_id: &str,
+        report: Option<UpstreamReport>,
+    ) -> BoxFuture<'_, ()>;
+    /// Speech of the session, as soon as each segment ends.
+    fn speech_segments(&self, segments: Vec<hypr_db_user::SpeechSegment>) -> BoxFuture<'_, ()>;
+}
+
+pub trait SttProvider: Send + Sync {
+    fn connection(&self) -> BoxFuture<'_, Result<ConnectionSTT, crate::Error>>;
+    /// Where to continue once the cloud upstream is gone.
+    fn fallback(&self) -> BoxFuture<'_, Result<ConnectionSTT, crate::Error>>;
+    fn listen_client(
+        &self,
+        conn: &ConnectionSTT,
+        params: ListenParams,
+    ) -> owhisper_client::ListenClientDual;
+}
+
+pub trait TranscriptSink: Send + Sync {
+    /// Adds words to the transcript of the session, and returns all of it.
+    fn append(
+        &self,
+        session_id: &str,
+        words: Vec<Word2>,
+    ) -> BoxFuture<'_, Result<Vec<Word2>, crate::Error>>;
+}
+
+pub trait EventEmitter: Send + Sync {
+    fn emit(&self, event: SessionEvent);
+}
+
+/// Everything the session talks to outside of its own audio processing.
+#[derive(Clone)]
+pub struct Host {
+    pub sources: Arc<dyn AudioSources>,
+    pub store: Arc<dyn SessionStore>,
+    pub stt: Arc<dyn SttProvider>,
+    pub transcripts: Arc<dyn TranscriptSink>,
+    pub events: Arc<dyn EventEmitter>,
+}
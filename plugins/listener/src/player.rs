@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use tauri::Manager;
+use tauri_specta::Event;
+
+use owhisper_interface::Word2;
+
+use crate::PlayerEvent;
+
+pub type SharedPlayer = tokio::sync::Mutex<Option<SessionPlayer>>;
+
+/// Playback of a recorded session. Closing it is dropping it.
+pub struct SessionPlayer {
+    session_id: String,
+    player: hypr_audio::AudioPlayer,
+}
+
+impl SessionPlayer {
+    pub fn open<R: tauri::Runtime>(
+        app: &tauri::AppHandle<R>,
+        session_id: String,
+        words: Vec<Word2>,
+    ) -> Result<Self, crate::Error> {
+        let session_dir = app.path().app_data_dir().unwrap().join(&session_id);
+        let duration = hypr_recorder::duration(&session_dir)?;
+
+        let player = hypr_audio::AudioPlayer::new(duration, move |position| {
+            match hypr_recorder::open_at(&session_dir, position) {
+                Ok(source) => Some(Box::new(source) as hypr_audio::PlayerSource),
+                Err(e) => {
+                    tracing::error!("open_recording_failed: {:?}", e);
+                    None
+                }
+            }
+        })?;
+
+        let mut playback = player.subscribe();
+        let app = app.clone();
+        let id = session_id.clone();
+        tauri::async_runtime::spawn(async move {
+            while playback.changed().await.is_ok() {
+                let p = *playback.borrow_and_update();
+                let position_ms = p.position.as_millis() as u64;
+
+                let event = PlayerEvent {
+                    session_id: id.clone(),
+                    position_ms,
+                    duration_ms: p.duration.as_millis() as u64,
+                    playing: p.playing,
+                    rate: p.rate,
+                    word_index: word_at(&words, position_ms),
+                };
+                if let Err(e) = event.emit(&app) {
+                    tracing::error!("emit_player_event_failed: {:?}", e);
+                }
+            }
+        });
+
+        Ok(Self { session_id, player })
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub fn play(&self) {
+        self.player.play();
+    }
+
+    pub fn pause(&self) {
+        self.player.pause();
+    }
+
+    pub fn seek(&self, position_ms: u64) {
+        self.player.seek(Duration::from_millis(position_ms));
+    }
+
+    pub fn set_rate(&self, rate: f32) {
+        self.player.set_rate(rate);
+    }
+}
+
+/// Index of the word being spoken at `position_ms`. When words overlap, the one that started last wins.
+pub fn word_at(words: &[Word2], position_ms: u64) -> Option<usize> {
+    words
+        .iter()
+        .enumerate()
+        .filter_map(|(i, w)| Some((i, w.start_ms?, w.end_ms?)))
+        .filter(|(_, start, end)| *start <= position_ms && position_ms < *end)
+        .max_by_key(|(_, start, _)| *start)
+        .map(|(i, _, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(start_ms: Option<u64>, end_ms: Option<u64>) -> Word2 {
+        Word2 {
+            text: "word".to_string(),
+            speaker: None,
+            confidence: None,
+            start_ms,
+            end_ms,
+        }
+    }
+
+    #[test]
+    fn test_word_at() {
+        let words = vec![
+            word(Some(0), Some(400)),
+            word(None, None),
+            word(Some(500), Some(900)),
+            word(Some(800), Some(1200)),
+        ];
+
+        assert_eq!(word_at(&words, 0), Some(0));
+        assert_eq!(word_at(&words, 399), Some(0));
+        assert_eq!(word_at(&words, 450), None);
+        assert_eq!(word_at(&words, 600), Some(2));
+        assert_eq!(word_at(&words, 850), Some(3));
+        assert_eq!(word_at(&words, 1200), None);
+    }
+}
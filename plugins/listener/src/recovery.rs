@@ -0,0 +1,248 @@
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use owhisper_interface::Word2;
+
+// Anything shorter is most likely a pause in the conversation, not lost audio.
+const MIN_UNCOVERED: Duration = Duration::from_secs(10);
+
+/// Transcribes the parts of the recording that the upstream lost locally, in the background.
+///
+/// `lost` is what the upstream reported, if it got to report anything. If it failed, or never reported,
+/// the parts of the recording without a transcript are recovered as well.
+pub async fn schedule<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    session_id: &str,
+    session_dir: PathBuf,
+    lost: Option<Vec<Range<u64>>>,
+) -> Result<(), crate::Error> {
+    use tauri_plugin_db::DatabasePluginExt;
+    use tauri_plugin_local_stt::LocalSttPluginExt;
+    use tauri_plugin_task::TaskPluginExt;
+
+    if lost.as_ref().is_some_and(|lost| lost.is_empty()) {
+        return Ok(());
+    }
+
+    let Ok(duration) = hypr_recorder::duration(&session_dir) else {
+        return Ok(());
+    };
+    let duration_ms = duration.as_millis() as u64;
+
+    let session = app
+        .db_get_session(session_id)
+        .await?
+        .ok_or(crate::Error::NoneSession)?;
+
+    let spans = union_spans(
+        clip_spans(lost.unwrap_or_default(), duration_ms),
+        uncovered_spans(&session.words, duration_ms),
+    );
+    if spans.is_empty() {
+        return Ok(());
+    }
+
+    if !app
+        .is_model_downloaded(&app.get_current_model()?)
+        .await
+        .unwrap_or(false)
+    {
+        tracing::warn!(spans = ?spans, "recovery_skipped_no_local_model");
+        return Ok(());
+    }
+
+    tracing::info!(spans = ?spans, "recovery_scheduled");
+
+    let handle = app.clone();
+    let session_id = session_id.to_string();
+
+    app.spawn_task_blocking(move |ctx| {
+        let result = hypr_recorder::open(&session_dir)
+            .map_err(crate::Error::from)
+            .and_then(|audio| {
+                handle
+                    .process_recorded(audio, spans)
+                    .map_err(crate::Error::from)
+            })
+            .and_then(|words| {
+                tauri::async_runtime::block_on(merge_into_session(&handle, &session_id, words))
+            });
+
+        let _ = match result {
+            Ok(_) => ctx.complete(),
+            Err(e) => {
+                tracing::error!("recovery_failed: {:?}", e);
+                ctx.fail(e.to_string())
+            }
+        };
+
+        async {}
+    });
+
+    Ok(())
+}
+
+async fn merge_into_session<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    session_id: &str,
+    words: Vec<Word2>,
+) -> Result<(), crate::Error> {
+    use tauri_plugin_db::DatabasePluginExt;
+
+    if words.is_empty() {
+        return Ok(());
+    }
+
+    let mut session = app
+        .db_get_session(session_id)
+        .await?
+        .ok_or(crate::Error::NoneSession)?;
+
+    session.words = merge_words(std::mem::take(&mut session.words), words);
+    app.db_upsert_session(session).await?;
+
+    Ok(())
+}
+
+/// Keeps what is within the recording, since the upstream does not know where it ends.
+pub fn clip_spans(spans: Vec<Range<u64>>, duration_ms: u64) -> Vec<Range<u64>> {
+    spans
+        .into_iter()
+        .map(|span| span.start..span.end.min(duration_ms))
+        .filter(|span| !span.is_empty())
+        .collect()
+}
+
+/// Gaps of at least `MIN_UNCOVERED` between the transcribed words, including before the first and after the last.
+pub fn uncovered_spans(words: &[Word2], duration_ms: u64) -> Vec<Range<u64>> {
+    let mut covered: Vec<Range<u64>> = words
+        .iter()
+        .filter_map(|w| match (w.start_ms, w.end_ms) {
+            (Some(start), Some(end)) => Some(start..end.max(start)),
+            _ => None,
+        })
+        .collect();
+    covered.sort_by_key(|r| r.start);
+
+    let min_gap = MIN_UNCOVERED.as_millis() as u64;
+    let mut spans = Vec::new();
+    let mut cursor = 0;
+
+    for range in covered
+        .into_iter()
+        .chain(std::iter::once(duration_ms..duration_ms))
+    {
+        let start = range.start.min(duration_ms);
+        if start >= cursor + min_gap {
+            spans.push(cursor..start);
+        }
+        cursor = cursor.max(range.end.min(duration_ms));
+    }
+
+    spans
+}
+
+/// Both sets of spans, with the overlapping and touching ones merged, by start time.
+pub fn union_spans(a: Vec<Range<u64>>, b: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    let mut spans = a.into_iter().chain(b).collect::<Vec<_>>();
+    spans.sort_by_key(|r| r.start);
+
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+            _ => merged.push(span),
+        }
+    }
+
+    merged
+}
+
+/// Inserts `recovered` into `existing` by start time, without reordering `existing`.
+pub fn merge_words(existing: Vec<Word2>, recovered: Vec<Word2>) -> Vec<Word2> {
+    let mut recovered = recovered.into_iter().peekable();
+    let mut merged = Vec::with_capacity(existing.len() + recovered.len());
+
+    for word in existing {
+        if let Some(start) = word.start_ms {
+            while let Some(next) = recovered.next_if(|r| r.start_ms.unwrap_or(0) < start) {
+                merged.push(next);
+            }
+        }
+        merged.push(word);
+    }
+
+    merged.extend(recovered);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(text: &str, start_ms: u64, end_ms: u64) -> Word2 {
+        Word2 {
+            text: text.to_string(),
+            start_ms: Some(start_ms),
+            end_ms: Some(end_ms),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_clip_spans() {
+        assert_eq!(clip_spans(vec![], 60_000), vec![]);
+        assert_eq!(
+            clip_spans(vec![3_000..20_000, 50_000..u64::MAX], 60_000),
+            vec![3_000..20_000, 50_000..60_000]
+        );
+        assert_eq!(
+            clip_spans(vec![3_000..20_000, 50_000..u64::MAX], 25_000),
+            vec![3_000..20_000]
+        );
+    }
+
+    #[test]
+    fn test_uncovered_spans() {
+        assert_eq!(uncovered_spans(&[], 5_000), vec![]);
+        assert_eq!(uncovered_spans(&[], 60_000), vec![0..60_000]);
+
+        let words = vec![
+            word("a", 1_000, 2_000),
+            word("b", 2_500, 3_000),
+            word("c", 20_000, 21_000),
+        ];
+        assert_eq!(
+            uncovered_spans(&words, 60_000),
+            vec![3_000..20_000, 21_000..60_000]
+        );
+        assert_eq!(uncovered_spans(&words, 25_000), vec![3_000..20_000]);
+    }
+
+    #[test]
+    fn test_union_spans() {
+        assert_eq!(union_spans(vec![], vec![]), vec![]);
+        assert_eq!(
+            union_spans(vec![5_000..8_000, 30_000..40_000], vec![3_000..20_000]),
+            vec![3_000..20_000, 30_000..40_000]
+        );
+        assert_eq!(
+            union_spans(vec![0..1_000], vec![1_000..2_000, 4_000..5_000]),
+            vec![0..2_000, 4_000..5_000]
+        );
+    }
+
+    #[test]
+    fn test_merge_words() {
+        let existing = vec![word("a", 0, 1_000), word("d", 30_000, 31_000)];
+        let recovered = vec![word("b", 10_000, 11_000), word("c", 20_000, 21_000)];
+
+        let merged = merge_words(existing, recovered)
+            .into_iter()
+            .map(|w| w.text)
+            .collect::<Vec<_>>();
+
+        assert_eq!(merged, vec!["a", "b", "c", "d"]);
+    }
+}
@@ -0,0 +1,168 @@
+use std::ops::Range;
+
+use futures_util::StreamExt;
+use rodio::Source;
+use tauri::Manager;
+
+use owhisper_interface::Word2;
+use tauri_plugin_connector::ConnectionSTT;
+
+// 64ms at 16kHz, per channel.
+const CHUNK_SAMPLES: usize = 1024;
+const CHUNK_BUFFER_SIZE: usize = 64;
+
+/// Transcribes the multitrack recording of a session again, sending the microphone and system audio as separate channels.
+/// The session's words are replaced with the result.
+pub async fn run<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    session_id: &str,
+) -> Result<Vec<Word2>, crate::Error> {
+    use tauri_plugin_connector::ConnectorPluginExt;
+    use tauri_plugin_db::DatabasePluginExt;
+
+    let mut session = app
+        .db_get_session(session_id)
+        .await?
+        .ok_or(crate::Error::NoneSession)?;
+
+    let session_dir = app.path().app_data_dir().unwrap().join(session_id);
+    let source = hypr_recorder::open(hypr_recorder::multitrack_dir(&session_dir))
+        .map_err(|_| crate::Error::NoMultitrackRecording)?;
+
+    if source.channels() != 2 || source.sample_rate() != crate::fsm::SAMPLE_RATE {
+        return Err(crate::Error::NoMultitrackRecording);
+    }
+
+    let conn = app.get_stt_connection().await?;
+    let words = transcribe(app, &conn, source).await?;
+
+    // Better to keep the old transcript than to replace it with nothing.
+    if !words.is_empty() {
+        session.words = words.clone();
+        app.db_upsert_session(session).await?;
+    }
+
+    Ok(words)
+}
+
+/// Transcribes `span` of a session's recording again with `conn`, replacing the words that start in it.
+///
+/// The multitrack recording is used if there is one. Otherwise the mixed recording is sent as the microphone.
+pub async fn run_span<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    session_id: &str,
+    conn: &ConnectionSTT,
+    span: Range<u64>,
+) -> Result<(), crate::Error> {
+    use tauri_plugin_db::DatabasePluginExt;
+
+    let session_dir = app.path().app_data_dir().unwrap().join(session_id);
+    let position = std::time::Duration::from_millis(span.start);
+    let source = hypr_recorder::open_at(hypr_recorder::multitrack_dir(&session_dir), position)
+        .or_else(|_| hypr_recorder::open_at(&session_dir, position))?;
+
+    if source.channels() > 2 || source.sample_rate() != crate::fsm::SAMPLE_RATE {
+        return Err(crate::Error::NoMultitrackRecording);
+    }
+
+    let length = std::time::Duration::from_millis(span.end - span.start);
+    let mut words = transcribe(app, conn, source.take_duration(length)).await?;
+    if words.is_empty() {
+        return Ok(());
+    }
+
+    for word in &mut words {
+        word.start_ms = word.start_ms.map(|ms| ms + span.start);
+        word.end_ms = word.end_ms.map(|ms| ms + span.start);
+    }
+
+    let mut session = app
+        .db_get_session(session_id)
+        .await?
+        .ok_or(crate::Error::NoneSession)?;
+
+    let kept = std::mem::take(&mut session.words)
+        .into_iter()
+        .filter(|w| !w.start_ms.is_some_and(|ms| span.contains(&ms)))
+        .collect();
+    session.words = crate::recovery::merge_words(kept, words);
+    app.db_upsert_session(session).await?;
+
+    Ok(())
+}
+
+async fn transcribe<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    conn: &ConnectionSTT,
+    source: impl Source<Item = f32> + Send + 'static,
+) -> Result<Vec<Word2>, crate::Error> {
+    use tauri_plugin_db::DatabasePluginExt;
+
+    let languages = {
+        let user_id = app.db_user_id().await?.unwrap_or_default();
+        app.db_get_config(&user_id).await?.map_or_else(
+            || vec![hypr_language::ISO639::En.into()],
+            |c| c.general.spoken_languages,
+        )
+    };
+
+    let params = owhisper_interface::ListenParams {
+        languages,
+        ..Default::default()
+    };
+
+    let client = crate::pipeline::build_listen_client(app, conn, params);
+
+    let (mic_tx, mic_rx) = flume::bounded::<bytes::Bytes>(CHUNK_BUFFER_SIZE);
+    let (speaker_tx, speaker_rx) = flume::bounded::<bytes::Bytes>(CHUNK_BUFFER_SIZE);
+
+    // Decoding is blocking, and the channels give us backpressure from the upstream.
+    // The reader stops on its own once the client drops the receivers.
+    tokio::task::spawn_blocking(move || {
+        let channels = source.channels() as usize;
+        let mut samples = source;
+
+        loop {
+            let frame = samples
+                .by_ref()
+                .take(CHUNK_SAMPLES * channels)
+                .collect::<Vec<f32>>();
+            if frame.is_empty() {
+                break;
+            }
+
+            // A mono recording has no system audio of its own.
+            let (mic, speaker) = if channels == 2 {
+                (
+                    frame.iter().step_by(2).copied().collect(),
+                    frame.iter().skip(1).step_by(2).copied().collect(),
+                )
+            } else {
+                let silence = vec![0.0; frame.len()];
+                (frame, silence)
+            };
+
+            if mic_tx
+                .send(hypr_audio_utils::f32_to_i16_bytes(mic))
+                .is_err()
+                || speaker_tx
+                    .send(hypr_audio_utils::f32_to_i16_bytes(speaker))
+                    .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let stream = client
+        .from_realtime_audio(mic_rx.into_stream(), speaker_rx.into_stream())
+        .await?;
+    futures_util::pin_mut!(stream);
+
+    let mut words = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        words.extend(chunk.words);
+    }
+
+    Ok(words)
+}
@@ -0,0 +1,412 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::time::Duration;
+
+use futures_util::{future::BoxFuture, Stream, StreamExt};
+
+use tauri_plugin_connector::ConnectionSTT;
+
+use crate::pipeline::Host;
+use crate::SessionEvent;
+
+// Audio that has not been covered by a transcript yet is kept around, so it can be replayed to another upstream.
+const MAX_REPLAY: Duration = Duration::from_secs(60);
+
+// 16kHz, 16-bit mono.
+const BYTES_PER_MS: usize = 32;
+
+// A dropped connection is retried this many times, backing off from `RETRY_BACKOFF`, before the cloud is given up.
+const MAX_CLOUD_RETRIES: u32 = 3;
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+// How often the cloud is tried again while the local fallback is transcribing.
+const CLOUD_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const CLOUD_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the upstreams of a session left for later, in session time.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct UpstreamReport {
+    /// Audio that no upstream acknowledged. The last one is open-ended if every upstream was gone.
+    pub lost: Vec<Range<u64>>,
+    /// Transcribed by the local fallback while the cloud was away, and the cloud came back afterwards.
+    pub fallback: Vec<Range<u64>>,
+}
+
+struct BufferedChunk {
+    offset_ms: u64,
+    mic: bytes::Bytes,
+    speaker: bytes::Bytes,
+}
+
+impl BufferedChunk {
+    fn end_ms(&self) -> u64 {
+        self.offset_ms + (self.mic.len().max(self.speaker.len()) / BYTES_PER_MS) as u64
+    }
+}
+
+#[derive(Default)]
+pub struct ReplayBuffer {
+    chunks: VecDeque<BufferedChunk>,
+    sent_ms: u64,
+    acked_ms: u64,
+}
+
+impl ReplayBuffer {
+    pub fn push(&mut self, mic: bytes::Bytes, speaker: bytes::Bytes) {
+        let chunk = BufferedChunk {
+            offset_ms: self.sent_ms,
+            mic,
+            speaker,
+        };
+        self.sent_ms = chunk.end_ms();
+        self.chunks.push_back(chunk);
+
+        while self
+            .chunks
+            .front()
+            .is_some_and(|c| self.sent_ms - c.offset_ms > MAX_REPLAY.as_millis() as u64)
+        {
+            self.chunks.pop_front();
+        }
+    }
+
+    /// Drops every chunk that ends before `until_ms`, since it is already transcribed.
+    pub fn ack(&mut self, until_ms: u64) {
+        self.acked_ms = self.acked_ms.max(until_ms);
+        while self.chunks.front().is_some_and(|c| c.end_ms() <= until_ms) {
+            self.chunks.pop_front();
+        }
+    }
+
+    /// Session time of the oldest audio that is not transcribed yet.
+    pub fn pending_from_ms(&self) -> u64 {
+        self.chunks
+            .front()
+            .map(|c| c.offset_ms)
+            .unwrap_or(self.sent_ms)
+    }
+
+    /// Audio that was pushed out of the buffer before it was acknowledged, so it cannot be replayed.
+    /// Only a failed upstream makes it lost, since a working one may just have heard silence.
+    pub fn take_evicted(&mut self) -> Option<Range<u64>> {
+        let evicted = self.acked_ms..self.pending_from_ms();
+        self.acked_ms = self.acked_ms.max(evicted.end);
+        (!evicted.is_empty()).then_some(evicted)
+    }
+
+    /// Everything not acknowledged so far, including what is still to come.
+    pub fn give_up(&mut self) -> Range<u64> {
+        let lost = self.acked_ms..u64::MAX;
+        self.chunks.clear();
+        self.acked_ms = u64::MAX;
+        lost
+    }
+
+    fn pending(&self) -> impl Iterator<Item = (bytes::Bytes, bytes::Bytes)> + '_ {
+        self.chunks
+            .iter()
+            .map(|c| (c.mic.clone(), c.speaker.clone()))
+    }
+}
+
+enum Outcome {
+    Finished,
+    Failed,
+    CloudBack,
+}
+
+struct Senders {
+    mic: flume::Sender<bytes::Bytes>,
+    speaker: flume::Sender<bytes::Bytes>,
+}
+
+/// Streams session audio to the STT upstream.
+///
+/// A cloud upstream that keeps failing is replaced by HyprLocal, until the cloud can be reached again.
+pub async fn run(
+    host: Host,
+    session_id: String,
+    conn: ConnectionSTT,
+    params: owhisper_interface::ListenParams,
+    mic_rx: flume::Receiver<Vec<f32>>,
+    speaker_rx: flume::Receiver<Vec<f32>>,
+) -> UpstreamReport {
+    // `zip` keeps a half-received pair across polls, so it is safe to use inside `select!`.
+    let mut audio_stream =
+        mic_rx
+            .into_stream()
+            .zip(speaker_rx.into_stream())
+            .map(|(mic, speaker)| {
+                (
+                    hypr_audio_utils::f32_to_i16_bytes(mic),
+                    hypr_audio_utils::f32_to_i16_bytes(speaker),
+                )
+            });
+
+    let mut buffer = ReplayBuffer::default();
+    let mut input_open = true;
+    let mut report = UpstreamReport::default();
+
+    let cloud = matches!(conn, ConnectionSTT::HyprCloud(_)).then(|| conn.clone());
+    let mut conn = conn;
+    let mut retries = 0;
+    let mut fallback_from_ms = None;
+    let mut cloud_probe: Option<BoxFuture<'static, ()>> = None;
+
+    loop {
+        let base_ms = buffer.pending_from_ms();
+        let client = host.stt.listen_client(&conn, params.clone());
+
+        let (mic_tx, conn_mic_rx) = flume::unbounded::<bytes::Bytes>();
+        let (speaker_tx, conn_speaker_rx) = flume::unbounded::<bytes::Bytes>();
+        for (mic, speaker) in buffer.pending() {
+            let _ = mic_tx.send(mic);
+            let _ = speaker_tx.send(speaker);
+        }
+
+        let mut senders = input_open.then_some(Senders {
+            mic: mic_tx,
+            speaker: speaker_tx,
+        });
+
+        let outcome = {
+            let connect = client
+                .from_realtime_audio(conn_mic_rx.into_stream(), conn_speaker_rx.into_stream());
+            futures_util::pin_mut!(connect);
+
+            let connected = loop {
+                tokio::select! {
+                    res = &mut connect => break res,
+                    audio = audio_stream.next(), if input_open => {
+                        input_open = forward(audio, &mut buffer, &mut senders);
+                    }
+                }
+            };
+
+            match connected {
+                Err(e) => {
+                    tracing::error!("listen_connect_failed: {:?}", e);
+                    Outcome::Failed
+                }
+                Ok(listen_stream) => {
+                    futures_util::pin_mut!(listen_stream);
+
+                    loop {
+                        tokio::select! {
+                            audio = audio_stream.next(), if input_open => {
+                                input_open = forward(audio, &mut buffer, &mut senders);
+                            }
+                            result = listen_stream.next() => match result {
+                                Some(chunk) => {
+                                    retries = 0;
+                                    handle_words(&host, &session_id, &mut buffer, base_ms, chunk.words).await;
+                                }
+                                None if input_open => break Outcome::Failed,
+                                None => break Outcome::Finished,
+                            },
+                            _ = async { cloud_probe.as_mut().unwrap().await }, if cloud_probe.is_some() && input_open => {
+                                break Outcome::CloudBack;
+                            }
+                        }
+                    }
+                }
+            }
+        };
+
+        match (outcome, &conn) {
+            (Outcome::Finished, _) => break,
+            (Outcome::CloudBack, _) => {
+                let from_ms = fallback_from_ms.take().unwrap_or(base_ms);
+                tracing::info!(fallback_from_ms = from_ms, "stt_cloud_restored");
+                report.fallback.push(from_ms..buffer.pending_from_ms());
+                host.events.emit(SessionEvent::SttRestored {});
+
+                cloud_probe = None;
+                conn = cloud.clone().unwrap();
+            }
+            (Outcome::Failed, ConnectionSTT::HyprCloud(_)) if retries < MAX_CLOUD_RETRIES => {
+                report.lost.extend(buffer.take_evicted());
+
+                let backoff = RETRY_BACKOFF * 2u32.pow(retries);
+                retries += 1;
+                tracing::warn!(attempt = retries, backoff = ?backoff, "stt_cloud_retry");
+
+                input_open = buffer_for(backoff, &mut audio_stream, &mut buffer, input_open).await;
+            }
+            (Outcome::Failed, ConnectionSTT::HyprCloud(_)) => match host.stt.fallback().await {
+                Ok(local) => {
+                    tracing::warn!(replay_from_ms = base_ms, "stt_fallback_to_local");
+                    host.events.emit(SessionEvent::SttFallback {});
+                    report.lost.extend(buffer.take_evicted());
+
+                    retries = 0;
+                    fallback_from_ms = Some(buffer.pending_from_ms());
+                    cloud_probe = Some(wait_for_cloud(conn.clone()));
+                    conn = local;
+                }
+                Err(e) => {
+                    tracing::error!("stt_fallback_unavailable: {:?}", e);
+                    report.lost.push(buffer.give_up());
+                    break;
+                }
+            },
+            (Outcome::Failed, ConnectionSTT::HyprLocal(_)) => {
+                report.lost.push(buffer.give_up());
+                break;
+            }
+        }
+    }
+
+    report
+}
+
+// Keeps taking audio for `duration` while there is no upstream. Returns whether the input is still open.
+async fn buffer_for(
+    duration: Duration,
+    audio_stream: &mut (impl Stream<Item = (bytes::Bytes, bytes::Bytes)> + Unpin),
+    buffer: &mut ReplayBuffer,
+    mut input_open: bool,
+) -> bool {
+    let sleep = tokio::time::sleep(duration);
+    futures_util::pin_mut!(sleep);
+
+    loop {
+        tokio::select! {
+            _ = &mut sleep => return input_open,
+            audio = audio_stream.next(), if input_open => {
+                input_open = forward(audio, buffer, &mut None);
+            }
+        }
+    }
+}
+
+// Resolves once the server behind `conn` accepts a connection again.
+// Only a TCP connection is made, so probing doesn't open (and then finalize) a listen session.
+fn wait_for_cloud(conn: ConnectionSTT) -> BoxFuture<'static, ()> {
+    Box::pin(async move {
+        let Some((host, port)) = probe_addr(&conn.as_ref().api_base) else {
+            tracing::error!(api_base = ?conn.as_ref().api_base, "stt_cloud_probe_invalid_url");
+            return std::future::pending().await;
+        };
+
+        loop {
+            tokio::time::sleep(CLOUD_PROBE_INTERVAL).await;
+
+            let connect = tokio::net::TcpStream::connect((host.as_str(), port));
+            if let Ok(Ok(_)) = tokio::time::timeout(CLOUD_PROBE_TIMEOUT, connect).await {
+                return;
+            }
+        }
+    })
+}
+
+fn probe_addr(api_base: &str) -> Option<(String, u16)> {
+    let url = url::Url::parse(api_base).ok()?;
+    Some((url.host_str()?.to_string(), url.port_or_known_default()?))
+}
+
+// Returns whether the input is still open. Dropping the senders lets the client finalize the stream.
+fn forward(
+    audio: Option<(bytes::Bytes, bytes::Bytes)>,
+    buffer: &mut ReplayBuffer,
+    senders: &mut Option<Senders>,
+) -> bool {
+    let Some((mic, speaker)) = audio else {
+        senders.take();
+        return false;
+    };
+
+    buffer.push(mic.clone(), speaker.clone());
+
+    if let Some(s) = senders {
+        if s.mic.send(mic).is_err() || s.speaker.send(speaker).is_err() {
+            senders.take();
+        }
+    }
+
+    true
+}
+
+async fn handle_words(
+    host: &Host,
+    session_id: &str,
+    buffer: &mut ReplayBuffer,
+    base_ms: u64,
+    mut words: Vec<owhisper_interface::Word2>,
+) {
+    // Timestamps are relative to the start of the current connection.
+    for word in &mut words {
+        word.start_ms = word.start_ms.map(|ms| ms + base_ms);
+        word.end_ms = word.end_ms.map(|ms| ms + base_ms);
+    }
+
+    if let Some(until_ms) = words.iter().filter_map(|w| w.end_ms).max() {
+        buffer.ack(until_ms);
+    }
+
+    // We don't have to do this, and inefficient. But this is what works at the moment.
+    match host.transcripts.append(session_id, words).await {
+        Ok(updated_words) => host.events.emit(SessionEvent::Words {
+            words: updated_words,
+        }),
+        Err(e) => tracing::error!("update_session_failed: {:?}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(ms: usize) -> bytes::Bytes {
+        bytes::Bytes::from(vec![0u8; ms * BYTES_PER_MS])
+    }
+
+    #[test]
+    fn test_replay_buffer() {
+        let mut buffer = ReplayBuffer::default();
+        assert_eq!(buffer.pending_from_ms(), 0);
+
+        for _ in 0..10 {
+            buffer.push(chunk(100), chunk(100));
+        }
+        assert_eq!(buffer.pending_from_ms(), 0);
+
+        buffer.ack(350);
+        assert_eq!(buffer.pending_from_ms(), 300);
+        assert_eq!(buffer.pending().count(), 7);
+
+        buffer.ack(1000);
+        assert_eq!(buffer.pending_from_ms(), 1000);
+        assert_eq!(buffer.pending().count(), 0);
+        assert_eq!(buffer.take_evicted(), None);
+    }
+
+    #[test]
+    fn test_replay_buffer_is_bounded() {
+        let mut buffer = ReplayBuffer::default();
+
+        for _ in 0..(MAX_REPLAY.as_millis() as usize / 1000 + 10) {
+            buffer.push(chunk(1000), chunk(1000));
+        }
+
+        assert_eq!(
+            buffer.pending().count(),
+            MAX_REPLAY.as_millis() as usize / 1000
+        );
+        assert_eq!(buffer.take_evicted(), Some(0..10_000));
+        assert_eq!(buffer.take_evicted(), None);
+        assert_eq!(buffer.give_up(), 10_000..u64::MAX);
+    }
+
+    #[test]
+    fn test_probe_addr() {
+        assert_eq!(
+            probe_addr("https://example.com"),
+            Some(("example.com".to_string(), 443))
+        );
+        assert_eq!(
+            probe_addr("http://127.0.0.1:1234/v1"),
+            Some(("127.0.0.1".to_string(), 1234))
+        );
+        assert_eq!(probe_addr("not a url"), None);
+    }
+}
@@ -10,6 +10,17 @@ const COMMANDS: &[&str] = &[
     "get_current_model",
     "set_current_model",
     "list_downloaded_model",
+    "is_retrieval_enabled",
+    "set_retrieval_enabled",
+    "is_embedding_model_downloaded",
+    "download_embedding_model",
+    "index_sessions",
+    "retrieve_session_chunks",
+    "list_custom_models",
+    "add_custom_model",
+    "remove_custom_model",
+    "get_task_model",
+    "set_task_model",
 ];
 
 fn main() {
@@ -42,6 +42,39 @@ async setCurrentModel(model: SupportedModel) : Promise<null> {
 },
 async listDownloadedModel() : Promise<SupportedModel[]> {
     return await TAURI_INVOKE("plugin:local-llm|list_downloaded_model");
+},
+async listCustomModels() : Promise<CustomModel[]> {
+    return await TAURI_INVOKE("plugin:local-llm|list_custom_models");
+},
+async addCustomModel(path: string) : Promise<CustomModel> {
+    return await TAURI_INVOKE("plugin:local-llm|add_custom_model", { path });
+},
+async removeCustomModel(id: string) : Promise<null> {
+    return await TAURI_INVOKE("plugin:local-llm|remove_custom_model", { id });
+},
+async getTaskModel(task: ModelTask) : Promise<ModelSelection> {
+    return await TAURI_INVOKE("plugin:local-llm|get_task_model", { task });
+},
+async setTaskModel(task: ModelTask, model: ModelSelection | null) : Promise<null> {
+    return await TAURI_INVOKE("plugin:local-llm|set_task_model", { task, model });
+},
+async isRetrievalEnabled() : Promise<boolean> {
+    return await TAURI_INVOKE("plugin:local-llm|is_retrieval_enabled");
+},
+async setRetrievalEnabled(enabled: boolean) : Promise<null> {
+    return await TAURI_INVOKE("plugin:local-llm|set_retrieval_enabled", { enabled });
+},
+async isEmbeddingModelDownloaded() : Promise<boolean> {
+    return await TAURI_INVOKE("plugin:local-llm|is_embedding_model_downloaded");
+},
+async downloadEmbeddingModel(channel: TAURI_CHANNEL<number>) : Promise<null> {
+    return await TAURI_INVOKE("plugin:local-llm|download_embedding_model", { channel });
+},
+async indexSessions() : Promise<number> {
+    return await TAURI_INVOKE("plugin:local-llm|index_sessions");
+},
+async retrieveSessionChunks(query: string, limit: number | null) : Promise<SessionChunkMatch[]> {
+    return await TAURI_INVOKE("plugin:local-llm|retrieve_session_chunks", { query, limit });
 }
 }
 
@@ -55,6 +88,21 @@ async listDownloadedModel() : Promise<SupportedModel[]> {
 
 /** user-defined types **/
 
+/**
+ * A GGUF file registered by the user, with what we read from its metadata.
+ */
+export type CustomModel = { id: string; name: string; path: string; architecture: string | null; context_length: number | null; quantization: string | null; size_bytes: number }
+export type ModelSelection = { type: "supported"; value: SupportedModel } | { type: "custom"; value: string }
+export type ModelTask = "enhance" | "title" | "chat"
+export type SessionChunkKind = "Title" | "Memo" | "Transcript"
+/**
+ * Retrieved chunk, with what is needed to cite it.
+ */
+export type SessionChunkMatch = { session_id: string; session_title: string; session_created_at: string; kind: SessionChunkKind; content: string; start_ms: number | null; end_ms: number | null; 
+/**
+ * Cosine distance to the query. Lower is closer.
+ */
+distance: number }
 export type SupportedModel = "Llama3p2_3bQ4" | "HyprLLM"
 export type TAURI_CHANNEL<TSend> = null
 
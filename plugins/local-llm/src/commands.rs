@@ -95,3 +95,105 @@ pub async fn set_current_model<R: tauri::Runtime>(
 ) -> Result<(), String> {
     app.set_current_model(model).map_err(|e| e.to_string())
 }
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_custom_models<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+) -> Result<Vec<crate::CustomModel>, String> {
+    app.list_custom_models().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn add_custom_model<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    path: String,
+) -> Result<crate::CustomModel, String> {
+    app.add_custom_model(path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn remove_custom_model<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    id: String,
+) -> Result<(), String> {
+    app.remove_custom_model(id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_task_model<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    task: crate::ModelTask,
+) -> Result<crate::ModelSelection, String> {
+    app.get_task_model(task).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_task_model<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    task: crate::ModelTask,
+    model: Option<crate::ModelSelection>,
+) -> Result<(), String> {
+    app.set_task_model(task, model).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn is_retrieval_enabled<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+) -> Result<bool, String> {
+    app.is_retrieval_enabled().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_retrieval_enabled<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    enabled: bool,
+) -> Result<(), String> {
+    app.set_retrieval_enabled(enabled)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn is_embedding_model_downloaded<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> bool {
+    app.is_embedding_model_downloaded()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn download_embedding_model<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    channel: Channel<i8>,
+) -> Result<(), String> {
+    app.download_embedding_model(channel)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn index_sessions<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> Result<u32, String> {
+    app.index_sessions()
+        .await
+        .map(|indexed| indexed as u32)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn retrieve_session_chunks<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    query: String,
+    limit: Option<u8>,
+) -> Result<Vec<hypr_db_user::SessionChunkMatch>, String> {
+    app.retrieve_session_chunks(query, limit.unwrap_or(5))
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hypr_file::DownloadProgress;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_db::DatabasePluginExt;
+
+use crate::LocalLlmPluginExt;
+
+pub const EMBEDDING_MODEL: &str = "nomic-embed-text-v1.5";
+const EMBEDDING_MODEL_FILE: &str = "nomic-embed-text-v1.5.gguf";
+const EMBEDDING_MODEL_URL: &str = "https://huggingface.co/nomic-ai/nomic-embed-text-v1.5-GGUF/resolve/main/nomic-embed-text-v1.5.Q8_0.gguf";
+
+// nomic-embed-text is trained with these task prefixes.
+pub const DOCUMENT_PREFIX: &str = "search_document: ";
+pub const QUERY_PREFIX: &str = "search_query: ";
+
+// Chunks embedded per round trip to the database.
+pub const INDEX_BATCH_SIZE: u32 = 32;
+
+// Sessions are indexed once they stop changing for this long, so a live transcript or typing doesn't re-embed on every save.
+const INDEX_DEBOUNCE: Duration = Duration::from_secs(30);
+
+pub fn model_path<R: Runtime>(app: &impl Manager<R>) -> PathBuf {
+    app.models_dir().join(EMBEDDING_MODEL_FILE)
+}
+
+/// Loads the embedding model. It is only ever downloaded through `download_embedding_model`.
+pub async fn get_embedder<R: Runtime>(
+    app: &impl Manager<R>,
+) -> Result<Arc<hypr_llama::Embedder>, crate::Error> {
+    let path = model_path(app);
+
+    let state = app.state::<crate::SharedState>();
+    let mut s = state.lock().await;
+
+    if let Some(embedder) = &s.embedder {
+        return Ok(embedder.clone());
+    }
+
+    if !path.exists() {
+        return Err(crate::Error::ModelNotDownloaded);
+    }
+
+    let embedder = Arc::new(hypr_llama::Embedder::new(path)?);
+    s.embedder = Some(embedder.clone());
+    Ok(embedder)
+}
+
+pub async fn download(
+    path: PathBuf,
+    progress: impl Fn(DownloadProgress) + Send + Sync,
+) -> Result<(), crate::Error> {
+    // Renamed once complete, so a partial file is never loaded.
+    let partial = path.with_extension("gguf.part");
+
+    hypr_file::download_file_parallel(EMBEDDING_MODEL_URL, &partial, progress).await?;
+    std::fs::rename(&partial, &path)?;
+    Ok(())
+}
+
+/// Keeps the index up to date with session upserts, while retrieval is enabled.
+pub async fn index_on_session_updates<R: Runtime>(app: AppHandle<R>) {
+    let Some(mut updates) = app.db_session_updates().await else {
+        return;
+    };
+
+    // Catches up with edits made while the app was closed, or retrieval was off.
+    app.index_sessions_in_background().await;
+
+    while updates.changed().await.is_ok() {
+        while let Ok(Ok(())) = tokio::time::timeout(INDEX_DEBOUNCE, updates.changed()).await {}
+
+        app.index_sessions_in_background().await;
+    }
+}
@@ -7,6 +7,10 @@ pub enum Error {
     #[error(transparent)]
     HyprLlamaError(#[from] hypr_llama::Error),
     #[error(transparent)]
+    HyprGbnfError(#[from] hypr_gbnf::Error),
+    #[error(transparent)]
+    HyprGgufError(#[from] hypr_gguf::Error),
+    #[error(transparent)]
     HyprFileError(#[from] hypr_file::Error),
     #[error(transparent)]
     ReqwestError(#[from] reqwest::Error),
@@ -14,8 +18,16 @@ pub enum Error {
     IoError(#[from] std::io::Error),
     #[error(transparent)]
     StoreError(#[from] tauri_plugin_store2::Error),
+    #[error(transparent)]
+    DatabaseError(#[from] tauri_plugin_db::Error),
     #[error("Model not downloaded")]
     ModelNotDownloaded,
+    #[error("Custom model not found: {0}")]
+    CustomModelNotFound(String),
+    #[error("Model has no chat template: {0}")]
+    MissingChatTemplate(String),
+    #[error("seed must be between 0 and {}, got {0}", u32::MAX)]
+    InvalidSeed(i64),
 }
 
 impl Serialize for Error {
@@ -1,6 +1,9 @@
-use std::{future::Future, path::PathBuf};
+use std::{collections::HashMap, future::Future, path::PathBuf};
+
+use strum::IntoEnumIterator;
 
 use tauri::{ipc::Channel, Manager, Runtime};
+use tauri_plugin_db::DatabasePluginExt;
 use tauri_plugin_store2::StorePluginExt;
 
 use hypr_file::{download_file_parallel, DownloadProgress};
@@ -21,6 +24,21 @@ pub trait LocalLlmPluginExt<R: Runtime> {
     fn get_current_model(&self) -> Result<crate::SupportedModel, crate::Error>;
     fn set_current_model(&self, model: crate::SupportedModel) -> Result<(), crate::Error>;
 
+    fn list_custom_models(&self) -> Result<Vec<crate::CustomModel>, crate::Error>;
+    fn add_custom_model(
+        &self,
+        path: impl Into<PathBuf>,
+    ) -> Result<crate::CustomModel, crate::Error>;
+    fn remove_custom_model(&self, id: impl AsRef<str>) -> Result<(), crate::Error>;
+    fn get_task_model(&self, task: crate::ModelTask)
+        -> Result<crate::ModelSelection, crate::Error>;
+    fn set_task_model(
+        &self,
+        task: crate::ModelTask,
+        model: Option<crate::ModelSelection>,
+    ) -> Result<(), crate::Error>;
+    fn model_path(&self, model: &crate::ModelSelection) -> Result<PathBuf, crate::Error>;
+
     fn download_model(
         &self,
         model: crate::SupportedModel,
@@ -31,6 +49,25 @@ pub trait LocalLlmPluginExt<R: Runtime> {
         &self,
         model: &crate::SupportedModel,
     ) -> impl Future<Output = Result<bool, crate::Error>>;
+
+    fn is_retrieval_enabled(&self) -> Result<bool, crate::Error>;
+    fn set_retrieval_enabled(
+        &self,
+        enabled: bool,
+    ) -> impl Future<Output = Result<(), crate::Error>>;
+    fn is_embedding_model_downloaded(&self) -> bool;
+    fn download_embedding_model(
+        &self,
+        channel: Channel<i8>,
+    ) -> impl Future<Output = Result<(), crate::Error>>;
+
+    fn index_sessions(&self) -> impl Future<Output = Result<usize, crate::Error>>;
+    fn index_sessions_in_background(&self) -> impl Future<Output = ()>;
+    fn retrieve_session_chunks(
+        &self,
+        query: impl Into<String>,
+        limit: u8,
+    ) -> impl Future<Output = Result<Vec<hypr_db_user::SessionChunkMatch>, crate::Error>>;
 }
 
 impl<R: Runtime, T: Manager<R>> LocalLlmPluginExt<R> for T {
@@ -95,18 +132,7 @@ impl<R: Runtime, T: Manager<R>> LocalLlmPluginExt<R> for T {
         let path = self.models_dir().join(m.file_name());
 
         let task = tokio::spawn(async move {
-            let callback = |progress: DownloadProgress| match progress {
-                DownloadProgress::Started => {
-                    let _ = channel.send(0);
-                }
-                DownloadProgress::Progress(downloaded, total_size) => {
-                    let percent = (downloaded as f64 / total_size as f64) * 100.0;
-                    let _ = channel.send(percent as i8);
-                }
-                DownloadProgress::Finished => {
-                    let _ = channel.send(100);
-                }
-            };
+            let callback = send_progress(channel.clone());
 
             if let Err(e) = download_file_parallel(m.model_url(), path, callback).await {
                 tracing::error!("model_download_error: {}", e);
@@ -163,10 +189,15 @@ impl<R: Runtime, T: Manager<R>> LocalLlmPluginExt<R> for T {
 
     #[tracing::instrument(skip_all)]
     async fn start_server(&self) -> Result<String, crate::Error> {
-        let current_model = self.get_current_model()?;
-
-        let model_path = self.models_dir().join(current_model.file_name());
-        let model_manager = crate::ModelManager::new(model_path);
+        let model_paths = crate::ModelTask::iter()
+            .map(|task| {
+                let model = self.get_task_model(task)?;
+                Ok((task, self.model_path(&model)?))
+            })
+            .collect::<Result<HashMap<_, _>, crate::Error>>()?;
+
+        let model_manager =
+            crate::ModelManager::new(model_paths, crate::DEFAULT_MEMORY_BUDGET_BYTES);
         let state = self.state::<crate::SharedState>();
 
         let server_state = crate::ServerState::new(model_manager);
@@ -233,4 +264,279 @@ impl<R: Runtime, T: Manager<R>> LocalLlmPluginExt<R> for T {
         store.set(crate::StoreKey::Model, model)?;
         Ok(())
     }
+
+    fn list_custom_models(&self) -> Result<Vec<crate::CustomModel>, crate::Error> {
+        let store = self.local_llm_store();
+        let models: Option<Vec<crate::CustomModel>> = store.get(crate::StoreKey::CustomModels)?;
+        Ok(models.unwrap_or_default())
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn add_custom_model(
+        &self,
+        path: impl Into<PathBuf>,
+    ) -> Result<crate::CustomModel, crate::Error> {
+        use hypr_gguf::GgufExt;
+
+        let path = path.into();
+        let metadata = path.gguf_metadata()?;
+
+        // `hypr_llama::Llama` can't prompt a model without one.
+        if metadata.chat_template.is_none() {
+            return Err(crate::Error::MissingChatTemplate(
+                path.to_string_lossy().to_string(),
+            ));
+        }
+
+        let model = crate::CustomModel {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: metadata.name.unwrap_or_else(|| {
+                path.file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            }),
+            path: path.to_string_lossy().to_string(),
+            architecture: metadata.architecture,
+            context_length: metadata
+                .context_length
+                .map(|l| l.min(u32::MAX as u64) as u32),
+            quantization: metadata.quantization,
+            size_bytes: metadata.size_bytes,
+        };
+
+        let mut models = self.list_custom_models()?;
+        models.push(model.clone());
+        self.local_llm_store()
+            .set(crate::StoreKey::CustomModels, models)?;
+
+        Ok(model)
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn remove_custom_model(&self, id: impl AsRef<str>) -> Result<(), crate::Error> {
+        let id = id.as_ref();
+        let store = self.local_llm_store();
+
+        let mut models = self.list_custom_models()?;
+        models.retain(|m| m.id != id);
+        store.set(crate::StoreKey::CustomModels, models)?;
+
+        // Tasks using it go back to the current model.
+        let mut task_models: HashMap<crate::ModelTask, crate::ModelSelection> =
+            store.get(crate::StoreKey::TaskModels)?.unwrap_or_default();
+        task_models.retain(|_, m| !matches!(m, crate::ModelSelection::Custom(c) if c == id));
+        store.set(crate::StoreKey::TaskModels, task_models)?;
+
+        Ok(())
+    }
+
+    fn get_task_model(
+        &self,
+        task: crate::ModelTask,
+    ) -> Result<crate::ModelSelection, crate::Error> {
+        let task_models: HashMap<crate::ModelTask, crate::ModelSelection> = self
+            .local_llm_store()
+            .get(crate::StoreKey::TaskModels)?
+            .unwrap_or_default();
+
+        match task_models.get(&task) {
+            Some(model) => Ok(model.clone()),
+            None => Ok(crate::ModelSelection::Supported(self.get_current_model()?)),
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn set_task_model(
+        &self,
+        task: crate::ModelTask,
+        model: Option<crate::ModelSelection>,
+    ) -> Result<(), crate::Error> {
+        let store = self.local_llm_store();
+        let mut task_models: HashMap<crate::ModelTask, crate::ModelSelection> =
+            store.get(crate::StoreKey::TaskModels)?.unwrap_or_default();
+
+        match model {
+            Some(model) => {
+                // Fails for custom models that were removed.
+                self.model_path(&model)?;
+                task_models.insert(task, model);
+            }
+            None => {
+                task_models.remove(&task);
+            }
+        }
+
+        store.set(crate::StoreKey::TaskModels, task_models)?;
+        Ok(())
+    }
+
+    fn model_path(&self, model: &crate::ModelSelection) -> Result<PathBuf, crate::Error> {
+        match model {
+            crate::ModelSelection::Supported(model) => {
+                Ok(self.models_dir().join(model.file_name()))
+            }
+            crate::ModelSelection::Custom(id) => self
+                .list_custom_models()?
+                .into_iter()
+                .find(|m| &m.id == id)
+                .map(|m| PathBuf::from(m.path))
+                .ok_or_else(|| crate::Error::CustomModelNotFound(id.clone())),
+        }
+    }
+
+    fn is_retrieval_enabled(&self) -> Result<bool, crate::Error> {
+        let enabled: Option<bool> = self
+            .local_llm_store()
+            .get(crate::StoreKey::RetrievalEnabled)?;
+        Ok(enabled.unwrap_or(false))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn set_retrieval_enabled(&self, enabled: bool) -> Result<(), crate::Error> {
+        self.local_llm_store()
+            .set(crate::StoreKey::RetrievalEnabled, enabled)?;
+
+        // Sessions edited while it was off are caught up with right away.
+        self.index_sessions_in_background().await;
+        Ok(())
+    }
+
+    fn is_embedding_model_downloaded(&self) -> bool {
+        crate::embedding::model_path(self).exists()
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn download_embedding_model(&self, channel: Channel<i8>) -> Result<(), crate::Error> {
+        let path = crate::embedding::model_path(self);
+        let app = self.app_handle().clone();
+
+        let task = tokio::spawn(async move {
+            let callback = send_progress(channel.clone());
+
+            match crate::embedding::download(path, callback).await {
+                Ok(()) => app.index_sessions_in_background().await,
+                Err(e) => {
+                    tracing::error!("embedding_model_download_error: {}", e);
+                    let _ = channel.send(-1);
+                }
+            }
+        });
+
+        {
+            let state = self.state::<crate::SharedState>();
+            let mut s = state.lock().await;
+
+            if let Some(existing_task) = s.embedding_download.replace(task) {
+                existing_task.abort();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Chunks the sessions edited since the last run, and embeds what changed. Returns how many chunks were embedded.
+    #[tracing::instrument(skip_all)]
+    async fn index_sessions(&self) -> Result<usize, crate::Error> {
+        let embedder = crate::embedding::get_embedder(self).await?;
+        let mut indexed = 0;
+
+        while self
+            .db_sync_stale_session_chunks(crate::embedding::INDEX_BATCH_SIZE)
+            .await?
+            > 0
+        {}
+
+        loop {
+            let chunks = self
+                .db_list_unembedded_session_chunks(
+                    crate::EMBEDDING_MODEL,
+                    crate::embedding::INDEX_BATCH_SIZE,
+                )
+                .await?;
+            if chunks.is_empty() {
+                break;
+            }
+
+            let texts = chunks
+                .iter()
+                .map(|chunk| format!("{}{}", crate::embedding::DOCUMENT_PREFIX, chunk.content))
+                .collect::<Vec<_>>();
+            let embeddings = {
+                let embedder = embedder.clone();
+                tokio::task::spawn_blocking(move || embedder.embed(&texts))
+                    .await
+                    .map_err(std::io::Error::other)??
+            };
+
+            for (chunk, embedding) in chunks.iter().zip(embeddings) {
+                self.db_set_session_chunk_embedding(&chunk.id, crate::EMBEDDING_MODEL, &embedding)
+                    .await?;
+            }
+            indexed += chunks.len();
+        }
+
+        Ok(indexed)
+    }
+
+    /// Starts `index_sessions`, unless it is running already or retrieval is off.
+    async fn index_sessions_in_background(&self) {
+        if !self.is_retrieval_enabled().unwrap_or(false) || !self.is_embedding_model_downloaded() {
+            return;
+        }
+
+        let state = self.state::<crate::SharedState>();
+        let mut s = state.lock().await;
+
+        if s.indexing.as_ref().is_some_and(|t| !t.is_finished()) {
+            return;
+        }
+
+        let app = self.app_handle().clone();
+        s.indexing = Some(tokio::spawn(async move {
+            if let Err(e) = app.index_sessions().await {
+                tracing::error!("index_sessions_error: {}", e);
+            }
+        }));
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn retrieve_session_chunks(
+        &self,
+        query: impl Into<String>,
+        limit: u8,
+    ) -> Result<Vec<hypr_db_user::SessionChunkMatch>, crate::Error> {
+        if !self.is_retrieval_enabled()? {
+            return Ok(vec![]);
+        }
+
+        // Edits since the last query are found once they are indexed, without holding up this one.
+        self.index_sessions_in_background().await;
+
+        let embedder = crate::embedding::get_embedder(self).await?;
+        let query = format!("{}{}", crate::embedding::QUERY_PREFIX, query.into());
+        let embedding = tokio::task::spawn_blocking(move || embedder.embed(&[query]))
+            .await
+            .map_err(std::io::Error::other)??
+            .remove(0);
+
+        let matches = self
+            .db_search_session_chunks(crate::EMBEDDING_MODEL, &embedding, limit)
+            .await?;
+        Ok(matches)
+    }
+}
+
+fn send_progress(channel: Channel<i8>) -> impl Fn(DownloadProgress) + Send + Sync {
+    move |progress| match progress {
+        DownloadProgress::Started => {
+            let _ = channel.send(0);
+        }
+        DownloadProgress::Progress(downloaded, total_size) => {
+            let percent = (downloaded as f64 / total_size as f64) * 100.0;
+            let _ = channel.send(percent as i8);
+        }
+        DownloadProgress::Finished => {
+            let _ = channel.send(100);
+        }
+    }
 }
@@ -5,6 +5,7 @@ use tauri::{Manager, Wry};
 use tokio::sync::Mutex;
 
 mod commands;
+mod embedding;
 mod error;
 mod ext;
 mod manager;
@@ -12,6 +13,7 @@ mod model;
 mod server;
 mod store;
 
+pub use embedding::EMBEDDING_MODEL;
 pub use error::*;
 pub use ext::*;
 pub use manager::*;
@@ -30,6 +32,9 @@ pub struct State {
     pub api_base: Option<String>,
     pub server: Option<crate::server::ServerHandle>,
     pub download_task: HashMap<SupportedModel, tokio::task::JoinHandle<()>>,
+    pub embedder: Option<Arc<hypr_llama::Embedder>>,
+    pub embedding_download: Option<tokio::task::JoinHandle<()>>,
+    pub indexing: Option<tokio::task::JoinHandle<()>>,
 }
 
 fn make_specta_builder<R: tauri::Runtime>() -> tauri_specta::Builder<R> {
@@ -48,6 +53,17 @@ fn make_specta_builder<R: tauri::Runtime>() -> tauri_specta::Builder<R> {
             commands::get_current_model::<Wry>,
             commands::set_current_model::<Wry>,
             commands::list_downloaded_model::<Wry>,
+            commands::list_custom_models::<Wry>,
+            commands::add_custom_model::<Wry>,
+            commands::remove_custom_model::<Wry>,
+            commands::get_task_model::<Wry>,
+            commands::set_task_model::<Wry>,
+            commands::is_retrieval_enabled::<Wry>,
+            commands::set_retrieval_enabled::<Wry>,
+            commands::is_embedding_model_downloaded::<Wry>,
+            commands::download_embedding_model::<Wry>,
+            commands::index_sessions::<Wry>,
+            commands::retrieve_session_chunks::<Wry>,
         ])
         .error_handling(tauri_specta::ErrorHandlingMode::Throw)
 }
@@ -81,6 +97,8 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
                 app.manage(state);
             }
 
+            tauri::async_runtime::spawn(embedding::index_on_session_updates(app.clone()));
+
             Ok(())
         })
         .build()
@@ -1,10 +1,17 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::{watch, Mutex};
 
+use crate::ModelTask;
+
+// Models are mapped into memory about as large as their file.
+pub const DEFAULT_MEMORY_BUDGET_BYTES: u64 = 6 * 1024 * 1024 * 1024;
+
 #[derive(Clone)]
 pub struct ModelManager {
-    model_path: std::path::PathBuf,
-    model: Arc<Mutex<Option<Arc<hypr_llama::Llama>>>>,
+    model_paths: Arc<HashMap<ModelTask, PathBuf>>,
+    models: Arc<Mutex<LoadedModels<hypr_llama::Llama>>>,
     last_activity: Arc<Mutex<Option<tokio::time::Instant>>>,
     _drop_guard: Arc<DropGuard>,
 }
@@ -20,12 +27,12 @@ impl Drop for DropGuard {
 }
 
 impl ModelManager {
-    pub fn new(model_path: impl Into<std::path::PathBuf>) -> Self {
+    pub fn new(model_paths: HashMap<ModelTask, PathBuf>, memory_budget: u64) -> Self {
         let (shutdown_tx, shutdown_rx) = watch::channel(());
 
         let manager = Self {
-            model_path: model_path.into(),
-            model: Arc::new(tokio::sync::Mutex::new(None)),
+            model_paths: Arc::new(model_paths),
+            models: Arc::new(Mutex::new(LoadedModels::new(memory_budget))),
             last_activity: Arc::new(tokio::sync::Mutex::new(None)),
             _drop_guard: Arc::new(DropGuard { shutdown_tx }),
         };
@@ -38,30 +45,40 @@ impl ModelManager {
         *self.last_activity.lock().await = Some(tokio::time::Instant::now());
     }
 
-    pub async fn get_model(&self) -> Result<std::sync::Arc<hypr_llama::Llama>, crate::Error> {
+    pub async fn get_model(
+        &self,
+        task: ModelTask,
+    ) -> Result<std::sync::Arc<hypr_llama::Llama>, crate::Error> {
         self.update_activity().await;
 
-        let mut guard = self.model.lock().await;
+        let model_path = self
+            .model_paths
+            .get(&task)
+            .ok_or(crate::Error::ModelNotDownloaded)?;
 
-        match guard.as_ref() {
-            Some(model) => Ok(model.clone()),
-            None => {
-                if !self.model_path.exists() {
-                    return Err(crate::Error::ModelNotDownloaded);
-                }
+        let mut guard = self.models.lock().await;
 
-                let model = Arc::new(hypr_llama::Llama::new(&self.model_path)?);
-                *guard = Some(model.clone());
-                Ok(model)
-            }
+        if let Some(model) = guard.get(model_path) {
+            return Ok(model);
+        }
+
+        if !model_path.exists() {
+            return Err(crate::Error::ModelNotDownloaded);
         }
+
+        let size = std::fs::metadata(model_path)?.len();
+        guard.make_room(size);
+
+        let model = Arc::new(hypr_llama::Llama::new(model_path)?);
+        guard.insert(model_path.clone(), size, model.clone());
+        Ok(model)
     }
 
     fn monitor(&self, shutdown_rx: watch::Receiver<()>) {
         let activity_check_interval = std::time::Duration::from_secs(3);
         let inactivity_threshold = std::time::Duration::from_secs(150);
 
-        let model = self.model.clone();
+        let models = self.models.clone();
         let last_activity = self.last_activity.clone();
 
         let _handle = tokio::spawn(async move {
@@ -78,13 +95,13 @@ impl ModelManager {
                     _ = interval.tick() => {
                         let should_unload = match *last_activity.lock().await {
                             Some(last_time) if last_time.elapsed() > inactivity_threshold => {
-                                model.lock().await.is_some()
+                                !models.lock().await.is_empty()
                             },
                             _ => false
                         };
 
                         if should_unload {
-                            *model.lock().await = None;
+                            models.lock().await.clear();
                         }
                     }
                 }
@@ -92,3 +109,85 @@ impl ModelManager {
         });
     }
 }
+
+/// Loaded models, least recently used first.
+struct LoadedModels<T> {
+    memory_budget: u64,
+    entries: Vec<(PathBuf, u64, Arc<T>)>,
+}
+
+impl<T> LoadedModels<T> {
+    fn new(memory_budget: u64) -> Self {
+        Self {
+            memory_budget,
+            entries: Vec::new(),
+        }
+    }
+
+    fn get(&mut self, path: &Path) -> Option<Arc<T>> {
+        let index = self.entries.iter().position(|(p, _, _)| p == path)?;
+        let entry = self.entries.remove(index);
+        let model = entry.2.clone();
+        self.entries.push(entry);
+        Some(model)
+    }
+
+    /// Unloads the least recently used models until one of `size` fits.
+    /// A model larger than the whole budget is still loaded, on its own.
+    fn make_room(&mut self, size: u64) {
+        while !self.entries.is_empty() && self.used() + size > self.memory_budget {
+            let (path, _, _) = self.entries.remove(0);
+            tracing::info!(path = %path.display(), "unloading_model");
+        }
+    }
+
+    fn insert(&mut self, path: PathBuf, size: u64, model: Arc<T>) {
+        self.entries.push((path, size, model));
+    }
+
+    fn used(&self) -> u64 {
+        self.entries.iter().map(|(_, size, _)| size).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(models: &mut LoadedModels<&'static str>, name: &'static str, size: u64) {
+        if models.get(Path::new(name)).is_none() {
+            models.make_room(size);
+            models.insert(PathBuf::from(name), size, Arc::new(name));
+        }
+    }
+
+    fn loaded(models: &LoadedModels<&'static str>) -> Vec<&'static str> {
+        models.entries.iter().map(|(_, _, m)| **m).collect()
+    }
+
+    #[test]
+    fn test_loaded_models_lru() {
+        let mut models = LoadedModels::new(10);
+
+        load(&mut models, "enhance", 4);
+        load(&mut models, "title", 2);
+        load(&mut models, "chat", 4);
+        assert_eq!(loaded(&models), vec!["enhance", "title", "chat"]);
+
+        // Used again, so `title` is the least recently used now.
+        load(&mut models, "enhance", 4);
+        load(&mut models, "other", 2);
+        assert_eq!(loaded(&models), vec!["chat", "enhance", "other"]);
+
+        load(&mut models, "huge", 20);
+        assert_eq!(loaded(&models), vec!["huge"]);
+    }
+}
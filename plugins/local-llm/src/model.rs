@@ -37,3 +37,45 @@ pub enum ModelIdentifier {
     #[serde(rename = "mock-onboarding")]
     MockOnboarding,
 }
+
+/// A GGUF file registered by the user, with what we read from its metadata.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, specta::Type)]
+pub struct CustomModel {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    pub architecture: Option<String>,
+    pub context_length: Option<u32>,
+    pub quantization: Option<String>,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, specta::Type)]
+#[serde(tag = "type", content = "value")]
+pub enum ModelSelection {
+    #[serde(rename = "supported")]
+    Supported(SupportedModel),
+    #[serde(rename = "custom")]
+    Custom(String),
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    specta::Type,
+    strum::EnumIter,
+)]
+pub enum ModelTask {
+    #[serde(rename = "enhance")]
+    Enhance,
+    #[serde(rename = "title")]
+    Title,
+    #[serde(rename = "chat")]
+    Chat,
+}
@@ -3,10 +3,11 @@ use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 
 use async_openai::types::{
-    ChatChoice, ChatChoiceStream, ChatCompletionMessageToolCallChunk,
-    ChatCompletionResponseMessage, ChatCompletionStreamResponseDelta, ChatCompletionToolType,
+    ChatChoice, ChatChoiceStream, ChatCompletionMessageToolCall,
+    ChatCompletionMessageToolCallChunk, ChatCompletionResponseMessage,
+    ChatCompletionStreamResponseDelta, ChatCompletionToolChoiceOption, ChatCompletionToolType,
     CreateChatCompletionRequest, CreateChatCompletionResponse, CreateChatCompletionStreamResponse,
-    FunctionCallStream, Role,
+    FinishReason, FunctionCall, FunctionCallStream, ResponseFormat, Role, Stop,
 };
 use axum::{
     extract::State as AxumState,
@@ -24,6 +25,9 @@ use tower_http::cors::{self, CorsLayer};
 
 use crate::ModelManager;
 
+// Clients declare this tool to receive prefill progress. It is not offered to the model.
+const PROGRESS_TOOL: &str = "update_progress";
+
 #[derive(Clone)]
 pub struct ServerHandle {
     pub addr: SocketAddr,
@@ -105,7 +109,11 @@ pub async fn run_server(state: ServerState) -> Result<ServerHandle, crate::Error
 }
 
 async fn health(AxumState(state): AxumState<ServerState>) -> impl IntoResponse {
-    match state.model_manager.get_model().await {
+    match state
+        .model_manager
+        .get_model(crate::ModelTask::Enhance)
+        .await
+    {
         Ok(_) => (StatusCode::OK, "OK".to_string()),
         Err(e) => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()),
     }
@@ -121,7 +129,7 @@ async fn cancel(AxumState(state): AxumState<ServerState>) -> impl IntoResponse {
 async fn chat_completions(
     AxumState(state): AxumState<ServerState>,
     Json(request): Json<CreateChatCompletionRequest>,
-) -> Result<Response, (StatusCode, String)> {
+) -> Result<Response, Response> {
     let response = if request.model == "mock-onboarding" {
         let provider = MockProvider::default();
         tracing::info!("using_mock_provider");
@@ -132,9 +140,66 @@ async fn chat_completions(
         provider.chat_completions(request, &state).await
     };
 
-    response
-        .map(|r| r.into_response())
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+    response.map(|r| r.into_response()).map_err(|e| match e {
+        crate::Error::HyprLlamaError(hypr_llama::Error::ContextLengthExceeded { .. }) => {
+            openai_error(
+                StatusCode::BAD_REQUEST,
+                "invalid_request_error",
+                "messages",
+                "context_length_exceeded",
+                e.to_string(),
+            )
+        }
+        crate::Error::HyprLlamaError(hypr_llama::Error::MaxTokensExceeded { .. }) => openai_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_error",
+            "max_tokens",
+            "max_tokens_exceeded",
+            e.to_string(),
+        ),
+        crate::Error::HyprLlamaError(hypr_llama::Error::GbnfError(_)) => openai_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_error",
+            "tools",
+            "invalid_function_parameters",
+            e.to_string(),
+        ),
+        crate::Error::InvalidSeed(_) => openai_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_error",
+            "seed",
+            "invalid_value",
+            e.to_string(),
+        ),
+        crate::Error::HyprGbnfError(_) => openai_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_error",
+            "response_format",
+            "invalid_json_schema",
+            e.to_string(),
+        ),
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    })
+}
+
+// https://platform.openai.com/docs/guides/error-codes
+fn openai_error(
+    status: StatusCode,
+    r#type: &str,
+    param: &str,
+    code: &str,
+    message: impl Into<String>,
+) -> Response {
+    let body = serde_json::json!({
+        "error": {
+            "message": message.into(),
+            "type": r#type,
+            "param": param,
+            "code": code,
+        }
+    });
+
+    (status, Json(body)).into_response()
 }
 
 struct LocalProvider {
@@ -151,8 +216,9 @@ impl LocalProvider {
         request: CreateChatCompletionRequest,
         state: &ServerState,
     ) -> Result<ChatCompletionResponse, crate::Error> {
-        let model = self.model_manager.get_model().await?;
-        tracing::info!("loaded_model: {:?}", model.name);
+        let task = Self::task(&request);
+        let model = self.model_manager.get_model(task).await?;
+        tracing::info!("loaded_model: {:?} ({:?})", model.name, task);
 
         build_chat_completion_response(&request, || {
             let (stream, token) = Self::build_stream(&model, &request)?;
@@ -162,6 +228,24 @@ impl LocalProvider {
         .await
     }
 
+    // Requests without `metadata.task` are chat.
+    fn task(request: &CreateChatCompletionRequest) -> crate::ModelTask {
+        request
+            .metadata
+            .as_ref()
+            .and_then(|v| v.get("task"))
+            .and_then(|v| serde_json::from_value::<crate::ModelTask>(v.clone()).ok())
+            .unwrap_or(crate::ModelTask::Chat)
+    }
+
+    fn grammar(request: &CreateChatCompletionRequest) -> Option<hypr_gbnf::Grammar> {
+        request
+            .metadata
+            .as_ref()
+            .and_then(|v| v.get("grammar"))
+            .and_then(|v| serde_json::from_value::<hypr_gbnf::Grammar>(v.clone()).ok())
+    }
+
     fn build_stream(
         model: &hypr_llama::Llama,
         request: &CreateChatCompletionRequest,
@@ -178,11 +262,7 @@ impl LocalProvider {
             .map(hypr_llama::FromOpenAI::from_openai)
             .collect();
 
-        let maybe_grammar = request
-            .metadata
-            .as_ref()
-            .and_then(|v| v.get("grammar"))
-            .and_then(|v| serde_json::from_value::<hypr_gbnf::Grammar>(v.clone()).ok());
+        let maybe_grammar = Self::grammar(request);
 
         // TODO: this is temporary hack to disable grammar for hypr-llm
         let grammar = match maybe_grammar {
@@ -199,7 +279,52 @@ impl LocalProvider {
             }
         };
 
-        let request = hypr_llama::LlamaRequest { messages, grammar };
+        // Takes precedence over `metadata.grammar`, since it is what OpenAI clients send.
+        let grammar = match &request.response_format {
+            Some(ResponseFormat::JsonObject) => Some(hypr_gbnf::json_object()),
+            Some(ResponseFormat::JsonSchema { json_schema }) => match &json_schema.schema {
+                Some(schema) => Some(hypr_gbnf::from_json_schema(schema)?),
+                None => Some(hypr_gbnf::json_object()),
+            },
+            Some(ResponseFormat::Text) | None => grammar,
+        };
+
+        let (tools, tool_choice) = Self::tools(request);
+        let wants_progress = request
+            .tools
+            .iter()
+            .flatten()
+            .any(|tool| tool.function.name == PROGRESS_TOOL);
+
+        let stop = match &request.stop {
+            Some(Stop::String(s)) => vec![s.clone()],
+            Some(Stop::StringArray(v)) => v.clone(),
+            None => vec![],
+        };
+
+        let seed = request
+            .seed
+            .map(|seed| u32::try_from(seed).map_err(|_| crate::Error::InvalidSeed(seed)))
+            .transpose()?;
+
+        #[allow(deprecated)]
+        let request = hypr_llama::LlamaRequest {
+            messages,
+            grammar,
+            max_output_tokens: request.max_completion_tokens.or(request.max_tokens),
+            sampling: hypr_llama::SamplingParams {
+                temperature: request.temperature,
+                top_p: request.top_p,
+                frequency_penalty: request.frequency_penalty,
+                presence_penalty: request.presence_penalty,
+                seed,
+            },
+            stop,
+            tools,
+            tool_choice,
+            ..Default::default()
+        };
+        let has_tools = !request.tools.is_empty();
 
         let (progress_sender, mut progress_receiver) = mpsc::unbounded_channel::<f64>();
 
@@ -213,26 +338,82 @@ impl LocalProvider {
         let mixed_stream = async_stream::stream! {
             tokio::pin!(content_stream);
 
+            // With tools, output is held back until it is clear it isn't a call. Calls are only parsed once complete.
+            let mut buffered = String::new();
+            let mut streaming = !has_tools;
+
             loop {
                 tokio::select! {
                     content_result = content_stream.next() => {
                         match content_result {
-                            Some(content) => yield StreamEvent::Content(content),
+                            Some(content) if streaming => yield StreamEvent::Content(content),
+                            Some(content) => {
+                                buffered.push_str(&content);
+                                if hypr_llama::starts_tool_call(&buffered) == Some(false) {
+                                    streaming = true;
+                                    yield StreamEvent::Content(std::mem::take(&mut buffered));
+                                }
+                            }
                             None => break,
                         }
                     },
                     progress_result = progress_receiver.recv() => {
                         match progress_result {
-                            Some(progress) => yield StreamEvent::Progress(progress),
-                            None => {}
+                            Some(progress) if wants_progress => yield StreamEvent::Progress(progress),
+                            _ => {}
                         }
                     }
                 }
             }
+
+            if !buffered.is_empty() {
+                let (content, calls) = hypr_llama::parse_tool_calls(&buffered);
+                if !content.is_empty() {
+                    yield StreamEvent::Content(content);
+                }
+                if !calls.is_empty() {
+                    yield StreamEvent::ToolCalls(calls);
+                }
+            }
         };
 
         Ok((Box::pin(mixed_stream), cancellation_token))
     }
+
+    fn tools(
+        request: &CreateChatCompletionRequest,
+    ) -> (Vec<hypr_llama::Tool>, hypr_llama::ToolChoice) {
+        let tools = request
+            .tools
+            .iter()
+            .flatten()
+            .filter(|tool| tool.function.name != PROGRESS_TOOL)
+            .map(|tool| hypr_llama::Tool {
+                name: tool.function.name.clone(),
+                description: tool.function.description.clone(),
+                parameters: tool
+                    .function
+                    .parameters
+                    .clone()
+                    .unwrap_or_else(|| serde_json::json!({ "type": "object" })),
+            });
+
+        match &request.tool_choice {
+            Some(ChatCompletionToolChoiceOption::None) => (vec![], hypr_llama::ToolChoice::Auto),
+            Some(ChatCompletionToolChoiceOption::Named(named)) => (
+                tools
+                    .filter(|tool| tool.name == named.function.name)
+                    .collect(),
+                hypr_llama::ToolChoice::Required,
+            ),
+            Some(ChatCompletionToolChoiceOption::Required) => {
+                (tools.collect(), hypr_llama::ToolChoice::Required)
+            }
+            Some(ChatCompletionToolChoiceOption::Auto) | None => {
+                (tools.collect(), hypr_llama::ToolChoice::Auto)
+            }
+        }
+    }
 }
 
 #[derive(Default)]
@@ -286,6 +467,11 @@ impl MockProvider {
 enum StreamEvent {
     Content(String),
     Progress(f64),
+    ToolCalls(Vec<hypr_llama::FunctionCall>),
+}
+
+fn tool_call_id() -> String {
+    format!("call_{}", uuid::Uuid::new_v4().simple())
 }
 
 async fn build_chat_completion_response(
@@ -355,20 +541,46 @@ async fn build_chat_completion_response(
     if !is_stream {
         let mut stream = response_stream_fn()?;
         let mut completion = String::new();
+        let mut tool_calls = Vec::new();
 
         while let Some(event) = futures_util::StreamExt::next(&mut stream).await {
             match event {
                 StreamEvent::Content(chunk) => completion.push_str(&chunk),
                 StreamEvent::Progress(_) => {}
+                StreamEvent::ToolCalls(calls) => tool_calls.extend(calls),
             }
         }
 
+        let (content, tool_calls, finish_reason) = if tool_calls.is_empty() {
+            (Some(completion), None, FinishReason::Stop)
+        } else {
+            let tool_calls = tool_calls
+                .into_iter()
+                .map(|call| ChatCompletionMessageToolCall {
+                    id: tool_call_id(),
+                    r#type: ChatCompletionToolType::Function,
+                    function: FunctionCall {
+                        name: call.name,
+                        arguments: call.arguments,
+                    },
+                })
+                .collect();
+
+            (
+                Some(completion).filter(|c| !c.is_empty()),
+                Some(tool_calls),
+                FinishReason::ToolCalls,
+            )
+        };
+
         let res = CreateChatCompletionResponse {
             choices: vec![ChatChoice {
                 message: ChatCompletionResponseMessage {
-                    content: Some(completion),
+                    content,
+                    tool_calls,
                     ..empty_message
                 },
+                finish_reason: Some(finish_reason),
                 ..empty_choice
             }],
             ..base_response_template
@@ -419,6 +631,33 @@ async fn build_chat_completion_response(
                     }],
                     ..response_template
                 },
+                StreamEvent::ToolCalls(calls) => CreateChatCompletionStreamResponse {
+                    choices: vec![ChatChoiceStream {
+                        index: 0,
+                        delta: ChatCompletionStreamResponseDelta {
+                            tool_calls: Some(
+                                calls
+                                    .into_iter()
+                                    .enumerate()
+                                    // Offset so they don't collide with the progress updates sent before.
+                                    .map(|(i, call)| ChatCompletionMessageToolCallChunk {
+                                        index: (index + i).try_into().unwrap_or(0),
+                                        id: Some(tool_call_id()),
+                                        r#type: Some(ChatCompletionToolType::Function),
+                                        function: Some(FunctionCallStream {
+                                            name: Some(call.name),
+                                            arguments: Some(call.arguments),
+                                        }),
+                                    })
+                                    .collect(),
+                            ),
+                            ..delta_template
+                        },
+                        finish_reason: Some(FinishReason::ToolCalls),
+                        logprobs: None,
+                    }],
+                    ..response_template
+                },
             };
 
             Ok(response)
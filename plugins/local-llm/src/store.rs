@@ -7,6 +7,12 @@ pub enum StoreKey {
     DefaultModelMigrated,
     /// Track the last app version that ran migrations
     LastMigrationVersion,
+    /// GGUF files registered by the user
+    CustomModels,
+    /// Model used for each `ModelTask`, when not the current one
+    TaskModels,
+    /// Whether chat looks up related past sessions, which needs the embedding model
+    RetrievalEnabled,
 }
 
 impl ScopedStoreKey for StoreKey {}
@@ -14,6 +14,8 @@ pub enum Error {
     IoError(#[from] std::io::Error),
     #[error(transparent)]
     StoreError(#[from] tauri_plugin_store2::Error),
+    #[error(transparent)]
+    TranscribeError(#[from] hypr_transcribe_whisper_local::Error),
     #[error("Model not downloaded")]
     ModelNotDownloaded,
 }
@@ -1,9 +1,11 @@
-use std::{future::Future, path::PathBuf};
+use std::{future::Future, ops::Range, path::PathBuf};
 
 use tauri::{ipc::Channel, Manager, Runtime};
 use tauri_plugin_shell::ShellExt;
 use tauri_plugin_store2::StorePluginExt;
 
+use tauri_specta::Event;
+
 use hypr_file::{download_file_parallel, DownloadProgress};
 use hypr_whisper_local_model::WhisperModel;
 
@@ -30,6 +32,12 @@ pub trait LocalSttPluginExt<R: Runtime> {
         channel: Channel<i8>,
     ) -> impl Future<Output = Result<(), crate::Error>>;
 
+    fn process_recorded(
+        &self,
+        audio: impl rodio::Source<Item = f32>,
+        spans: Vec<Range<u64>>,
+    ) -> Result<Vec<owhisper_interface::Word2>, crate::Error>;
+
     fn is_model_downloading(&self, model: &WhisperModel) -> impl Future<Output = bool>;
     fn is_model_downloaded(
         &self,
@@ -209,6 +217,47 @@ impl<R: Runtime, T: Manager<R>> LocalSttPluginExt<R> for T {
         Ok(())
     }
 
+    #[tracing::instrument(skip_all)]
+    fn process_recorded(
+        &self,
+        audio: impl rodio::Source<Item = f32>,
+        spans: Vec<Range<u64>>,
+    ) -> Result<Vec<owhisper_interface::Word2>, crate::Error> {
+        let model = self.get_current_model()?;
+        let model_path = self.models_dir().join(model.file_name());
+
+        if !model_path.exists() {
+            return Err(crate::Error::ModelNotDownloaded);
+        }
+
+        let app = self.app_handle().clone();
+
+        // Progress is reported in milliseconds of `spans` processed so far.
+        let covered = |until_ms: u64| -> usize {
+            spans
+                .iter()
+                .map(|s| until_ms.clamp(s.start, s.end.max(s.start)) - s.start)
+                .sum::<u64>() as usize
+        };
+        let total = covered(u64::MAX);
+
+        let words = hypr_transcribe_whisper_local::process_recorded_source(
+            model_path,
+            audio,
+            &spans,
+            |word| {
+                let _ = crate::events::RecordedProcessingEvent::Progress {
+                    current: covered(word.end_ms.unwrap_or_default()),
+                    total,
+                    word: word.clone(),
+                }
+                .emit(&app);
+            },
+        )?;
+
+        Ok(words)
+    }
+
     #[tracing::instrument(skip_all)]
     async fn is_model_downloading(&self, model: &WhisperModel) -> bool {
         let state = self.state::<crate::SharedState>();
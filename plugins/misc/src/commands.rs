@@ -33,10 +33,7 @@ pub async fn audio_exist<R: tauri::Runtime>(
     session_id: String,
 ) -> Result<bool, String> {
     let data_dir = app.path().app_data_dir().unwrap();
-    let audio_path = data_dir.join(session_id).join("audio.wav");
-
-    let v = std::fs::exists(audio_path).map_err(|e| e.to_string())?;
-    Ok(v)
+    Ok(hypr_recorder::exists(data_dir.join(session_id)))
 }
 
 #[tauri::command]
@@ -46,9 +43,7 @@ pub async fn audio_delete<R: tauri::Runtime>(
     session_id: String,
 ) -> Result<(), String> {
     let data_dir = app.path().app_data_dir().unwrap();
-    let audio_path = data_dir.join(session_id).join("audio.wav");
-
-    std::fs::remove_file(audio_path).map_err(|e| e.to_string())?;
+    hypr_recorder::remove(data_dir.join(session_id)).map_err(|e| e.to_string())?;
     Ok(())
 }
 
@@ -59,10 +54,11 @@ pub async fn audio_open<R: tauri::Runtime>(
     session_id: String,
 ) -> Result<(), String> {
     let data_dir = app.path().app_data_dir().unwrap();
-    let audio_path = data_dir.join(session_id).join("audio.wav");
+    let files = hypr_recorder::list_files(data_dir.join(session_id)).map_err(|e| e.to_string())?;
+    let audio_path = files.first().ok_or("no recording")?;
 
     app.opener()
-        .reveal_item_in_dir(&audio_path)
+        .reveal_item_in_dir(audio_path)
         .map_err(|e| e.to_string())?;
 
     Ok(())
@@ -1,4 +1,13 @@
-const COMMANDS: &[&str] = &["render", "register_template"];
+const COMMANDS: &[&str] = &[
+    "render",
+    "register_template",
+    "split_timeline",
+    "locate_in_timeline",
+    "list_predefined_templates",
+    "get_template_source",
+    "override_template",
+    "reset_template",
+];
 
 fn main() {
     tauri_plugin::Builder::new(COMMANDS).build();
@@ -12,6 +12,24 @@ async render(name: string, ctx: Partial<{ [key in string]: JsonValue }>) : Promi
 },
 async registerTemplate(name: string, template: string) : Promise<null> {
     return await TAURI_INVOKE("plugin:template|register_template", { name, template });
+},
+async splitTimeline(words: Word2[], maxTokens: number) : Promise<Word2[][]> {
+    return await TAURI_INVOKE("plugin:template|split_timeline", { words, maxTokens });
+},
+async locateInTimeline(words: Word2[], quote: string) : Promise<[number, number] | null> {
+    return await TAURI_INVOKE("plugin:template|locate_in_timeline", { words, quote });
+},
+async listPredefinedTemplates() : Promise<PredefinedTemplateInfo[]> {
+    return await TAURI_INVOKE("plugin:template|list_predefined_templates");
+},
+async getTemplateSource(name: string) : Promise<string> {
+    return await TAURI_INVOKE("plugin:template|get_template_source", { name });
+},
+async overrideTemplate(name: string, template: string) : Promise<null> {
+    return await TAURI_INVOKE("plugin:template|override_template", { name, template });
+},
+async resetTemplate(name: string) : Promise<null> {
+    return await TAURI_INVOKE("plugin:template|reset_template", { name });
 }
 }
 
@@ -27,6 +45,9 @@ async registerTemplate(name: string, template: string) : Promise<null> {
 
 export type Grammar = { task: "enhance"; sections: string[] | null } | { task: "title" } | { task: "tags" }
 export type JsonValue = null | boolean | number | string | JsonValue[] | Partial<{ [key in string]: JsonValue }>
+export type PredefinedTemplateInfo = { name: string; overridden: boolean }
+export type SpeakerIdentity = { type: "unassigned"; value: { index: number } } | { type: "assigned"; value: { id: string; label: string } }
+export type Word2 = { text: string; speaker: SpeakerIdentity | null; confidence: number | null; start_ms: number | null; end_ms: number | null }
 
 /** tauri-specta globals **/
 
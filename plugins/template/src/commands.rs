@@ -19,3 +19,61 @@ pub async fn register_template<R: tauri::Runtime>(
 ) -> Result<(), String> {
     app.register_template(name, template)
 }
+
+#[tauri::command]
+#[specta::specta]
+pub async fn split_timeline<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    words: Vec<owhisper_interface::Word2>,
+    max_tokens: u32,
+) -> Result<Vec<Vec<owhisper_interface::Word2>>, String> {
+    Ok(app.split_timeline(&words, max_tokens as usize))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn locate_in_timeline<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    words: Vec<owhisper_interface::Word2>,
+    quote: String,
+) -> Result<Option<(u32, u32)>, String> {
+    Ok(app
+        .locate_in_timeline(&words, &quote)
+        .map(|(start, end)| (start as u32, end as u32)))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_predefined_templates<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+) -> Result<Vec<crate::PredefinedTemplateInfo>, String> {
+    app.list_predefined_templates()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_template_source<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    name: String,
+) -> Result<String, String> {
+    app.get_template_source(name)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn override_template<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    name: String,
+    template: String,
+) -> Result<(), String> {
+    app.override_template(name, template)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn reset_template<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    name: String,
+) -> Result<(), String> {
+    app.reset_template(name)
+}
@@ -1,3 +1,14 @@
+use std::path::PathBuf;
+
+use hypr_template::PredefinedTemplate;
+use strum::IntoEnumIterator;
+
+#[derive(serde::Serialize, specta::Type)]
+pub struct PredefinedTemplateInfo {
+    pub name: String,
+    pub overridden: bool,
+}
+
 pub trait TemplatePluginExt<R: tauri::Runtime> {
     fn render(
         &self,
@@ -9,6 +20,36 @@ pub trait TemplatePluginExt<R: tauri::Runtime> {
         name: impl Into<String>,
         template: impl Into<String>,
     ) -> Result<(), String>;
+    fn split_timeline(
+        &self,
+        words: &[owhisper_interface::Word2],
+        max_tokens: usize,
+    ) -> Vec<Vec<owhisper_interface::Word2>>;
+    fn locate_in_timeline(
+        &self,
+        words: &[owhisper_interface::Word2],
+        quote: &str,
+    ) -> Option<(usize, usize)>;
+
+    fn template_overrides_dir(&self) -> Result<PathBuf, String>;
+    fn load_template_overrides(&self) -> Result<(), String>;
+    fn list_predefined_templates(&self) -> Result<Vec<PredefinedTemplateInfo>, String>;
+    fn get_template_source(&self, name: impl AsRef<str>) -> Result<String, String>;
+    fn override_template(
+        &self,
+        name: impl AsRef<str>,
+        template: impl Into<String>,
+    ) -> Result<(), String>;
+    fn reset_template(&self, name: impl AsRef<str>) -> Result<(), String>;
+}
+
+fn parse_predefined(name: &str) -> Result<PredefinedTemplate, String> {
+    name.parse::<PredefinedTemplate>()
+        .map_err(|_| format!("unknown template: {}", name))
+}
+
+fn override_path(dir: &std::path::Path, template: PredefinedTemplate) -> PathBuf {
+    dir.join(format!("{}.jinja", template))
 }
 
 impl<R: tauri::Runtime, T: tauri::Manager<R>> crate::TemplatePluginExt<R> for T {
@@ -45,4 +86,118 @@ impl<R: tauri::Runtime, T: tauri::Manager<R>> crate::TemplatePluginExt<R> for T
                 .map_err(|e| e.to_string())
         }
     }
+
+    #[tracing::instrument(skip_all)]
+    fn split_timeline(
+        &self,
+        words: &[owhisper_interface::Word2],
+        max_tokens: usize,
+    ) -> Vec<Vec<owhisper_interface::Word2>> {
+        hypr_template::timeline::split(words, max_tokens)
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn locate_in_timeline(
+        &self,
+        words: &[owhisper_interface::Word2],
+        quote: &str,
+    ) -> Option<(usize, usize)> {
+        hypr_template::timeline::locate(words, quote)
+    }
+
+    fn template_overrides_dir(&self) -> Result<PathBuf, String> {
+        let dir = self
+            .path()
+            .app_data_dir()
+            .map_err(|e| e.to_string())?
+            .join("templates");
+        Ok(dir)
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn load_template_overrides(&self) -> Result<(), String> {
+        let dir = self.template_overrides_dir()?;
+        let state = self.state::<crate::ManagedState>();
+        let mut guard = state.lock().unwrap();
+
+        for template in PredefinedTemplate::iter() {
+            let Ok(source) = std::fs::read_to_string(override_path(&dir, template)) else {
+                continue;
+            };
+
+            // A broken override would break every feature using it, so the bundled one is kept.
+            if let Err(e) = hypr_template::validate(&source) {
+                tracing::warn!(template = %template, error = %e, "invalid_template_override");
+                continue;
+            }
+
+            guard
+                .env
+                .add_template_owned(template.to_string(), source)
+                .map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    fn list_predefined_templates(&self) -> Result<Vec<PredefinedTemplateInfo>, String> {
+        let dir = self.template_overrides_dir()?;
+
+        Ok(PredefinedTemplate::iter()
+            .map(|template| PredefinedTemplateInfo {
+                name: template.to_string(),
+                overridden: override_path(&dir, template).exists(),
+            })
+            .collect())
+    }
+
+    fn get_template_source(&self, name: impl AsRef<str>) -> Result<String, String> {
+        let template = parse_predefined(name.as_ref())?;
+        let path = override_path(&self.template_overrides_dir()?, template);
+
+        match std::fs::read_to_string(path) {
+            Ok(source) => Ok(source),
+            Err(_) => Ok(template.source().to_string()),
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn override_template(
+        &self,
+        name: impl AsRef<str>,
+        template: impl Into<String>,
+    ) -> Result<(), String> {
+        let predefined = parse_predefined(name.as_ref())?;
+        let source = template.into();
+        hypr_template::validate(&source).map_err(|e| e.to_string())?;
+
+        let dir = self.template_overrides_dir()?;
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        std::fs::write(override_path(&dir, predefined), &source).map_err(|e| e.to_string())?;
+
+        let state = self.state::<crate::ManagedState>();
+        let mut guard = state.lock().unwrap();
+        guard
+            .env
+            .add_template_owned(predefined.to_string(), source)
+            .map_err(|e| e.to_string())
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn reset_template(&self, name: impl AsRef<str>) -> Result<(), String> {
+        let predefined = parse_predefined(name.as_ref())?;
+        let path = override_path(&self.template_overrides_dir()?, predefined);
+
+        if path.exists() {
+            std::fs::remove_file(path).map_err(|e| e.to_string())?;
+        }
+
+        let name: &'static str = predefined.into();
+        let state = self.state::<crate::ManagedState>();
+        let mut guard = state.lock().unwrap();
+        guard
+            .env
+            .add_template(name, predefined.source())
+            .map_err(|e| e.to_string())
+    }
 }
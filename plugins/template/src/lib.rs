@@ -4,7 +4,7 @@ use tauri::{Manager, Wry};
 mod commands;
 mod ext;
 
-pub use ext::TemplatePluginExt;
+pub use ext::{PredefinedTemplateInfo, TemplatePluginExt};
 
 const PLUGIN_NAME: &str = "template";
 
@@ -28,6 +28,12 @@ fn make_specta_builder<R: tauri::Runtime>() -> tauri_specta::Builder<R> {
         .commands(tauri_specta::collect_commands![
             commands::render::<Wry>,
             commands::register_template::<Wry>,
+            commands::split_timeline::<Wry>,
+            commands::locate_in_timeline::<Wry>,
+            commands::list_predefined_templates::<Wry>,
+            commands::get_template_source::<Wry>,
+            commands::override_template::<Wry>,
+            commands::reset_template::<Wry>,
         ])
         .typ::<hypr_gbnf::Grammar>()
         .error_handling(tauri_specta::ErrorHandlingMode::Throw)
@@ -42,6 +48,11 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
             let mut state = State::default();
             hypr_template::init(&mut state.env);
             app.manage(Mutex::new(state));
+
+            if let Err(e) = app.load_template_overrides() {
+                tracing::error!(error = %e, "failed_to_load_template_overrides");
+            }
+
             Ok(())
         })
         .build()